@@ -0,0 +1,272 @@
+//! Measures the compact tile layer storage through the engine's own code
+//! paths, next to a model of the previous layout, where every placed tile
+//! carried a clone of its `TileProperties`.
+//!
+//! Tilesets and the renderer need OpenGL, so the bench opens a hidden
+//! window for its context. Run with `cargo bench --bench tile_storage`.
+
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::mem::size_of;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use glam::Mat4;
+use glutin::event_loop::EventLoop;
+use glutin::window::WindowBuilder;
+use glutin::ContextBuilder;
+use teengine::tile::{
+    PropertyValue, TileData, TileInstance, TileLayer, TileMap, TileMapRenderer,
+    TileProperties, Tileset, TilesetData,
+};
+
+const MAP_SIZE: u32 = 1024;
+const TILE_SIZE: u32 = 32;
+/// Tile kinds in an 8 by 8 tileset image.
+const TILE_KINDS: u32 = 64;
+/// Every this many tiles has its own properties override.
+const OVERRIDE_EVERY: u32 = 97;
+const ROUNDS: u32 = 10;
+
+/// Tile as layers stored it before: ID plus its own copy of the
+/// properties.
+#[derive(Clone)]
+struct ClonedTile {
+    // Only counted towards the layout's size
+    #[allow(dead_code)]
+    id: u32,
+    properties: TileProperties,
+}
+
+fn main() {
+    let event_loop = EventLoop::new();
+    let context = ContextBuilder::new()
+        .with_gl(glutin::GlRequest::Specific(glutin::Api::OpenGl, (3, 3)))
+        .with_gl_profile(glutin::GlProfile::Core)
+        .build_windowed(WindowBuilder::new().with_visible(false), &event_loop)
+        .expect("Failed to create OpenGL context");
+    let context = unsafe {
+        context
+            .make_current()
+            .expect("Failed to make OpenGL context current")
+    };
+    gl::load_with(|symbol| context.get_proc_address(symbol) as *const _);
+
+    let properties: Vec<TileProperties> =
+        (0..TILE_KINDS).map(tile_properties).collect();
+    let tileset_path = write_tileset(&properties);
+    let tileset = Tileset::new(&tileset_path).expect("Failed to load tileset");
+    let id_at = |x: u32, y: u32| (x * 7 + y * 13) % TILE_KINDS;
+
+    let mut layer = TileLayer::new(MAP_SIZE, MAP_SIZE);
+    let mut cloned: Vec<Vec<Option<ClonedTile>>> =
+        vec![vec![None; MAP_SIZE as usize]; MAP_SIZE as usize];
+    for y in 0..MAP_SIZE {
+        for x in 0..MAP_SIZE {
+            let id = id_at(x, y);
+            layer.set_tile(x, y, TileInstance::new(id));
+            cloned[y as usize][x as usize] = Some(ClonedTile {
+                id,
+                properties: properties[id as usize].clone(),
+            });
+            if (y * MAP_SIZE + x).is_multiple_of(OVERRIDE_EVERY) {
+                layer.set_properties_override(
+                    x,
+                    y,
+                    properties[id as usize].clone().with_collision(),
+                );
+            }
+        }
+    }
+    let mut tilemap = TileMap::new(MAP_SIZE, MAP_SIZE, TILE_SIZE, 1.0, tileset);
+    tilemap.add_layer("ground".to_string(), layer);
+
+    let cells = (MAP_SIZE * MAP_SIZE) as usize;
+    println!("{MAP_SIZE}x{MAP_SIZE} tiles, {TILE_KINDS} tile kinds");
+    println!();
+    println!("memory");
+    let compact_bytes = cells * size_of::<Option<TileInstance>>();
+    let cloned_bytes = cells
+        * (size_of::<Option<ClonedTile>>()
+            + custom_properties_heap(&properties));
+    report_bytes("  compact", compact_bytes);
+    report_bytes("  cloned properties", cloned_bytes);
+    println!(
+        "  {:.1}x smaller",
+        cloned_bytes as f64 / compact_bytes as f64
+    );
+
+    println!();
+    println!("collision scan (tile and properties of every tile)");
+    let compact_time = time(|| {
+        let layer = &tilemap.layers["ground"];
+        let mut solid = 0;
+        for y in 0..MAP_SIZE {
+            for x in 0..MAP_SIZE {
+                if layer.get_tile(x, y).is_some() {
+                    solid += tilemap
+                        .properties_in(layer, x, y)
+                        .is_some_and(TileProperties::is_collidable)
+                        as usize;
+                }
+            }
+        }
+        solid
+    });
+    let cloned_time = time(|| {
+        let mut solid = 0;
+        for y in 0..MAP_SIZE {
+            for x in 0..MAP_SIZE {
+                // The old `TileLayer::get_tile` cloned the whole tile
+                if let Some(tile) = cloned[y as usize][x as usize].clone() {
+                    solid += tile.properties.is_collidable() as usize;
+                }
+            }
+        }
+        solid
+    });
+    report_time("  TileMap::properties_in", compact_time, cloned_time);
+    report_time("  cloned properties", cloned_time, cloned_time);
+
+    println!();
+    println!("render (TileMapRenderer::render of the whole map)");
+    let mut renderer =
+        TileMapRenderer::new(65536).expect("Failed to create tile renderer");
+    let projection = Mat4::orthographic_rh(
+        0.0,
+        (MAP_SIZE * TILE_SIZE) as f32,
+        (MAP_SIZE * TILE_SIZE) as f32,
+        0.0,
+        -1.0,
+        1.0,
+    );
+    let compact_time = time(|| {
+        renderer.render(&tilemap, &projection);
+        unsafe { gl::Finish() };
+        cells
+    });
+    let cloned_time = time(|| {
+        // The old render loop fetched every tile through the cloning
+        // `TileLayer::get_tile` before drawing it
+        let mut drawn = 0;
+        for row in &cloned {
+            for tile in row {
+                drawn += black_box(tile.clone()).is_some() as usize;
+            }
+        }
+        renderer.render(&tilemap, &projection);
+        unsafe { gl::Finish() };
+        drawn
+    });
+    report_time("  TileMapRenderer::render", compact_time, cloned_time);
+    report_time("  cloned properties", cloned_time, cloned_time);
+
+    // Drop the GL objects while the context is still current
+    drop(renderer);
+    drop(tilemap);
+    let _ = std::fs::remove_dir_all(tileset_path.parent().unwrap());
+}
+
+fn tile_properties(id: u32) -> TileProperties {
+    let mut properties = TileProperties::new_default();
+    if id.is_multiple_of(4) {
+        properties = properties.with_collision();
+    }
+    properties
+        .custom_properties
+        .insert("variant".to_string(), PropertyValue::Int(id as i32));
+    properties
+}
+
+/// Write an 8 by 8 tile image and its tileset JSON to a temporary
+/// directory and return the JSON's path.
+fn write_tileset(properties: &[TileProperties]) -> PathBuf {
+    let directory = std::env::temp_dir().join("teengine_tile_storage_bench");
+    std::fs::create_dir_all(&directory)
+        .expect("Failed to create bench directory");
+
+    let side = 8 * TILE_SIZE;
+    let image = image::RgbaImage::from_fn(side, side, |x, y| {
+        let kind = (y / TILE_SIZE * 8 + x / TILE_SIZE) as u8;
+        image::Rgba([kind * 4, 255 - kind * 4, 128, 255])
+    });
+    let image_path = directory.join("tiles.png");
+    image
+        .save(&image_path)
+        .expect("Failed to write tileset image");
+
+    let tiles: HashMap<u32, TileData> = properties
+        .iter()
+        .enumerate()
+        .map(|(id, properties)| {
+            let id = id as u32;
+            let data = TileData {
+                id,
+                properties: properties.clone(),
+                animation: None,
+                adjacency: None,
+                destruction: None,
+            };
+            (id, data)
+        })
+        .collect();
+    let data = TilesetData {
+        name: "bench".to_string(),
+        image_path: path_string(&image_path),
+        tile_width: TILE_SIZE,
+        tile_height: TILE_SIZE,
+        spacing: 0,
+        margin: 0,
+        color_key: None,
+        tiles,
+        terrain_sets: Vec::new(),
+    };
+    let tileset_path = directory.join("tiles.json");
+    let json =
+        serde_json::to_string(&data).expect("Failed to serialize tileset");
+    std::fs::write(&tileset_path, json).expect("Failed to write tileset");
+    tileset_path
+}
+
+fn path_string(path: &Path) -> String {
+    path.to_str().expect("Bench path is not UTF-8").to_string()
+}
+
+/// Heap bytes one cloned custom property map holds, roughly: its buckets
+/// plus the key strings.
+fn custom_properties_heap(properties: &[TileProperties]) -> usize {
+    let map: &HashMap<String, PropertyValue> = &properties[0].custom_properties;
+    map.capacity() * (size_of::<String>() + size_of::<PropertyValue>() + 1)
+        + map.keys().map(String::capacity).sum::<usize>()
+}
+
+/// Fastest of `ROUNDS` runs.
+fn time(mut run: impl FnMut() -> usize) -> Duration {
+    (0..ROUNDS)
+        .map(|_| {
+            let start = Instant::now();
+            black_box(run());
+            start.elapsed()
+        })
+        .min()
+        .unwrap_or_default()
+}
+
+fn report_bytes(
+    label: &str,
+    bytes: usize,
+) {
+    println!("{label:<26}{:>10.1} MiB", bytes as f64 / (1024.0 * 1024.0));
+}
+
+fn report_time(
+    label: &str,
+    time: Duration,
+    baseline: Duration,
+) {
+    println!(
+        "{label:<26}{:>10.2} ms  {:.2}x",
+        time.as_secs_f64() * 1000.0,
+        baseline.as_secs_f64() / time.as_secs_f64()
+    );
+}
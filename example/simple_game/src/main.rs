@@ -1,12 +1,10 @@
 use glam::Vec2;
-use rand::prelude::*;
 use std::path::Path;
 use std::time::{Duration, Instant};
 use teengine::{
     input::input_manager::InputAction,
     tile::{
-        TileInstance, TileLayer, TileMap, TileMapRenderer, TileProperties,
-        Tileset,
+        procgen::DrunkardWalk, TileLayer, TileMap, TileMapRenderer, Tileset,
     },
     AnimatedSprite, AnimationSequence, Engine, Game, Rect, Sprite, Texture,
 };
@@ -73,37 +71,18 @@ impl SimpleGame {
         // create 20x15 tilemap (640x480 pixels)
         let mut tilemap = TileMap::new(20, 15, 32, 4.0, tileset);
 
-        // create ground layer
+        // carve a seeded cave of grass through box tiles, starting where
+        // the player spawns
+        let cave = DrunkardWalk::new()
+            .with_floor_ratio(0.6)
+            .with_start(1, 2)
+            .generate(20, 15, 42);
+        let grass = tilemap.tile_instance(0);
+        let wall = tilemap.tile_instance(2);
         let mut ground_layer = TileLayer::new(20, 15);
-
-        for y in 0..15 {
-            for x in 0..20 {
-                let tile = TileInstance {
-                    id: 0, // grass tile id
-                    properties: tilemap
-                        .tileset
-                        .get_tile_properties(0)
-                        .unwrap_or(&TileProperties::new_default())
-                        .clone(),
-                };
-                ground_layer.set_tile(x as u32, y as u32, tile);
-            }
-        }
-
-        // Generate a random box tiles
-        for _ in 0..10 {
-            let x = rand::thread_rng().gen_range(0..20);
-            let y = rand::thread_rng().gen_range(0..15);
-            let tile = TileInstance {
-                id: 2,
-                properties: tilemap
-                    .tileset
-                    .get_tile_properties(2)
-                    .unwrap_or(&TileProperties::new_default())
-                    .clone(),
-            };
-            ground_layer.set_tile(x as u32, y as u32, tile);
-        }
+        cave.write_to(&mut ground_layer, |&is_floor| {
+            Some(if is_floor { grass } else { wall })
+        });
 
         tilemap.add_layer("ground".to_string(), ground_layer);
         self.tilemap = Some(tilemap);
@@ -115,59 +94,32 @@ impl SimpleGame {
         tilemap: Option<&TileMap>,
         pos: Vec2,
     ) -> bool {
-        if let Some(tilemap) = tilemap {
-            let tile_size = tilemap.tile_size as f32 * tilemap.scale;
-            let character_width = 18.0 * 4.0;
-            let character_height = 18.0 * 4.0;
-
-            let char_left = pos.x;
-            let char_right = pos.x + character_width;
-            let char_top = pos.y;
-            let char_bottom = pos.y + character_height;
-
-            let start_x = (char_left / tile_size).floor() as i32;
-            let end_x = (char_right / tile_size).ceil() as i32;
-            let start_y = (char_top / tile_size).floor() as i32;
-            let end_y = (char_bottom / tile_size).ceil() as i32;
-
-            for y in start_y..=end_y {
-                for x in start_x..=end_x {
-                    if let Some(tile) =
-                        tilemap.get_tile_at("ground", x as u32, y as u32)
-                    {
-                        if tile.properties.is_collidable() {
-                            let tile_left = x as f32 * tile_size;
-                            let tile_right = tile_left + tile_size;
-                            let tile_top = y as f32 * tile_size;
-                            let tile_bottom = tile_top + tile_size;
-
-                            if char_left < tile_right
-                                && char_right > tile_left
-                                && char_top < tile_bottom
-                                && char_bottom > tile_top
-                            {
-                                return true;
-                            }
-                        }
-                    }
-                }
-            }
-        }
-        false
+        let Some(tilemap) = tilemap else {
+            return false;
+        };
+        let character_size = Vec2::splat(18.0 * 4.0);
+        tilemap
+            .tiles_in_aabb(pos, pos + character_size)
+            .any(|(x, y)| {
+                tilemap
+                    .properties_at("ground", x, y)
+                    .is_some_and(|properties| properties.is_collidable())
+            })
     }
 }
 
 impl Game for SimpleGame {
     fn init(
         &mut self,
-        _engine: &Engine,
+        engine: &Engine,
     ) {
         let texture = Texture::new(Path::new("assets/sprite.png"))
             .expect("Failed to load texture");
+        engine.hot_reloader.watch_texture(&texture);
 
         let sprite = Sprite::new(
             texture,
-            Vec2::new(100.0, 300.0),
+            Vec2::new(156.0, 284.0), // centered on tile (1, 2)
             0.0,
             Rect::new(18.0, 18.0),
             Rect::new(90.0, 90.0),
@@ -187,6 +139,10 @@ impl Game for SimpleGame {
             eprintln!("Failed to initialize tilemap: {}", e);
         }
 
+        if let Some(tilemap) = &self.tilemap {
+            engine.hot_reloader.watch_tilemap(tilemap);
+        }
+
         self.tilemap_renderer = Some(
             TileMapRenderer::new(1000)
                 .expect("Failed to create tilemap renderer"),
@@ -202,8 +158,8 @@ impl Game for SimpleGame {
         let tilemap = self.tilemap.as_ref();
 
         if let Some(animated_sprite) = &mut self.animated_sprite {
-            let current_pos = animated_sprite.sprite().position.clone();
-            let mut new_pos = animated_sprite.sprite().position.clone();
+            let current_pos = animated_sprite.sprite().position;
+            let mut new_pos = animated_sprite.sprite().position;
             let weight = movement_speed;
 
             if engine
@@ -264,6 +220,7 @@ impl Game for SimpleGame {
 
         // update tilemap
         if let Some(tilemap) = &mut self.tilemap {
+            engine.hot_reloader.reload_tilemap(tilemap);
             tilemap.update(engine.delta_time());
         }
     }
@@ -276,6 +233,7 @@ impl Game for SimpleGame {
             if let Some(renderer) = &mut self.tilemap_renderer {
                 renderer.render(tilemap, &engine.projection);
             }
+            engine.debug_draw.tilemap_collision(tilemap);
         }
 
         if let Some(animated_sprite) = &self.animated_sprite {
@@ -0,0 +1,591 @@
+use glam::Vec2;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use super::backend::{AudioBackend, NullBackend};
+use super::decoder::Decoder;
+use super::mixer::{Bus, Mixer, PlaySettings, VoiceId};
+use super::sound::Sound;
+use super::spatial::{Attenuation, Emitter, EmitterId, Falloff};
+use crate::tile::properties::PropertyValue;
+use crate::tile::TileMap;
+
+// Seconds of music decoded ahead of playback
+const MUSIC_BUFFER_SECONDS: f32 = 1.0;
+
+#[derive(Debug, Clone, Copy)]
+pub struct MusicSettings {
+    pub volume: f32,
+    pub looping: bool,
+    /// Seconds the previous track fades out while this one fades in.
+    pub crossfade: f32,
+}
+
+impl Default for MusicSettings {
+    fn default() -> Self {
+        Self {
+            volume: 1.0,
+            looping: true,
+            crossfade: 0.0,
+        }
+    }
+}
+
+impl MusicSettings {
+    pub fn with_volume(
+        mut self,
+        volume: f32,
+    ) -> Self {
+        self.volume = volume;
+        self
+    }
+
+    pub fn with_looping(
+        mut self,
+        looping: bool,
+    ) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    pub fn with_crossfade(
+        mut self,
+        seconds: f32,
+    ) -> Self {
+        self.crossfade = seconds;
+        self
+    }
+}
+
+struct MusicStream {
+    id: u64,
+    decoder: Decoder,
+    looping: bool,
+}
+
+/// Plays sound effects and streamed music through an [`AudioBackend`].
+///
+/// Sound effects are decoded up front with [`Sound::load`] and played
+/// fire-and-forget on the sfx bus. Music is decoded a little ahead of
+/// playback in [`AudioManager::update`], which the engine calls once per
+/// frame, so files are never read on the audio thread.
+///
+/// Positional sounds are attenuated and panned relative to the listener,
+/// which the engine moves to `Engine::camera` every frame.
+pub struct AudioManager {
+    mixer: Arc<Mutex<Mixer>>,
+    backend: RefCell<Box<dyn AudioBackend>>,
+    streams: RefCell<Vec<MusicStream>>,
+    next_music_id: Cell<u64>,
+    listener: Cell<Vec2>,
+    pan_width: Cell<f32>,
+    emitters: RefCell<Vec<Emitter>>,
+    next_emitter_id: Cell<u64>,
+    sound_cache: RefCell<HashMap<PathBuf, Sound>>,
+}
+
+impl Default for AudioManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AudioManager {
+    /// Open the default sound device when the `audio-device` feature is
+    /// enabled, falling back to a silent [`NullBackend`].
+    pub fn new() -> Self {
+        #[cfg(feature = "audio-device")]
+        {
+            let device = super::backend::DeviceBackend::new()
+                .and_then(|device| Self::with_backend(Box::new(device)));
+            match device {
+                Ok(manager) => return manager,
+                Err(e) => eprintln!("Audio device unavailable: {}", e),
+            }
+        }
+
+        println!("Using silent audio backend");
+        Self::with_backend(Box::new(NullBackend::default()))
+            .expect("Null audio backend cannot fail to start")
+    }
+
+    pub fn with_backend(
+        mut backend: Box<dyn AudioBackend>
+    ) -> Result<Self, String> {
+        let mixer = Arc::new(Mutex::new(Mixer::new(backend.sample_rate())));
+        backend.start(mixer.clone())?;
+
+        Ok(Self {
+            mixer,
+            backend: RefCell::new(backend),
+            streams: RefCell::new(Vec::new()),
+            next_music_id: Cell::new(0),
+            listener: Cell::new(Vec2::ZERO),
+            pan_width: Cell::new(480.0),
+            emitters: RefCell::new(Vec::new()),
+            next_emitter_id: Cell::new(0),
+            sound_cache: RefCell::new(HashMap::new()),
+        })
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.mixer().sample_rate()
+    }
+
+    pub fn set_bus_volume(
+        &self,
+        bus: Bus,
+        volume: f32,
+    ) {
+        self.mixer().set_bus_volume(bus, volume);
+    }
+
+    pub fn bus_volume(
+        &self,
+        bus: Bus,
+    ) -> f32 {
+        self.mixer().bus_volume(bus)
+    }
+
+    /// Play `sound` once at full volume and normal pitch.
+    pub fn play(
+        &self,
+        sound: &Sound,
+    ) -> VoiceId {
+        self.play_with(sound, PlaySettings::default())
+    }
+
+    pub fn play_with(
+        &self,
+        sound: &Sound,
+        settings: PlaySettings,
+    ) -> VoiceId {
+        self.mixer().play(sound, settings)
+    }
+
+    /// Stop a sound before it finishes. Does nothing if it already ended.
+    pub fn stop(
+        &self,
+        voice: VoiceId,
+    ) {
+        self.mixer().stop_voice(voice);
+    }
+
+    pub fn is_playing(
+        &self,
+        voice: VoiceId,
+    ) -> bool {
+        self.mixer().is_playing(voice)
+    }
+
+    /// Change the volume of a playing sound.
+    pub fn set_volume(
+        &self,
+        voice: VoiceId,
+        volume: f32,
+    ) {
+        self.mixer()
+            .update_voice(voice, |settings| settings.volume = volume);
+    }
+
+    /// Change the stereo position of a playing sound.
+    pub fn set_pan(
+        &self,
+        voice: VoiceId,
+        pan: f32,
+    ) {
+        self.mixer()
+            .update_voice(voice, |settings| settings.pan = pan);
+    }
+
+    /// Change the pitch of a playing sound.
+    pub fn set_pitch(
+        &self,
+        voice: VoiceId,
+        pitch: f32,
+    ) {
+        self.mixer()
+            .update_voice(voice, |settings| settings.pitch = pitch);
+    }
+
+    pub fn listener_position(&self) -> Vec2 {
+        self.listener.get()
+    }
+
+    /// Move the listener. The engine does this every frame from the camera.
+    pub fn set_listener_position(
+        &self,
+        position: Vec2,
+    ) {
+        self.listener.set(position);
+    }
+
+    /// Horizontal distance from the listener at which a positional sound is
+    /// panned fully to one side.
+    pub fn set_pan_width(
+        &self,
+        width: f32,
+    ) {
+        self.pan_width.set(width);
+    }
+
+    /// Play `sound` once from a world position, such as a footstep at a
+    /// sprite's position. Volume and pan follow the listener while it plays.
+    pub fn play_at(
+        &self,
+        sound: &Sound,
+        position: Vec2,
+        settings: PlaySettings,
+        attenuation: Attenuation,
+    ) -> VoiceId {
+        let mut emitter = Emitter {
+            id: self.next_emitter_id(),
+            sound: sound.clone(),
+            positions: vec![position],
+            settings: settings.with_looping(false),
+            attenuation,
+            voice: None,
+            one_shot: true,
+        };
+        let (volume, pan) =
+            emitter.volume_and_pan(self.listener.get(), self.pan_width.get());
+        let voice = self.play_with(
+            sound,
+            emitter.settings.with_volume(volume).with_pan(pan),
+        );
+        emitter.voice = Some(voice);
+        self.emitters.borrow_mut().push(emitter);
+        voice
+    }
+
+    /// Loop `sound` from a world position until the emitter is removed. The
+    /// sound only plays while the listener is within `max_distance`.
+    pub fn add_emitter(
+        &self,
+        sound: &Sound,
+        position: Vec2,
+        settings: PlaySettings,
+        attenuation: Attenuation,
+    ) -> EmitterId {
+        self.add_emitter_at(sound, vec![position], settings, attenuation)
+    }
+
+    pub fn set_emitter_position(
+        &self,
+        id: EmitterId,
+        position: Vec2,
+    ) {
+        if let Some(emitter) =
+            self.emitters.borrow_mut().iter_mut().find(|e| e.id == id)
+        {
+            emitter.positions = vec![position];
+        }
+    }
+
+    pub fn remove_emitter(
+        &self,
+        id: EmitterId,
+    ) {
+        let mut emitters = self.emitters.borrow_mut();
+        if let Some(index) = emitters.iter().position(|e| e.id == id) {
+            if let Some(voice) = emitters.remove(index).voice {
+                self.stop(voice);
+            }
+        }
+    }
+
+    /// Create ambient loops for tiles whose custom properties name a sound:
+    ///
+    /// - `ambient_sound`: path of a `.wav` or `.ogg` file (required)
+    /// - `ambient_volume`: volume, defaults to 1.0
+    /// - `ambient_min_distance`, `ambient_max_distance`: attenuation range
+    ///   in world units, defaulting to [`Attenuation::default`]
+    /// - `ambient_falloff`: `linear`, `inverse` or `quadratic`
+    ///
+    /// Tiles sharing the same settings become one emitter heard from the
+    /// tile closest to the listener, so a lava pool does not get louder with
+    /// every tile.
+    pub fn add_tile_emitters(
+        &self,
+        tilemap: &TileMap,
+    ) -> Result<Vec<EmitterId>, String> {
+        let mut groups: Vec<(String, PlaySettings, Attenuation, Vec<Vec2>)> =
+            Vec::new();
+
+        let mut layer_names: Vec<&String> = tilemap.layers.keys().collect();
+        layer_names.sort();
+        for name in layer_names {
+            let layer = &tilemap.layers[name];
+            for (y, row) in layer.tiles.iter().enumerate() {
+                for x in 0..row.len() {
+                    let Some(properties) =
+                        tilemap.properties_in(layer, x as u32, y as u32)
+                    else {
+                        continue;
+                    };
+                    let properties = &properties.custom_properties;
+                    let Some(PropertyValue::String(path)) =
+                        properties.get("ambient_sound")
+                    else {
+                        continue;
+                    };
+
+                    let number = |key: &str| match properties.get(key) {
+                        Some(PropertyValue::Float(value)) => Some(*value),
+                        Some(PropertyValue::Int(value)) => Some(*value as f32),
+                        _ => None,
+                    };
+                    let defaults = Attenuation::default();
+                    let falloff = match properties.get("ambient_falloff") {
+                        Some(PropertyValue::String(name)) => {
+                            Falloff::from_name(name).ok_or_else(|| {
+                                format!("Unknown ambient_falloff: {}", name)
+                            })?
+                        }
+                        _ => defaults.falloff,
+                    };
+                    let settings = PlaySettings::default()
+                        .with_volume(number("ambient_volume").unwrap_or(1.0))
+                        .with_looping(true);
+                    let attenuation = Attenuation::new(
+                        number("ambient_min_distance")
+                            .unwrap_or(defaults.min_distance),
+                        number("ambient_max_distance")
+                            .unwrap_or(defaults.max_distance),
+                        falloff,
+                    );
+
+                    let position = tilemap.tile_center(x as i32, y as i32);
+                    let group = groups.iter_mut().find(|(p, s, a, _)| {
+                        p == path
+                            && s.volume == settings.volume
+                            && a.min_distance == attenuation.min_distance
+                            && a.max_distance == attenuation.max_distance
+                            && a.falloff == attenuation.falloff
+                    });
+                    match group {
+                        Some((_, _, _, positions)) => positions.push(position),
+                        None => groups.push((
+                            path.clone(),
+                            settings,
+                            attenuation,
+                            vec![position],
+                        )),
+                    }
+                }
+            }
+        }
+
+        groups
+            .into_iter()
+            .map(|(path, settings, attenuation, positions)| {
+                let sound = self.load_cached(Path::new(&path))?;
+                Ok(self.add_emitter_at(
+                    &sound,
+                    positions,
+                    settings,
+                    attenuation,
+                ))
+            })
+            .collect()
+    }
+
+    /// Stream a `.wav` or `.ogg` file as background music, replacing the
+    /// current track. With a crossfade both tracks play while one fades out
+    /// and the other fades in.
+    pub fn play_music(
+        &self,
+        path: &Path,
+        settings: MusicSettings,
+    ) -> Result<(), String> {
+        let decoder = Decoder::open(path)?;
+        let id = self.next_music_id.get();
+        self.next_music_id.set(id + 1);
+
+        {
+            let mut mixer = self.mixer();
+            mixer.stop_tracks(settings.crossfade);
+            mixer.add_track(
+                id,
+                decoder.sample_rate(),
+                settings.volume,
+                settings.crossfade,
+            );
+        }
+
+        // The previous streams keep decoding until their fade out ends
+        self.streams.borrow_mut().push(MusicStream {
+            id,
+            decoder,
+            looping: settings.looping,
+        });
+
+        self.fill_music();
+        Ok(())
+    }
+
+    /// Fade out the current music over `fade_out` seconds. The stream keeps
+    /// decoding until the fade reaches silence.
+    pub fn stop_music(
+        &self,
+        fade_out: f32,
+    ) {
+        self.mixer().stop_tracks(fade_out);
+    }
+
+    pub fn is_music_playing(&self) -> bool {
+        self.mixer().has_active_track()
+    }
+
+    /// Decode music ahead, update positional sounds and advance backends
+    /// without an audio thread.
+    pub fn update(
+        &self,
+        delta_time: f32,
+    ) {
+        self.fill_music();
+        self.update_emitters();
+        self.backend.borrow_mut().update(delta_time);
+    }
+
+    fn next_emitter_id(&self) -> EmitterId {
+        let id = self.next_emitter_id.get();
+        self.next_emitter_id.set(id + 1);
+        EmitterId(id)
+    }
+
+    fn add_emitter_at(
+        &self,
+        sound: &Sound,
+        positions: Vec<Vec2>,
+        settings: PlaySettings,
+        attenuation: Attenuation,
+    ) -> EmitterId {
+        let id = self.next_emitter_id();
+        self.emitters.borrow_mut().push(Emitter {
+            id,
+            sound: sound.clone(),
+            positions,
+            settings: settings.with_looping(true),
+            attenuation,
+            voice: None,
+            one_shot: false,
+        });
+        id
+    }
+
+    fn load_cached(
+        &self,
+        path: &Path,
+    ) -> Result<Sound, String> {
+        if let Some(sound) = self.sound_cache.borrow().get(path) {
+            return Ok(sound.clone());
+        }
+        let sound = Sound::load(path)?;
+        self.sound_cache
+            .borrow_mut()
+            .insert(path.to_path_buf(), sound.clone());
+        Ok(sound)
+    }
+
+    /// Refresh volume and pan of positional sounds. Looping emitters start
+    /// when the listener comes into range and stop when it leaves.
+    fn update_emitters(&self) {
+        let listener = self.listener.get();
+        let pan_width = self.pan_width.get();
+        let mut mixer = self.mixer();
+
+        self.emitters.borrow_mut().retain_mut(|emitter| {
+            let (volume, pan) = emitter.volume_and_pan(listener, pan_width);
+
+            if let Some(voice) = emitter.voice {
+                if !mixer.is_playing(voice) {
+                    emitter.voice = None;
+                    if emitter.one_shot {
+                        return false;
+                    }
+                }
+            }
+
+            match emitter.voice {
+                Some(voice) if volume <= 0.0 && !emitter.one_shot => {
+                    mixer.stop_voice(voice);
+                    emitter.voice = None;
+                }
+                Some(voice) => mixer.update_voice(voice, |settings| {
+                    settings.volume = volume;
+                    settings.pan = pan;
+                }),
+                None if volume > 0.0 => {
+                    emitter.voice = Some(mixer.play(
+                        &emitter.sound,
+                        emitter.settings.with_volume(volume).with_pan(pan),
+                    ));
+                }
+                None => (),
+            }
+            true
+        });
+    }
+
+    fn mixer(&self) -> MutexGuard<'_, Mixer> {
+        // A panic on the audio thread leaves the mixer usable
+        self.mixer
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn fill_music(&self) {
+        self.streams.borrow_mut().retain_mut(|stream| {
+            let target = (stream.decoder.sample_rate() as f32
+                * MUSIC_BUFFER_SECONDS) as usize;
+
+            let mut rewound = false;
+            loop {
+                let Some(buffered) =
+                    self.mixer().track_buffered_frames(stream.id)
+                else {
+                    // Faded out and removed by the mixer
+                    return false;
+                };
+                if buffered >= target {
+                    return true;
+                }
+
+                // Decode without holding the lock the audio thread needs
+                let chunk = match stream.decoder.read_chunk() {
+                    Ok(Some(chunk)) => {
+                        rewound = false;
+                        chunk
+                    }
+                    // A file with no samples at all must not loop forever
+                    Ok(None) if stream.looping && !rewound => {
+                        match stream.decoder.rewind() {
+                            Ok(()) => {
+                                rewound = true;
+                                continue;
+                            }
+                            Err(e) => {
+                                eprintln!("{}", e);
+                                self.mixer().end_track(stream.id);
+                                return false;
+                            }
+                        }
+                    }
+                    Ok(None) => {
+                        self.mixer().end_track(stream.id);
+                        return false;
+                    }
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        self.mixer().end_track(stream.id);
+                        return false;
+                    }
+                };
+                self.mixer().push_track_samples(stream.id, &chunk);
+            }
+        });
+    }
+}
@@ -0,0 +1,272 @@
+use std::sync::{Arc, Mutex};
+
+use super::mixer::Mixer;
+
+/// Audio output the mixer is played through.
+pub trait AudioBackend {
+    /// Output sample rate the mixer renders at.
+    fn sample_rate(&self) -> u32;
+
+    /// Begin pulling samples from `mixer`. Device backends mix on their own
+    /// audio thread from here on.
+    fn start(
+        &mut self,
+        mixer: Arc<Mutex<Mixer>>,
+    ) -> Result<(), String>;
+
+    /// Called once per frame with the frame time. Backends without an audio
+    /// thread mix `delta_time` seconds of audio here so playback keeps
+    /// advancing.
+    fn update(
+        &mut self,
+        _delta_time: f32,
+    ) {
+    }
+}
+
+/// Mixes on the calling thread as game time passes.
+struct ManualOutput {
+    sample_rate: u32,
+    mixer: Option<Arc<Mutex<Mixer>>>,
+    pending_frames: f64,
+    buffer: Vec<f32>,
+}
+
+impl ManualOutput {
+    fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            mixer: None,
+            pending_frames: 0.0,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Mix the frames covering `delta_time` into `self.buffer`.
+    fn render(
+        &mut self,
+        delta_time: f32,
+    ) -> &[f32] {
+        self.pending_frames +=
+            delta_time.max(0.0) as f64 * self.sample_rate as f64;
+        let frames = self.pending_frames as usize;
+        self.pending_frames -= frames as f64;
+
+        self.buffer.clear();
+        self.buffer.resize(frames * 2, 0.0);
+        if let Some(mut mixer) =
+            self.mixer.as_ref().and_then(|mixer| mixer.lock().ok())
+        {
+            mixer.mix(&mut self.buffer, 2);
+        }
+        &self.buffer
+    }
+}
+
+/// Backend that keeps sounds playing in time but discards the output.
+///
+/// Used when no sound device is available.
+pub struct NullBackend {
+    output: ManualOutput,
+}
+
+impl NullBackend {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            output: ManualOutput::new(sample_rate),
+        }
+    }
+}
+
+impl Default for NullBackend {
+    fn default() -> Self {
+        Self::new(44100)
+    }
+}
+
+impl AudioBackend for NullBackend {
+    fn sample_rate(&self) -> u32 {
+        self.output.sample_rate
+    }
+
+    fn start(
+        &mut self,
+        mixer: Arc<Mutex<Mixer>>,
+    ) -> Result<(), String> {
+        self.output.mixer = Some(mixer);
+        Ok(())
+    }
+
+    fn update(
+        &mut self,
+        delta_time: f32,
+    ) {
+        self.output.render(delta_time);
+    }
+}
+
+/// Samples captured by a [`LoopbackBackend`].
+#[derive(Clone, Default)]
+pub struct LoopbackOutput {
+    samples: Arc<Mutex<Vec<f32>>>,
+}
+
+impl LoopbackOutput {
+    /// Take every interleaved stereo sample mixed since the last call.
+    pub fn take(&self) -> Vec<f32> {
+        self.samples
+            .lock()
+            .map(|mut samples| std::mem::take(&mut *samples))
+            .unwrap_or_default()
+    }
+}
+
+/// Backend that records the mixed stereo output instead of playing it, so
+/// audio can be checked without a sound device.
+///
+/// ```
+/// use teengine::audio::{AudioManager, LoopbackBackend, PlaySettings, Sound};
+///
+/// let backend = LoopbackBackend::new(8000);
+/// let output = backend.output();
+/// let audio = AudioManager::with_backend(Box::new(backend)).unwrap();
+///
+/// let beep = Sound::from_samples(vec![0.5; 2 * 800], 8000);
+/// audio.play_with(&beep, PlaySettings::default().with_volume(0.5));
+/// audio.update(0.05);
+///
+/// let samples = output.take();
+/// assert_eq!(samples.len(), 2 * 400);
+/// assert!((samples[0] - 0.25).abs() < 1e-4);
+/// ```
+pub struct LoopbackBackend {
+    output: ManualOutput,
+    captured: LoopbackOutput,
+}
+
+impl LoopbackBackend {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            output: ManualOutput::new(sample_rate),
+            captured: LoopbackOutput::default(),
+        }
+    }
+
+    /// Handle for reading the captured samples after the backend was handed
+    /// to `AudioManager`.
+    pub fn output(&self) -> LoopbackOutput {
+        self.captured.clone()
+    }
+}
+
+impl AudioBackend for LoopbackBackend {
+    fn sample_rate(&self) -> u32 {
+        self.output.sample_rate
+    }
+
+    fn start(
+        &mut self,
+        mixer: Arc<Mutex<Mixer>>,
+    ) -> Result<(), String> {
+        self.output.mixer = Some(mixer);
+        Ok(())
+    }
+
+    fn update(
+        &mut self,
+        delta_time: f32,
+    ) {
+        let samples = self.output.render(delta_time);
+        if let Ok(mut captured) = self.captured.samples.lock() {
+            captured.extend_from_slice(samples);
+        }
+    }
+}
+
+/// Plays through the system's default output device.
+#[cfg(feature = "audio-device")]
+pub struct DeviceBackend {
+    device: cpal::Device,
+    config: cpal::SupportedStreamConfig,
+    stream: Option<cpal::Stream>,
+}
+
+#[cfg(feature = "audio-device")]
+impl DeviceBackend {
+    pub fn new() -> Result<Self, String> {
+        use cpal::traits::{DeviceTrait, HostTrait};
+
+        let device = cpal::default_host()
+            .default_output_device()
+            .ok_or_else(|| "No audio output device found".to_string())?;
+        let config = device
+            .default_output_config()
+            .map_err(|e| format!("Failed to query audio device: {}", e))?;
+
+        Ok(Self {
+            device,
+            config,
+            stream: None,
+        })
+    }
+
+    fn build_stream<T>(
+        &self,
+        mixer: Arc<Mutex<Mixer>>,
+    ) -> Result<cpal::Stream, cpal::BuildStreamError>
+    where
+        T: cpal::SizedSample + cpal::FromSample<f32>,
+    {
+        use cpal::traits::DeviceTrait;
+
+        let config: cpal::StreamConfig = self.config.clone().into();
+        let channels = config.channels as usize;
+        let mut buffer = Vec::new();
+
+        self.device.build_output_stream(
+            &config,
+            move |data: &mut [T], _| {
+                buffer.clear();
+                buffer.resize(data.len(), 0.0);
+                if let Ok(mut mixer) = mixer.lock() {
+                    mixer.mix(&mut buffer, channels);
+                }
+                for (out, &sample) in data.iter_mut().zip(&buffer) {
+                    *out = T::from_sample(sample);
+                }
+            },
+            |e| eprintln!("Audio stream error: {}", e),
+            None,
+        )
+    }
+}
+
+#[cfg(feature = "audio-device")]
+impl AudioBackend for DeviceBackend {
+    fn sample_rate(&self) -> u32 {
+        self.config.sample_rate().0
+    }
+
+    fn start(
+        &mut self,
+        mixer: Arc<Mutex<Mixer>>,
+    ) -> Result<(), String> {
+        use cpal::traits::StreamTrait;
+
+        let stream = match self.config.sample_format() {
+            cpal::SampleFormat::F32 => self.build_stream::<f32>(mixer),
+            cpal::SampleFormat::I16 => self.build_stream::<i16>(mixer),
+            cpal::SampleFormat::U16 => self.build_stream::<u16>(mixer),
+            format => {
+                return Err(format!("Unsupported sample format: {}", format))
+            }
+        }
+        .map_err(|e| format!("Failed to open audio stream: {}", e))?;
+
+        stream
+            .play()
+            .map_err(|e| format!("Failed to start audio stream: {}", e))?;
+        self.stream = Some(stream);
+        Ok(())
+    }
+}
@@ -0,0 +1,158 @@
+use hound::{SampleFormat, WavReader};
+use lewton::inside_ogg::OggStreamReader;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+// Frames decoded per WAV chunk. OGG chunks are one Vorbis packet.
+const WAV_CHUNK_FRAMES: usize = 4096;
+
+enum Source {
+    Wav(WavReader<BufReader<File>>),
+    Ogg(Box<OggStreamReader<BufReader<File>>>),
+}
+
+/// Incremental WAV/OGG decoder producing interleaved stereo `f32` samples.
+///
+/// Mono files are duplicated to both channels and files with more than two
+/// channels keep only the first two.
+pub(crate) struct Decoder {
+    path: PathBuf,
+    source: Source,
+    sample_rate: u32,
+    channels: usize,
+}
+
+impl Decoder {
+    pub(crate) fn open(path: &Path) -> Result<Self, String> {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_ascii_lowercase());
+        let error = |e: &dyn std::fmt::Display| {
+            format!("Failed to open audio {}: {}", path.display(), e)
+        };
+
+        let (source, sample_rate, channels) = match extension.as_deref() {
+            Some("wav") => {
+                let reader = WavReader::open(path).map_err(|e| error(&e))?;
+                let spec = reader.spec();
+                (
+                    Source::Wav(reader),
+                    spec.sample_rate,
+                    spec.channels as usize,
+                )
+            }
+            Some("ogg") => {
+                let file = File::open(path).map_err(|e| error(&e))?;
+                let reader = OggStreamReader::new(BufReader::new(file))
+                    .map_err(|e| error(&e))?;
+                let sample_rate = reader.ident_hdr.audio_sample_rate;
+                let channels = reader.ident_hdr.audio_channels as usize;
+                (Source::Ogg(Box::new(reader)), sample_rate, channels)
+            }
+            _ => {
+                return Err(format!(
+                    "Unsupported audio format: {} (expected .wav or .ogg)",
+                    path.display()
+                ))
+            }
+        };
+
+        if channels == 0 || sample_rate == 0 {
+            return Err(format!("Invalid audio stream: {}", path.display()));
+        }
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            source,
+            sample_rate,
+            channels,
+        })
+    }
+
+    pub(crate) fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Decode the next chunk. Returns `None` at the end of the stream.
+    pub(crate) fn read_chunk(&mut self) -> Result<Option<Vec<f32>>, String> {
+        let samples: Vec<f32> = match &mut self.source {
+            Source::Wav(reader) => {
+                let spec = reader.spec();
+                let count = WAV_CHUNK_FRAMES * self.channels;
+                match spec.sample_format {
+                    SampleFormat::Float => reader
+                        .samples::<f32>()
+                        .take(count)
+                        .collect::<Result<_, _>>(),
+                    SampleFormat::Int => {
+                        let scale =
+                            1.0 / (1u64 << (spec.bits_per_sample - 1)) as f32;
+                        reader
+                            .samples::<i32>()
+                            .take(count)
+                            .map(|sample| sample.map(|s| s as f32 * scale))
+                            .collect()
+                    }
+                }
+                .map_err(|e| {
+                    format!("Failed to decode {}: {}", self.path.display(), e)
+                })?
+            }
+            Source::Ogg(reader) => loop {
+                // Vorbis packets may decode to zero samples
+                match reader.read_dec_packet_itl() {
+                    Ok(Some(packet)) if packet.is_empty() => continue,
+                    Ok(Some(packet)) => {
+                        break packet
+                            .iter()
+                            .map(|&sample| sample as f32 / 32768.0)
+                            .collect();
+                    }
+                    Ok(None) => break Vec::new(),
+                    Err(e) => {
+                        return Err(format!(
+                            "Failed to decode {}: {}",
+                            self.path.display(),
+                            e
+                        ))
+                    }
+                }
+            },
+        };
+
+        if samples.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Self::to_stereo(&samples, self.channels)))
+    }
+
+    /// Restart decoding from the beginning of the file.
+    pub(crate) fn rewind(&mut self) -> Result<(), String> {
+        if let Source::Wav(reader) = &mut self.source {
+            return reader.seek(0).map_err(|e| {
+                format!("Failed to rewind {}: {}", self.path.display(), e)
+            });
+        }
+
+        // Vorbis streams are simply reopened
+        let path = self.path.clone();
+        *self = Self::open(&path)?;
+        Ok(())
+    }
+
+    fn to_stereo(
+        samples: &[f32],
+        channels: usize,
+    ) -> Vec<f32> {
+        match channels {
+            1 => samples.iter().flat_map(|&s| [s, s]).collect(),
+            2 => samples.to_vec(),
+            _ => samples
+                .chunks_exact(channels)
+                .flat_map(|frame| [frame[0], frame[1]])
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,447 @@
+use std::collections::VecDeque;
+
+use super::sound::Sound;
+
+/// Mixer bus a sound is routed through. Every bus is scaled by `Master`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Bus {
+    Master,
+    Music,
+    Sfx,
+}
+
+impl Bus {
+    fn index(self) -> usize {
+        match self {
+            Bus::Master => 0,
+            Bus::Music => 1,
+            Bus::Sfx => 2,
+        }
+    }
+}
+
+/// Handle to a playing sound effect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VoiceId(u64);
+
+#[derive(Debug, Clone, Copy)]
+pub struct PlaySettings {
+    pub volume: f32,
+    /// Playback speed multiplier; 2.0 plays an octave higher.
+    pub pitch: f32,
+    /// Stereo position from -1.0 (left) to 1.0 (right).
+    pub pan: f32,
+    pub looping: bool,
+}
+
+impl Default for PlaySettings {
+    fn default() -> Self {
+        Self {
+            volume: 1.0,
+            pitch: 1.0,
+            pan: 0.0,
+            looping: false,
+        }
+    }
+}
+
+impl PlaySettings {
+    pub fn with_volume(
+        mut self,
+        volume: f32,
+    ) -> Self {
+        self.volume = volume;
+        self
+    }
+
+    pub fn with_pitch(
+        mut self,
+        pitch: f32,
+    ) -> Self {
+        self.pitch = pitch;
+        self
+    }
+
+    pub fn with_pan(
+        mut self,
+        pan: f32,
+    ) -> Self {
+        self.pan = pan;
+        self
+    }
+
+    pub fn with_looping(
+        mut self,
+        looping: bool,
+    ) -> Self {
+        self.looping = looping;
+        self
+    }
+}
+
+struct Voice {
+    id: VoiceId,
+    sound: Sound,
+    settings: PlaySettings,
+    /// Read position in source frames.
+    position: f64,
+}
+
+/// Gain that moves linearly towards a target over a number of frames.
+struct Fade {
+    gain: f32,
+    target: f32,
+    step: f32,
+}
+
+impl Fade {
+    fn new(
+        from: f32,
+        to: f32,
+        frames: f32,
+    ) -> Self {
+        let mut fade = Self {
+            gain: from,
+            target: from,
+            step: 0.0,
+        };
+        fade.fade_to(to, frames);
+        fade
+    }
+
+    fn fade_to(
+        &mut self,
+        target: f32,
+        frames: f32,
+    ) {
+        self.target = target;
+        if frames < 1.0 {
+            self.gain = target;
+            self.step = 0.0;
+        } else {
+            self.step = (target - self.gain).abs() / frames;
+        }
+    }
+
+    fn advance(&mut self) -> f32 {
+        let gain = self.gain;
+        if self.gain < self.target {
+            self.gain = (self.gain + self.step).min(self.target);
+        } else if self.gain > self.target {
+            self.gain = (self.gain - self.step).max(self.target);
+        }
+        gain
+    }
+}
+
+/// Streamed music fed in chunks by the main thread.
+struct MusicTrack {
+    id: u64,
+    buffer: VecDeque<f32>,
+    /// Source frames per output frame.
+    step: f64,
+    /// Fraction of the way from the first to the second buffered frame.
+    position: f64,
+    volume: f32,
+    fade: Fade,
+    /// Remove the track once the fade out reaches silence.
+    stopping: bool,
+    /// No more samples will be pushed.
+    ended: bool,
+}
+
+impl MusicTrack {
+    fn is_finished(&self) -> bool {
+        (self.stopping && self.fade.gain <= 0.0)
+            || (self.ended && self.buffer.len() < 4)
+    }
+}
+
+/// Software mixer that sums sound effects and music into interleaved
+/// output samples.
+///
+/// Backends call [`Mixer::mix`] to pull audio; everything else goes through
+/// `AudioManager`.
+pub struct Mixer {
+    sample_rate: u32,
+    bus_volumes: [f32; 3],
+    voices: Vec<Voice>,
+    tracks: Vec<MusicTrack>,
+    next_voice_id: u64,
+    scratch: Vec<f32>,
+}
+
+impl Mixer {
+    pub(crate) fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            bus_volumes: [1.0; 3],
+            voices: Vec::new(),
+            tracks: Vec::new(),
+            next_voice_id: 0,
+            scratch: Vec::new(),
+        }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub(crate) fn set_bus_volume(
+        &mut self,
+        bus: Bus,
+        volume: f32,
+    ) {
+        self.bus_volumes[bus.index()] = volume.max(0.0);
+    }
+
+    pub(crate) fn bus_volume(
+        &self,
+        bus: Bus,
+    ) -> f32 {
+        self.bus_volumes[bus.index()]
+    }
+
+    pub(crate) fn play(
+        &mut self,
+        sound: &Sound,
+        settings: PlaySettings,
+    ) -> VoiceId {
+        let id = VoiceId(self.next_voice_id);
+        self.next_voice_id += 1;
+
+        if sound.frames() > 0 {
+            self.voices.push(Voice {
+                id,
+                sound: sound.clone(),
+                settings,
+                position: 0.0,
+            });
+        }
+        id
+    }
+
+    pub(crate) fn update_voice(
+        &mut self,
+        id: VoiceId,
+        update: impl FnOnce(&mut PlaySettings),
+    ) {
+        if let Some(voice) = self.voices.iter_mut().find(|v| v.id == id) {
+            update(&mut voice.settings);
+        }
+    }
+
+    pub(crate) fn stop_voice(
+        &mut self,
+        id: VoiceId,
+    ) {
+        self.voices.retain(|voice| voice.id != id);
+    }
+
+    pub(crate) fn is_playing(
+        &self,
+        id: VoiceId,
+    ) -> bool {
+        self.voices.iter().any(|voice| voice.id == id)
+    }
+
+    pub(crate) fn add_track(
+        &mut self,
+        id: u64,
+        source_rate: u32,
+        volume: f32,
+        fade_in: f32,
+    ) {
+        self.tracks.push(MusicTrack {
+            id,
+            buffer: VecDeque::new(),
+            step: source_rate as f64 / self.sample_rate as f64,
+            position: 0.0,
+            volume,
+            fade: Fade::new(0.0, 1.0, fade_in * self.sample_rate as f32),
+            stopping: false,
+            ended: false,
+        });
+    }
+
+    /// Buffered source frames of a track, or `None` once it was removed.
+    pub(crate) fn track_buffered_frames(
+        &self,
+        id: u64,
+    ) -> Option<usize> {
+        self.tracks
+            .iter()
+            .find(|track| track.id == id)
+            .map(|track| track.buffer.len() / 2)
+    }
+
+    pub(crate) fn push_track_samples(
+        &mut self,
+        id: u64,
+        samples: &[f32],
+    ) {
+        if let Some(track) = self.tracks.iter_mut().find(|t| t.id == id) {
+            track.buffer.extend(samples);
+        }
+    }
+
+    /// Returns `true` if a track is playing that is not fading out.
+    pub(crate) fn has_active_track(&self) -> bool {
+        self.tracks.iter().any(|track| !track.stopping)
+    }
+
+    /// Mark that no more samples will be pushed to a track.
+    pub(crate) fn end_track(
+        &mut self,
+        id: u64,
+    ) {
+        if let Some(track) = self.tracks.iter_mut().find(|t| t.id == id) {
+            track.ended = true;
+        }
+    }
+
+    /// Fade out every track that is still playing and remove it once silent.
+    pub(crate) fn stop_tracks(
+        &mut self,
+        fade_out: f32,
+    ) {
+        let frames = fade_out * self.sample_rate as f32;
+        for track in &mut self.tracks {
+            track.stopping = true;
+            track.fade.fade_to(0.0, frames);
+        }
+    }
+
+    /// Mix every active sound into `out`, interleaved with `channels`
+    /// channels. Mono output takes the average of both sides; channels past
+    /// the second are left silent.
+    pub fn mix(
+        &mut self,
+        out: &mut [f32],
+        channels: usize,
+    ) {
+        let channels = channels.max(1);
+        let frames = out.len() / channels;
+
+        self.scratch.clear();
+        self.scratch.resize(frames * 2, 0.0);
+
+        let master = self.bus_volumes[Bus::Master.index()];
+        let sfx = self.bus_volumes[Bus::Sfx.index()] * master;
+        let music = self.bus_volumes[Bus::Music.index()] * master;
+
+        let scratch = &mut self.scratch;
+        let sample_rate = self.sample_rate;
+        self.voices.retain_mut(|voice| {
+            Self::mix_voice(voice, scratch, sfx, sample_rate)
+        });
+        self.tracks
+            .retain_mut(|track| Self::mix_track(track, scratch, music));
+
+        for (frame, mixed) in out
+            .chunks_exact_mut(channels)
+            .zip(self.scratch.chunks_exact(2))
+        {
+            let left = mixed[0].clamp(-1.0, 1.0);
+            let right = mixed[1].clamp(-1.0, 1.0);
+            frame.fill(0.0);
+            if channels == 1 {
+                frame[0] = (left + right) * 0.5;
+            } else {
+                frame[0] = left;
+                frame[1] = right;
+            }
+        }
+    }
+
+    /// Returns `false` once the voice finished playing.
+    fn mix_voice(
+        voice: &mut Voice,
+        scratch: &mut [f32],
+        bus_volume: f32,
+        sample_rate: u32,
+    ) -> bool {
+        let samples = voice.sound.samples();
+        let frames = voice.sound.frames();
+        let settings = voice.settings;
+        let step = settings.pitch.max(0.0) as f64
+            * voice.sound.sample_rate() as f64
+            / sample_rate as f64;
+        let (left_gain, right_gain) = pan_gains(settings.pan);
+        let volume = settings.volume * bus_volume;
+
+        for out in scratch.chunks_exact_mut(2) {
+            let index = voice.position as usize;
+            let next = if index + 1 < frames {
+                index + 1
+            } else if settings.looping {
+                0
+            } else {
+                index
+            };
+            let t = (voice.position - index as f64) as f32;
+
+            let left = lerp(samples[index * 2], samples[next * 2], t);
+            let right = lerp(samples[index * 2 + 1], samples[next * 2 + 1], t);
+            out[0] += left * left_gain * volume;
+            out[1] += right * right_gain * volume;
+
+            voice.position += step;
+            if voice.position >= frames as f64 {
+                if !settings.looping {
+                    return false;
+                }
+                voice.position %= frames as f64;
+            }
+        }
+
+        true
+    }
+
+    /// Returns `false` once the track finished or faded out.
+    fn mix_track(
+        track: &mut MusicTrack,
+        scratch: &mut [f32],
+        bus_volume: f32,
+    ) -> bool {
+        for out in scratch.chunks_exact_mut(2) {
+            if track.is_finished() {
+                return false;
+            }
+            // Buffer underrun: stay silent until more samples arrive
+            if track.buffer.len() < 4 {
+                break;
+            }
+
+            let t = track.position as f32;
+            let gain = track.fade.advance() * track.volume * bus_volume;
+            out[0] += lerp(track.buffer[0], track.buffer[2], t) * gain;
+            out[1] += lerp(track.buffer[1], track.buffer[3], t) * gain;
+
+            track.position += track.step;
+            while track.position >= 1.0 && track.buffer.len() >= 2 {
+                track.buffer.drain(..2);
+                track.position -= 1.0;
+            }
+        }
+
+        !track.is_finished()
+    }
+}
+
+fn lerp(
+    a: f32,
+    b: f32,
+    t: f32,
+) -> f32 {
+    a + (b - a) * t
+}
+
+/// Constant power panning, normalized so the center keeps full volume.
+fn pan_gains(pan: f32) -> (f32, f32) {
+    let angle = (pan.clamp(-1.0, 1.0) + 1.0) * std::f32::consts::FRAC_PI_4;
+    (
+        angle.cos() * std::f32::consts::SQRT_2,
+        angle.sin() * std::f32::consts::SQRT_2,
+    )
+}
@@ -0,0 +1,14 @@
+pub mod audio_manager;
+pub mod backend;
+mod decoder;
+pub mod mixer;
+pub mod sound;
+pub mod spatial;
+
+pub use audio_manager::{AudioManager, MusicSettings};
+#[cfg(feature = "audio-device")]
+pub use backend::DeviceBackend;
+pub use backend::{AudioBackend, LoopbackBackend, LoopbackOutput, NullBackend};
+pub use mixer::{Bus, Mixer, PlaySettings, VoiceId};
+pub use sound::Sound;
+pub use spatial::{Attenuation, EmitterId, Falloff};
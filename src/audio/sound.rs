@@ -0,0 +1,55 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use super::decoder::Decoder;
+
+/// Sound effect decoded into memory as interleaved stereo samples.
+///
+/// Clones share the sample data, so a sound can be played many times at
+/// once without copying it.
+#[derive(Debug, Clone)]
+pub struct Sound {
+    samples: Arc<[f32]>,
+    sample_rate: u32,
+}
+
+impl Sound {
+    /// Decode a `.wav` or `.ogg` file.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let mut decoder = Decoder::open(path)?;
+        let mut samples = Vec::new();
+        while let Some(chunk) = decoder.read_chunk()? {
+            samples.extend_from_slice(&chunk);
+        }
+        Ok(Self::from_samples(samples, decoder.sample_rate()))
+    }
+
+    /// Build a sound from interleaved stereo samples in the range -1..1.
+    pub fn from_samples(
+        samples: Vec<f32>,
+        sample_rate: u32,
+    ) -> Self {
+        Self {
+            samples: samples.into(),
+            sample_rate,
+        }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Number of stereo frames.
+    pub fn frames(&self) -> usize {
+        self.samples.len() / 2
+    }
+
+    /// Length in seconds at the original pitch.
+    pub fn duration(&self) -> f32 {
+        self.frames() as f32 / self.sample_rate as f32
+    }
+
+    pub(crate) fn samples(&self) -> &[f32] {
+        &self.samples
+    }
+}
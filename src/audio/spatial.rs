@@ -0,0 +1,135 @@
+use glam::Vec2;
+
+use super::mixer::{PlaySettings, VoiceId};
+use super::sound::Sound;
+
+/// How volume drops between `min_distance` and `max_distance`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Falloff {
+    #[default]
+    Linear,
+    /// Falls quickly near the source and slowly further away.
+    Inverse,
+    /// Stays loud near the source and falls quickly near the edge.
+    Quadratic,
+}
+
+impl Falloff {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "linear" => Some(Falloff::Linear),
+            "inverse" => Some(Falloff::Inverse),
+            "quadratic" => Some(Falloff::Quadratic),
+            _ => None,
+        }
+    }
+}
+
+/// Distance attenuation of a positional sound, in world units.
+///
+/// ```
+/// use teengine::audio::{Attenuation, Falloff};
+///
+/// let attenuation = Attenuation::new(10.0, 110.0, Falloff::Linear);
+/// assert_eq!(attenuation.gain(5.0), 1.0);
+/// assert_eq!(attenuation.gain(60.0), 0.5);
+/// assert_eq!(attenuation.gain(200.0), 0.0);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Attenuation {
+    /// Closer than this the sound plays at full volume.
+    pub min_distance: f32,
+    /// Further than this the sound is silent.
+    pub max_distance: f32,
+    pub falloff: Falloff,
+}
+
+impl Default for Attenuation {
+    fn default() -> Self {
+        Self {
+            min_distance: 64.0,
+            max_distance: 640.0,
+            falloff: Falloff::Linear,
+        }
+    }
+}
+
+impl Attenuation {
+    pub fn new(
+        min_distance: f32,
+        max_distance: f32,
+        falloff: Falloff,
+    ) -> Self {
+        Self {
+            min_distance,
+            max_distance,
+            falloff,
+        }
+    }
+
+    /// Volume multiplier for a sound `distance` units from the listener.
+    pub fn gain(
+        &self,
+        distance: f32,
+    ) -> f32 {
+        let min = self.min_distance.max(0.0);
+        let max = self.max_distance.max(min);
+        if distance <= min {
+            return 1.0;
+        }
+        if distance >= max {
+            return 0.0;
+        }
+
+        let t = (distance - min) / (max - min);
+        match self.falloff {
+            Falloff::Linear => 1.0 - t,
+            Falloff::Inverse => {
+                // 1/d curve rescaled to reach exactly zero at max_distance
+                let min = min.max(1.0);
+                let at_max = min / max.max(min);
+                ((min / distance.max(min) - at_max) / (1.0 - at_max))
+                    .clamp(0.0, 1.0)
+            }
+            Falloff::Quadratic => 1.0 - t * t,
+        }
+    }
+}
+
+/// Handle to a looping positional sound.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EmitterId(pub(crate) u64);
+
+/// Positional sound. An emitter may cover several points, such as every
+/// tile of a lava pool, and is heard from the one closest to the listener.
+pub(crate) struct Emitter {
+    pub(crate) id: EmitterId,
+    pub(crate) sound: Sound,
+    pub(crate) positions: Vec<Vec2>,
+    pub(crate) settings: PlaySettings,
+    pub(crate) attenuation: Attenuation,
+    pub(crate) voice: Option<VoiceId>,
+    /// Removed once its voice finished instead of looping.
+    pub(crate) one_shot: bool,
+}
+
+impl Emitter {
+    /// Volume and pan of the emitter heard from `listener`.
+    pub(crate) fn volume_and_pan(
+        &self,
+        listener: Vec2,
+        pan_width: f32,
+    ) -> (f32, f32) {
+        let Some(nearest) = self.positions.iter().copied().min_by(|a, b| {
+            a.distance_squared(listener)
+                .total_cmp(&b.distance_squared(listener))
+        }) else {
+            return (0.0, 0.0);
+        };
+
+        let gain = self.attenuation.gain(nearest.distance(listener));
+        let pan =
+            ((nearest.x - listener.x) / pan_width.max(1.0)).clamp(-1.0, 1.0);
+        (self.settings.volume * gain, pan)
+    }
+}
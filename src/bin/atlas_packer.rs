@@ -0,0 +1,109 @@
+//! Packs images into a texture atlas offline.
+//!
+//! ```text
+//! atlas_packer [--padding N] [--extrude N] [--max-size N] <output.png> <inputs...>
+//! ```
+//!
+//! Inputs can be image files or directories, whose PNG files are added in
+//! name order. The atlas layout is written next to the output image with a
+//! `.json` extension and can be loaded with `TextureAtlas::load`.
+
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use teengine::texture::atlas::AtlasBuilder;
+
+const USAGE: &str = "Usage: atlas_packer [--padding N] [--extrude N] \
+                     [--max-size N] <output.png> <inputs...>";
+
+fn parse_number(
+    flag: &str,
+    value: Option<String>,
+) -> Result<u32, String> {
+    value
+        .ok_or_else(|| format!("Missing value for {}", flag))?
+        .parse()
+        .map_err(|e| format!("Invalid value for {}: {}", flag, e))
+}
+
+fn collect_inputs(
+    path: &Path,
+    inputs: &mut Vec<PathBuf>,
+) -> Result<(), String> {
+    if !path.is_dir() {
+        inputs.push(path.to_path_buf());
+        return Ok(());
+    }
+
+    let mut files: Vec<PathBuf> = std::fs::read_dir(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("png"))
+        })
+        .collect();
+    files.sort();
+    inputs.extend(files);
+    Ok(())
+}
+
+fn run() -> Result<(), String> {
+    let mut builder = AtlasBuilder::new();
+    let mut positional = Vec::new();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--padding" => {
+                builder =
+                    builder.with_padding(parse_number(&arg, args.next())?)
+            }
+            "--extrude" => {
+                builder =
+                    builder.with_extrude(parse_number(&arg, args.next())?)
+            }
+            "--max-size" => {
+                builder =
+                    builder.with_max_size(parse_number(&arg, args.next())?)
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            _ => positional.push(PathBuf::from(arg)),
+        }
+    }
+
+    if positional.len() < 2 {
+        return Err(USAGE.to_string());
+    }
+    let output = positional.remove(0);
+
+    let mut inputs = Vec::new();
+    for path in &positional {
+        collect_inputs(path, &mut inputs)?;
+    }
+    for input in &inputs {
+        builder.add_file(input)?;
+    }
+
+    let layout = builder.write(&output)?;
+    println!(
+        "Packed {} images into {} ({}x{})",
+        layout.regions.len(),
+        output.display(),
+        layout.width,
+        layout.height
+    );
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
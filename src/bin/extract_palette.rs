@@ -0,0 +1,101 @@
+//! Extracts palettes from images for palette-indexed sprites.
+//!
+//! ```text
+//! extract_palette [--key RRGGBB] [--indexed <indexed.png>] <palette.png> <base.png> [variants...]
+//! ```
+//!
+//! The first palette row holds the colors of the base image in the order
+//! they first appear. Each variant, a recolored copy of the base image,
+//! adds one row with its colors at the same indices. The palette image can
+//! be loaded with `PaletteTexture::load`. With `--indexed` the base image
+//! is also written with its palette indices in the red channel, ready for
+//! `IndexedImage::open_encoded`.
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+use teengine::sprite::utils::color_key_util::ColorKey;
+use teengine::texture::palette::{IndexedImage, Palette};
+
+const USAGE: &str = "Usage: extract_palette [--key RRGGBB] \
+                     [--indexed <indexed.png>] <palette.png> <base.png> \
+                     [variants...]";
+
+fn open_rgba(path: &PathBuf) -> Result<image::RgbaImage, String> {
+    image::open(path)
+        .map(|image| image.into_rgba8())
+        .map_err(|e| format!("Failed to load {}: {}", path.display(), e))
+}
+
+fn run() -> Result<(), String> {
+    let mut color_key = None;
+    let mut indexed_output = None;
+    let mut positional = Vec::new();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--key" => {
+                let hex = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --key".to_string())?;
+                color_key = Some(ColorKey::from_hex(&hex, 0.01)?);
+            }
+            "--indexed" => {
+                let path = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --indexed".to_string())?;
+                indexed_output = Some(PathBuf::from(path));
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            _ => positional.push(PathBuf::from(arg)),
+        }
+    }
+
+    if positional.len() < 2 {
+        return Err(USAGE.to_string());
+    }
+    let output = positional.remove(0);
+    let base_path = positional.remove(0);
+
+    let base = open_rgba(&base_path)?;
+    let palette = Palette::extract(&base, color_key.as_ref())?;
+    let indexed = IndexedImage::from_rgba(&base, &palette, color_key.as_ref())?;
+
+    let mut palettes = vec![palette];
+    for variant_path in &positional {
+        let variant = open_rgba(variant_path)?;
+        let variant = Palette::from_variant(&indexed, &variant)
+            .map_err(|e| format!("{}: {}", variant_path.display(), e))?;
+        palettes.push(variant);
+    }
+
+    Palette::to_image(&palettes)
+        .save(&output)
+        .map_err(|e| format!("Failed to write {}: {}", output.display(), e))?;
+    if let Some(path) = &indexed_output {
+        indexed.to_rgba().save(path).map_err(|e| {
+            format!("Failed to write {}: {}", path.display(), e)
+        })?;
+    }
+
+    println!(
+        "Wrote {} palettes of {} colors to {}",
+        palettes.len(),
+        palettes[0].len(),
+        output.display()
+    );
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
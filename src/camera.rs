@@ -0,0 +1,74 @@
+use glam::{Mat4, Vec2, Vec3};
+
+/// 2D camera looking at a point in world space.
+///
+/// `position` is the world point shown at the center of the view. Pass
+/// [`Camera::view_projection`] instead of `Engine::projection` to renderers
+/// to draw the world through the camera. The audio listener follows the
+/// engine camera.
+#[derive(Debug, Clone, Copy)]
+pub struct Camera {
+    pub position: Vec2,
+    /// Screen pixels per world unit.
+    pub zoom: f32,
+    viewport_size: Vec2,
+}
+
+impl Camera {
+    /// Camera centered on a `viewport_size` view, so world and screen
+    /// coordinates match until it is moved.
+    pub fn new(viewport_size: Vec2) -> Self {
+        Self {
+            position: viewport_size * 0.5,
+            zoom: 1.0,
+            viewport_size,
+        }
+    }
+
+    pub fn viewport_size(&self) -> Vec2 {
+        self.viewport_size
+    }
+
+    pub fn set_viewport_size(
+        &mut self,
+        viewport_size: Vec2,
+    ) {
+        self.viewport_size = viewport_size;
+    }
+
+    /// Transform from world space to screen pixels.
+    pub fn view_matrix(&self) -> Mat4 {
+        Mat4::from_translation((self.viewport_size * 0.5).extend(0.0))
+            * Mat4::from_scale(Vec3::new(self.zoom, self.zoom, 1.0))
+            * Mat4::from_translation((-self.position).extend(0.0))
+    }
+
+    pub fn view_projection(
+        &self,
+        projection: &Mat4,
+    ) -> Mat4 {
+        *projection * self.view_matrix()
+    }
+
+    pub fn world_to_screen(
+        &self,
+        world: Vec2,
+    ) -> Vec2 {
+        (world - self.position) * self.zoom + self.viewport_size * 0.5
+    }
+
+    /// `screen` is in view units, the space `Engine::projection` covers.
+    /// Use `Engine::screen_to_world` for window pixels.
+    pub fn screen_to_world(
+        &self,
+        screen: Vec2,
+    ) -> Vec2 {
+        (screen - self.viewport_size * 0.5) / self.zoom + self.position
+    }
+
+    /// Top left and bottom right corners of the visible world area.
+    pub fn visible_rect(&self) -> (Vec2, Vec2) {
+        let half_size = self.viewport_size * 0.5 / self.zoom;
+        (self.position - half_size, self.position + half_size)
+    }
+}
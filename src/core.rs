@@ -1,14 +1,23 @@
+use std::cell::{Cell, RefCell};
 use std::time::Instant;
 
 // src/core.rs
-use glam::Mat4;
+use glam::{Mat4, Vec2};
 use glutin::event::{ElementState, Event, KeyboardInput, WindowEvent};
 use glutin::event_loop::{ControlFlow, EventLoop};
 use glutin::window::WindowBuilder;
 use glutin::ContextBuilder;
 
+use crate::audio::AudioManager;
+use crate::camera::Camera;
+use crate::debug::DebugDraw;
+use crate::hot_reload::HotReloader;
 use crate::input::input_manager::InputManager;
+use crate::render::{PostProcessor, RenderTarget, ResizePolicy, Viewport};
 use crate::sprite::sprite_renderer::SpriteRenderer;
+use crate::text::TextRenderer;
+use crate::texture::options::TextureOptions;
+use crate::tile::properties::Color;
 
 pub trait Game {
     fn init(
@@ -27,9 +36,18 @@ pub trait Game {
 
 pub struct Engine {
     pub sprite_renderer: SpriteRenderer,
+    pub text_renderer: TextRenderer,
+    pub debug_draw: DebugDraw,
     pub input_manager: InputManager,
+    pub audio_manager: AudioManager,
+    pub hot_reloader: HotReloader,
+    pub post_processor: PostProcessor,
     pub projection: Mat4,
+    pub camera: Camera,
     window_context: glutin::WindowedContext<glutin::PossiblyCurrent>,
+    viewport: Cell<Viewport>,
+    clear_color: Cell<Color>,
+    scene_target: RefCell<Option<RenderTarget>>,
     last_frame_time: Instant,
     delta_time: f32,
 }
@@ -79,25 +97,35 @@ impl Engine {
             gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
         }
 
-        let projection = Mat4::orthographic_rh(
-            0.0,
-            width as f32,
-            height as f32,
-            0.0,
-            -1.0,
-            1.0,
+        let window = windowed_context.window();
+        let inner_size = window.inner_size();
+        let viewport = Viewport::new(
+            (inner_size.width, inner_size.height),
+            window.scale_factor(),
         );
 
         println!("Creating sprite renderer...");
         let mut engine = Self {
             sprite_renderer: SpriteRenderer::new(),
+            text_renderer: TextRenderer::new(),
+            debug_draw: DebugDraw::new().expect("Failed to create debug draw"),
             input_manager: InputManager::new(),
-            projection,
+            audio_manager: AudioManager::new(),
+            hot_reloader: HotReloader::new(),
+            post_processor: PostProcessor::new()
+                .expect("Failed to create post processor"),
+            projection: Mat4::IDENTITY,
+            camera: Camera::new(viewport.view_size()),
             window_context: windowed_context,
+            viewport: Cell::new(viewport),
+            clear_color: Cell::new(Color::new(0.0, 0.0, 0.0, 1.0)),
+            scene_target: RefCell::new(None),
             last_frame_time: Instant::now(),
             delta_time: 0.0,
         };
 
+        engine.update_view();
+
         println!("Initializing game...");
         game.init(&engine);
 
@@ -118,23 +146,42 @@ impl Engine {
                     }
                     WindowEvent::Resized(physical_size) => {
                         engine.window_context.resize(physical_size);
-                        unsafe {
-                            gl::Viewport(
-                                0,
-                                0,
-                                physical_size.width as i32,
-                                physical_size.height as i32,
-                            );
-                        }
-                        engine.projection = Mat4::orthographic_rh(
-                            0.0,
-                            physical_size.width as f32,
-                            physical_size.height as f32,
-                            0.0,
-                            -1.0,
-                            1.0,
+                        engine.update_viewport(|viewport| {
+                            viewport.set_window_size((
+                                physical_size.width,
+                                physical_size.height,
+                            ))
+                        });
+                    }
+                    WindowEvent::ScaleFactorChanged {
+                        scale_factor,
+                        new_inner_size,
+                    } => {
+                        engine.window_context.resize(*new_inner_size);
+                        engine.update_viewport(|viewport| {
+                            viewport.set_scale_factor(scale_factor);
+                            viewport.set_window_size((
+                                new_inner_size.width,
+                                new_inner_size.height,
+                            ));
+                        });
+                    }
+                    WindowEvent::CursorMoved { position, .. } => {
+                        let window_position =
+                            Vec2::new(position.x as f32, position.y as f32);
+                        let view_position =
+                            engine.viewport().window_to_view(window_position);
+                        engine.input_manager.process_cursor_moved(
+                            window_position,
+                            view_position,
                         );
                     }
+                    WindowEvent::MouseInput { state, button, .. } => {
+                        let pressed = state == ElementState::Pressed;
+                        engine
+                            .input_manager
+                            .process_mouse_button(button, pressed);
+                    }
                     WindowEvent::KeyboardInput {
                         input:
                             KeyboardInput {
@@ -152,16 +199,18 @@ impl Engine {
                     _ => (),
                 },
                 Event::MainEventsCleared => {
+                    engine.update_view();
                     engine.input_manager.update();
+                    engine.hot_reloader.poll();
                     game.update(&mut engine);
+                    engine
+                        .audio_manager
+                        .set_listener_position(engine.camera.position);
+                    engine.audio_manager.update(engine.delta_time);
                     engine.window_context.window().request_redraw();
                 }
                 Event::RedrawRequested(_) => {
-                    unsafe {
-                        gl::ClearColor(0.0, 0.0, 0.0, 1.0);
-                        gl::Clear(gl::COLOR_BUFFER_BIT);
-                    }
-                    game.render(&engine);
+                    engine.render_frame(&mut game);
                     engine.window_context.swap_buffers().unwrap();
                 }
                 _ => (),
@@ -172,4 +221,172 @@ impl Engine {
     pub fn delta_time(&self) -> f32 {
         self.delta_time
     }
+
+    /// Draw the game at a fixed design resolution fitted into the window
+    /// by the resize policy. `projection` and the camera switch to the new
+    /// view size on the next frame.
+    pub fn set_virtual_resolution(
+        &self,
+        width: u32,
+        height: u32,
+    ) {
+        self.update_viewport(|viewport| {
+            viewport.set_design_size(Some((width, height)))
+        });
+    }
+
+    /// Draw at the window size in logical pixels again.
+    pub fn clear_virtual_resolution(&self) {
+        self.update_viewport(|viewport| viewport.set_design_size(None));
+    }
+
+    pub fn virtual_resolution(&self) -> Option<(u32, u32)> {
+        self.viewport().design_size()
+    }
+
+    /// How the virtual resolution is fitted into the window. Defaults to
+    /// [`ResizePolicy::Letterbox`].
+    pub fn set_resize_policy(
+        &self,
+        policy: ResizePolicy,
+    ) {
+        self.update_viewport(|viewport| viewport.set_policy(policy));
+    }
+
+    pub fn resize_policy(&self) -> ResizePolicy {
+        self.viewport().policy()
+    }
+
+    /// Current mapping between window pixels and view units.
+    pub fn viewport(&self) -> Viewport {
+        self.viewport.get()
+    }
+
+    /// Convert a window position in physical pixels, e.g. from a cursor
+    /// event, to world coordinates through the viewport and the camera.
+    pub fn screen_to_world(
+        &self,
+        window_position: Vec2,
+    ) -> Vec2 {
+        let view_position = self.viewport().window_to_view(window_position);
+        self.camera.screen_to_world(view_position)
+    }
+
+    /// Cursor position in world coordinates.
+    pub fn mouse_world_position(&self) -> Vec2 {
+        self.camera
+            .screen_to_world(self.input_manager.mouse_position())
+    }
+
+    /// Color the frame is cleared to before `Game::render`.
+    pub fn set_clear_color(
+        &self,
+        color: Color,
+    ) {
+        self.clear_color.set(color);
+    }
+
+    fn update_viewport(
+        &self,
+        update: impl FnOnce(&mut Viewport),
+    ) {
+        let mut viewport = self.viewport.get();
+        update(&mut viewport);
+        self.viewport.set(viewport);
+    }
+
+    fn update_view(&mut self) {
+        let viewport = self.viewport();
+        let view_size = viewport.view_size();
+        self.projection = Mat4::orthographic_rh(
+            0.0,
+            view_size.x,
+            view_size.y,
+            0.0,
+            -1.0,
+            1.0,
+        );
+        self.camera.set_viewport_size(view_size);
+
+        // Keep the view position of a resting cursor in sync with the new
+        // mapping
+        let window_position = self.input_manager.mouse_window_position();
+        self.input_manager.process_cursor_moved(
+            window_position,
+            viewport.window_to_view(window_position),
+        );
+    }
+
+    /// Draw the game straight to the window, or into an offscreen target
+    /// that goes through the post-processing chain and upscaling.
+    fn render_frame<G: Game>(
+        &self,
+        game: &mut G,
+    ) {
+        let viewport = self.viewport();
+        let (window_width, window_height) = viewport.window_size();
+        let output = viewport.output_rect();
+        let offscreen = viewport.needs_offscreen()
+            || self.post_processor.has_active_effects();
+
+        if offscreen {
+            let view_size = viewport.view_size().ceil();
+            let (width, height) = (view_size.x as u32, view_size.y as u32);
+            let mut scene_target = self.scene_target.borrow_mut();
+            let target = match scene_target.take() {
+                Some(mut target) => {
+                    target.resize(width, height).map(|_| target)
+                }
+                None => {
+                    RenderTarget::new(width, height, TextureOptions::default())
+                }
+            };
+            match target {
+                Ok(target) => {
+                    *scene_target = Some(target);
+                }
+                Err(e) => {
+                    eprintln!("Failed to create scene render target: {}", e);
+                }
+            }
+        }
+
+        let scene_target = self.scene_target.borrow();
+        let clear = self.clear_color.get();
+        let target = scene_target.as_ref().filter(|_| offscreen);
+
+        // Black bars outside the output rect
+        unsafe {
+            gl::Viewport(0, 0, window_width as i32, window_height as i32);
+            gl::ClearColor(0.0, 0.0, 0.0, 1.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+        }
+        match target {
+            Some(target) => {
+                target.bind();
+                target.clear(clear.r, clear.g, clear.b, clear.a);
+            }
+            None => unsafe {
+                let [x, y, width, height] = output;
+                gl::Viewport(x, y, width, height);
+                gl::Enable(gl::SCISSOR_TEST);
+                gl::Scissor(x, y, width, height);
+                gl::ClearColor(clear.r, clear.g, clear.b, clear.a);
+                gl::Clear(gl::COLOR_BUFFER_BIT);
+                gl::Disable(gl::SCISSOR_TEST);
+            },
+        }
+
+        game.render(self);
+        self.debug_draw.render(
+            &self.sprite_renderer,
+            &self.text_renderer,
+            &self.projection,
+        );
+
+        if let Some(target) = target {
+            target.unbind();
+            self.post_processor.process(target.texture(), output);
+        }
+    }
 }
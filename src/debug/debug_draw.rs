@@ -0,0 +1,360 @@
+use glam::{Mat4, Vec2};
+use std::cell::{Cell, RefCell};
+
+use crate::sprite::sprite_renderer::{SpriteQuad, SpriteRenderer};
+use crate::text::{Font, TextRenderer, TextStyle};
+use crate::texture::options::TextureOptions;
+use crate::texture::texture::Texture;
+use crate::tile::properties::{CollisionType, Color};
+use crate::tile::{TileFlags, TileMap};
+
+const TILE_SOLID_COLOR: Color = Color::new(1.0, 0.2, 0.2, 1.0);
+const TILE_PLATFORM_COLOR: Color = Color::new(0.2, 0.6, 1.0, 1.0);
+const TILE_TRIGGER_COLOR: Color = Color::new(1.0, 0.9, 0.2, 1.0);
+const TILE_CUSTOM_COLOR: Color = Color::new(1.0, 0.2, 1.0, 1.0);
+
+struct DebugText {
+    text: String,
+    position: Vec2,
+    color: Color,
+}
+
+/// Immediate-mode debug shapes.
+///
+/// Shapes queued during a frame are drawn in one batch after
+/// `Game::render` and then cleared. Positions are in the same world space
+/// as sprites.
+pub struct DebugDraw {
+    enabled: Cell<bool>,
+    show_tile_collision: Cell<bool>,
+    line_width: Cell<f32>,
+    white_texture: Texture,
+    quads: RefCell<Vec<SpriteQuad>>,
+    texts: RefCell<Vec<DebugText>>,
+    font: RefCell<Option<Box<dyn Font>>>,
+}
+
+impl DebugDraw {
+    pub fn new() -> Result<Self, String> {
+        let white_texture = Texture::from_rgba_bytes(
+            1,
+            1,
+            &[255, 255, 255, 255],
+            TextureOptions::default(),
+        )?;
+
+        Ok(Self {
+            enabled: Cell::new(true),
+            show_tile_collision: Cell::new(false),
+            line_width: Cell::new(1.0),
+            white_texture,
+            quads: RefCell::new(Vec::new()),
+            texts: RefCell::new(Vec::new()),
+            font: RefCell::new(None),
+        })
+    }
+
+    /// While disabled every draw call is ignored.
+    pub fn set_enabled(
+        &self,
+        enabled: bool,
+    ) {
+        self.enabled.set(enabled);
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.get()
+    }
+
+    /// Toggle the collision overlay drawn by [`DebugDraw::tilemap_collision`].
+    pub fn set_tile_collision_overlay(
+        &self,
+        show: bool,
+    ) {
+        self.show_tile_collision.set(show);
+    }
+
+    pub fn is_tile_collision_overlay_shown(&self) -> bool {
+        self.show_tile_collision.get()
+    }
+
+    pub fn set_line_width(
+        &self,
+        width: f32,
+    ) {
+        self.line_width.set(width);
+    }
+
+    /// Font used by [`DebugDraw::text`]. Text is skipped until one is set.
+    pub fn set_font(
+        &self,
+        font: Box<dyn Font>,
+    ) {
+        *self.font.borrow_mut() = Some(font);
+    }
+
+    pub fn line(
+        &self,
+        from: Vec2,
+        to: Vec2,
+        color: Color,
+    ) {
+        if !self.enabled.get() {
+            return;
+        }
+
+        let direction = (to - from).normalize_or_zero();
+        let normal = direction.perp() * (self.line_width.get() * 0.5);
+        self.push_polygon(
+            [from + normal, to + normal, to - normal, from - normal],
+            color,
+        );
+    }
+
+    pub fn rect(
+        &self,
+        position: Vec2,
+        size: Vec2,
+        color: Color,
+    ) {
+        let top_right = position + Vec2::new(size.x, 0.0);
+        let bottom_right = position + size;
+        let bottom_left = position + Vec2::new(0.0, size.y);
+
+        self.line(position, top_right, color);
+        self.line(top_right, bottom_right, color);
+        self.line(bottom_right, bottom_left, color);
+        self.line(bottom_left, position, color);
+    }
+
+    pub fn filled_rect(
+        &self,
+        position: Vec2,
+        size: Vec2,
+        color: Color,
+    ) {
+        if !self.enabled.get() {
+            return;
+        }
+
+        let max = position + size;
+        self.push_polygon(
+            [
+                position,
+                Vec2::new(max.x, position.y),
+                max,
+                Vec2::new(position.x, max.y),
+            ],
+            color,
+        );
+    }
+
+    pub fn circle(
+        &self,
+        center: Vec2,
+        radius: f32,
+        color: Color,
+    ) {
+        let points = Self::circle_points(center, radius);
+        for (i, &point) in points.iter().enumerate() {
+            self.line(point, points[(i + 1) % points.len()], color);
+        }
+    }
+
+    pub fn filled_circle(
+        &self,
+        center: Vec2,
+        radius: f32,
+        color: Color,
+    ) {
+        if !self.enabled.get() {
+            return;
+        }
+
+        let points = Self::circle_points(center, radius);
+        for (i, &point) in points.iter().enumerate() {
+            let next = points[(i + 1) % points.len()];
+            self.push_polygon([center, point, next, next], color);
+        }
+    }
+
+    /// Line from `from` to `to` with a filled head at `to`.
+    pub fn arrow(
+        &self,
+        from: Vec2,
+        to: Vec2,
+        color: Color,
+    ) {
+        if !self.enabled.get() {
+            return;
+        }
+
+        let length = (to - from).length();
+        if length <= f32::EPSILON {
+            return;
+        }
+
+        let direction = (to - from) / length;
+        let head_length =
+            (length * 0.25).min(self.line_width.get() * 8.0 + 4.0);
+        let head_base = to - direction * head_length;
+        let head_side = direction.perp() * (head_length * 0.5);
+
+        self.line(from, head_base, color);
+        self.push_polygon(
+            [to, head_base + head_side, head_base - head_side, to],
+            color,
+        );
+    }
+
+    /// Queue `text` with its top left corner at `position`.
+    pub fn text(
+        &self,
+        position: Vec2,
+        text: &str,
+        color: Color,
+    ) {
+        if !self.enabled.get() {
+            return;
+        }
+
+        self.texts.borrow_mut().push(DebugText {
+            text: text.to_string(),
+            position,
+            color,
+        });
+    }
+
+    /// Outline the collision shape of every collidable tile in `tilemap`
+    /// while the tile collision overlay is enabled.
+    pub fn tilemap_collision(
+        &self,
+        tilemap: &TileMap,
+    ) {
+        if !self.enabled.get() || !self.show_tile_collision.get() {
+            return;
+        }
+
+        let size = tilemap.world_tile_size();
+
+        for layer in tilemap.layers.values() {
+            for (y, row) in layer.tiles.iter().enumerate() {
+                for x in 0..row.len() {
+                    let Some(properties) =
+                        tilemap.properties_in(layer, x as u32, y as u32)
+                    else {
+                        continue;
+                    };
+                    let physics = &properties.physics;
+                    if !physics.collision {
+                        continue;
+                    }
+
+                    let origin = tilemap.tile_to_world(x as u32, y as u32);
+                    match &physics.collision_type {
+                        CollisionType::None => (),
+                        CollisionType::Full => {
+                            self.rect(origin, size, TILE_SOLID_COLOR)
+                        }
+                        CollisionType::Slope(_) => {
+                            let flags = layer
+                                .get_tile(x as u32, y as u32)
+                                .map_or(TileFlags::NONE, |tile| tile.flags);
+                            if let Some(outline) = physics
+                                .collision_type
+                                .slope_outline(size, flags)
+                            {
+                                self.outline(origin, &outline)
+                            }
+                        }
+                        CollisionType::Platform => self.line(
+                            origin,
+                            origin + Vec2::new(size.x, 0.0),
+                            TILE_PLATFORM_COLOR,
+                        ),
+                        CollisionType::Trigger => {
+                            self.rect(origin, size, TILE_TRIGGER_COLOR)
+                        }
+                        CollisionType::Custom(_) => {
+                            self.rect(origin, size, TILE_CUSTOM_COLOR)
+                        }
+                    }
+
+                    if physics.one_way_platform {
+                        self.arrow(
+                            origin + size * Vec2::new(0.5, 0.75),
+                            origin + size * Vec2::new(0.5, 0.25),
+                            TILE_PLATFORM_COLOR,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// Draw every queued shape and text, then clear the queue.
+    pub fn render(
+        &self,
+        sprite_renderer: &SpriteRenderer,
+        text_renderer: &TextRenderer,
+        projection: &Mat4,
+    ) {
+        let mut quads = self.quads.borrow_mut();
+        sprite_renderer.draw_quads(&self.white_texture, &quads, projection);
+        quads.clear();
+
+        let mut texts = self.texts.borrow_mut();
+        if let Some(font) = self.font.borrow_mut().as_mut() {
+            for text in texts.iter() {
+                text_renderer.draw_text(
+                    sprite_renderer,
+                    font.as_mut(),
+                    &text.text,
+                    text.position,
+                    &TextStyle::default().with_color(text.color),
+                    projection,
+                );
+            }
+        }
+        texts.clear();
+    }
+
+    /// Closed polygon through `points`, relative to `origin`.
+    fn outline(
+        &self,
+        origin: Vec2,
+        points: &[Vec2],
+    ) {
+        for (index, &point) in points.iter().enumerate() {
+            let next = points[(index + 1) % points.len()];
+            self.line(origin + point, origin + next, TILE_SOLID_COLOR);
+        }
+    }
+
+    fn circle_points(
+        center: Vec2,
+        radius: f32,
+    ) -> Vec<Vec2> {
+        let segments = ((radius * 0.5) as usize).clamp(12, 64);
+        (0..segments)
+            .map(|i| {
+                let angle = i as f32 / segments as f32 * std::f32::consts::TAU;
+                center + Vec2::from_angle(angle) * radius
+            })
+            .collect()
+    }
+
+    /// Queue a convex polygon with up to four corners. Triangles repeat the
+    /// last corner.
+    fn push_polygon(
+        &self,
+        corners: [Vec2; 4],
+        color: Color,
+    ) {
+        self.quads.borrow_mut().push(SpriteQuad {
+            corners,
+            uvs: [Vec2::splat(0.5); 4],
+            color: color.to_vec4(),
+        });
+    }
+}
@@ -0,0 +1,3 @@
+pub mod debug_draw;
+
+pub use debug_draw::*;
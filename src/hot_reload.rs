@@ -0,0 +1,327 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::rc::{Rc, Weak};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::shader::ShaderProgram;
+use crate::texture::texture::Texture;
+use crate::tile::{TileMap, Tileset};
+
+/// GPU asset that can rebuild itself from its source files without changing
+/// the handle other objects hold.
+pub(crate) trait Reloadable {
+    fn source_paths(&self) -> Vec<PathBuf>;
+    fn reload(&self) -> Result<(), String>;
+}
+
+enum WatchTarget {
+    /// Reloaded by the watcher as soon as a change is seen.
+    Asset(Weak<dyn Reloadable>),
+    /// Only recorded as changed; the owner applies the reload (tilesets).
+    Path,
+}
+
+struct WatchedFiles {
+    paths: Vec<PathBuf>,
+    modified: Vec<Option<SystemTime>>,
+    target: WatchTarget,
+}
+
+/// Polls the modification time of watched asset files and swaps changed
+/// textures, shaders and tilesets in place.
+///
+/// The engine calls [`HotReloader::poll`] once per frame before
+/// `Game::update`. Textures and shaders are reloaded directly; tilesets are
+/// owned by a `TileMap`, so the game applies those with
+/// [`HotReloader::reload_tileset`]. Failed reloads are logged and the
+/// previous asset keeps being used.
+pub struct HotReloader {
+    enabled: Cell<bool>,
+    poll_interval: Cell<Duration>,
+    last_poll: Cell<Instant>,
+    watched: RefCell<Vec<WatchedFiles>>,
+    changed: RefCell<HashSet<PathBuf>>,
+}
+
+impl Default for HotReloader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HotReloader {
+    pub fn new() -> Self {
+        Self {
+            enabled: Cell::new(cfg!(debug_assertions)),
+            poll_interval: Cell::new(Duration::from_millis(500)),
+            last_poll: Cell::new(Instant::now()),
+            watched: RefCell::new(Vec::new()),
+            changed: RefCell::new(HashSet::new()),
+        }
+    }
+
+    /// Hot reloading is enabled by default in debug builds only.
+    pub fn set_enabled(
+        &self,
+        enabled: bool,
+    ) {
+        self.enabled.set(enabled);
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.get()
+    }
+
+    pub fn set_poll_interval(
+        &self,
+        interval: Duration,
+    ) {
+        self.poll_interval.set(interval);
+    }
+
+    /// Watch the image file of a texture. Textures without a source file are
+    /// ignored.
+    pub fn watch_texture(
+        &self,
+        texture: &Texture,
+    ) {
+        let inner: Rc<dyn Reloadable> = texture.inner().clone();
+        self.watch_asset(&inner);
+    }
+
+    /// Watch the vertex and fragment sources of a shader program built with
+    /// `ShaderProgram::from_files`.
+    pub fn watch_shader(
+        &self,
+        shader: &ShaderProgram,
+    ) {
+        let inner: Rc<dyn Reloadable> = shader.inner().clone();
+        self.watch_asset(&inner);
+    }
+
+    /// Watch a tileset's JSON file and its texture.
+    pub fn watch_tileset(
+        &self,
+        tileset: &Tileset,
+    ) {
+        self.watch_texture(tileset.texture());
+
+        let path = tileset.source_path().to_path_buf();
+        let mut watched = self.watched.borrow_mut();
+        let already_watched = watched.iter().any(|entry| {
+            matches!(entry.target, WatchTarget::Path) && entry.paths[0] == path
+        });
+        if !already_watched {
+            watched.push(WatchedFiles {
+                modified: vec![Self::modified_time(&path)],
+                paths: vec![path],
+                target: WatchTarget::Path,
+            });
+        }
+    }
+
+    /// Watch every tileset of `tilemap`.
+    pub fn watch_tilemap(
+        &self,
+        tilemap: &TileMap,
+    ) {
+        for entry in &tilemap.tilesets {
+            self.watch_tileset(&entry.tileset);
+        }
+    }
+
+    /// Reload the tilesets of `tilemap` whose JSON or image changed during
+    /// the last poll. Returns `true` if any was reloaded.
+    ///
+    /// The reloaded tilesets only replace the old ones if their gid ranges
+    /// still fit the map; otherwise the map is left untouched.
+    pub fn reload_tilemap(
+        &self,
+        tilemap: &mut TileMap,
+    ) -> bool {
+        let mut reloaded = Vec::new();
+        for (index, entry) in tilemap.tilesets.iter().enumerate() {
+            if !self.is_tileset_changed(&entry.tileset) {
+                continue;
+            }
+            match entry.tileset.reloaded() {
+                Ok(tileset) => reloaded.push((index, tileset)),
+                Err(e) => Self::report_failure(&entry.tileset, &e),
+            }
+        }
+        if reloaded.is_empty() {
+            return false;
+        }
+
+        for (index, tileset) in &mut reloaded {
+            std::mem::swap(&mut tilemap.tilesets[*index].tileset, tileset);
+        }
+        if let Err(e) = tilemap.check_tileset_ranges() {
+            // Put the previous tilesets back
+            for (index, tileset) in &mut reloaded {
+                std::mem::swap(&mut tilemap.tilesets[*index].tileset, tileset);
+            }
+            eprintln!("Failed to reload tilesets: {}", e);
+            return false;
+        }
+
+        for (index, _) in &reloaded {
+            self.report_reload(&tilemap.tilesets[*index].tileset);
+        }
+        // Tile properties may have changed under cached path grids
+        tilemap.mark_changed();
+        true
+    }
+
+    /// Reload `tileset` if its JSON or image changed during the last poll.
+    /// A new image may have a different size, so the tile UVs are rebuilt
+    /// either way. For a tileset of a `TileMap`, prefer
+    /// [`HotReloader::reload_tilemap`], which also checks that the gid
+    /// ranges still fit, or call `TileMap::mark_changed` afterwards.
+    ///
+    /// Returns `true` when the tileset was reloaded.
+    pub fn reload_tileset(
+        &self,
+        tileset: &mut Tileset,
+    ) -> bool {
+        if !self.is_tileset_changed(tileset) {
+            return false;
+        }
+
+        match tileset.reload() {
+            Ok(()) => {
+                self.report_reload(tileset);
+                true
+            }
+            Err(e) => {
+                Self::report_failure(tileset, &e);
+                false
+            }
+        }
+    }
+
+    /// Returns `true` if `path` changed during the last poll.
+    pub fn is_changed(
+        &self,
+        path: &Path,
+    ) -> bool {
+        self.changed.borrow().contains(path)
+    }
+
+    /// Check watched files and reload the ones that changed.
+    pub fn poll(&self) {
+        self.changed.borrow_mut().clear();
+
+        if !self.enabled.get()
+            || self.last_poll.get().elapsed() < self.poll_interval.get()
+        {
+            return;
+        }
+        self.last_poll.set(Instant::now());
+
+        let mut changed = self.changed.borrow_mut();
+        self.watched.borrow_mut().retain_mut(|entry| {
+            let asset = match &entry.target {
+                WatchTarget::Asset(weak) => match weak.upgrade() {
+                    Some(asset) => Some(asset),
+                    // The asset was dropped, stop watching it
+                    None => return false,
+                },
+                WatchTarget::Path => None,
+            };
+
+            let mut is_changed = false;
+            for (path, modified) in
+                entry.paths.iter().zip(entry.modified.iter_mut())
+            {
+                let current = Self::modified_time(path);
+                if current != *modified {
+                    *modified = current;
+                    is_changed = true;
+                    changed.insert(path.clone());
+                }
+            }
+
+            if let (true, Some(asset)) = (is_changed, asset) {
+                let paths = entry
+                    .paths
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                match asset.reload() {
+                    Ok(()) => println!("Reloaded: {}", paths),
+                    Err(e) => eprintln!("Failed to reload {}: {}", paths, e),
+                }
+            }
+
+            true
+        });
+    }
+
+    fn is_tileset_changed(
+        &self,
+        tileset: &Tileset,
+    ) -> bool {
+        let image_changed = tileset
+            .texture()
+            .path()
+            .is_some_and(|path| self.is_changed(path));
+        image_changed || self.is_changed(tileset.source_path())
+    }
+
+    fn report_reload(
+        &self,
+        tileset: &Tileset,
+    ) {
+        println!("Reloaded tileset: {}", tileset.source_path().display());
+        // The image path may have changed, so make sure the current texture
+        // is watched.
+        self.watch_texture(tileset.texture());
+    }
+
+    fn report_failure(
+        tileset: &Tileset,
+        error: &str,
+    ) {
+        eprintln!(
+            "Failed to reload tileset {}: {}",
+            tileset.source_path().display(),
+            error
+        );
+    }
+
+    fn watch_asset(
+        &self,
+        asset: &Rc<dyn Reloadable>,
+    ) {
+        let paths = asset.source_paths();
+        if paths.is_empty() {
+            return;
+        }
+
+        let mut watched = self.watched.borrow_mut();
+        let already_watched = watched.iter().any(|entry| match &entry.target {
+            WatchTarget::Asset(weak) => {
+                weak.upgrade().is_some_and(|other| Rc::ptr_eq(&other, asset))
+            }
+            WatchTarget::Path => false,
+        });
+        if already_watched {
+            return;
+        }
+
+        watched.push(WatchedFiles {
+            modified: paths.iter().map(|p| Self::modified_time(p)).collect(),
+            paths,
+            target: WatchTarget::Asset(Rc::downgrade(asset)),
+        });
+    }
+
+    fn modified_time(path: &Path) -> Option<SystemTime> {
+        std::fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+}
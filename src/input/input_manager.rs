@@ -1,5 +1,7 @@
-use glutin::event::VirtualKeyCode;
+use glam::Vec2;
+use glutin::event::{MouseButton, VirtualKeyCode};
 use std::collections::HashMap;
+use std::hash::Hash;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum KeyState {
@@ -23,6 +25,16 @@ pub struct InputManager {
     key_states: HashMap<VirtualKeyCode, KeyState>,
     action_mappings: HashMap<InputAction, Vec<VirtualKeyCode>>,
     previous_keys: HashMap<VirtualKeyCode, bool>,
+    mouse_states: HashMap<MouseButton, KeyState>,
+    previous_mouse_buttons: HashMap<MouseButton, bool>,
+    mouse_position: Vec2,
+    mouse_window_position: Vec2,
+}
+
+impl Default for InputManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl InputManager {
@@ -53,25 +65,20 @@ impl InputManager {
             key_states: HashMap::new(),
             action_mappings,
             previous_keys: HashMap::new(),
+            mouse_states: HashMap::new(),
+            previous_mouse_buttons: HashMap::new(),
+            mouse_position: Vec2::ZERO,
+            mouse_window_position: Vec2::ZERO,
         }
     }
 
     pub fn update(&mut self) {
         // Update the current state based on the previous frame's key states
-        for (key, state) in self.key_states.iter_mut() {
-            let was_pressed = *self.previous_keys.get(key).unwrap_or(&false);
-            let is_pressed =
-                matches!(state, KeyState::Pressed | KeyState::Held);
-
-            *state = match (was_pressed, is_pressed) {
-                (true, true) => KeyState::Held,
-                (false, true) => KeyState::Pressed,
-                (true, false) => KeyState::Released,
-                (false, false) => KeyState::Idle,
-            };
-
-            self.previous_keys.insert(*key, is_pressed);
-        }
+        advance_states(&mut self.key_states, &mut self.previous_keys);
+        advance_states(
+            &mut self.mouse_states,
+            &mut self.previous_mouse_buttons,
+        );
     }
 
     pub fn process_keyboard_input(
@@ -79,19 +86,61 @@ impl InputManager {
         keycode: VirtualKeyCode,
         pressed: bool,
     ) {
-        let current_state =
-            self.key_states.entry(keycode).or_insert(KeyState::Idle);
-        *current_state = if pressed {
-            match *current_state {
-                KeyState::Idle | KeyState::Released => KeyState::Pressed,
-                KeyState::Pressed | KeyState::Held => KeyState::Held,
-            }
-        } else {
-            match *current_state {
-                KeyState::Pressed | KeyState::Held => KeyState::Released,
-                KeyState::Released | KeyState::Idle => KeyState::Idle,
-            }
-        };
+        apply_press(&mut self.key_states, keycode, pressed);
+    }
+
+    pub fn process_mouse_button(
+        &mut self,
+        button: MouseButton,
+        pressed: bool,
+    ) {
+        apply_press(&mut self.mouse_states, button, pressed);
+    }
+
+    /// Record the cursor position, both in physical window pixels and
+    /// already converted to view units by the engine's viewport.
+    pub fn process_cursor_moved(
+        &mut self,
+        window_position: Vec2,
+        view_position: Vec2,
+    ) {
+        self.mouse_window_position = window_position;
+        self.mouse_position = view_position;
+    }
+
+    /// Cursor position in view units, the same space `Engine::projection`
+    /// covers. Use `Engine::mouse_world_position` for world coordinates.
+    pub fn mouse_position(&self) -> Vec2 {
+        self.mouse_position
+    }
+
+    /// Cursor position in physical pixels from the top left of the window.
+    pub fn mouse_window_position(&self) -> Vec2 {
+        self.mouse_window_position
+    }
+
+    pub fn is_mouse_button_down(
+        &self,
+        button: MouseButton,
+    ) -> bool {
+        matches!(
+            self.mouse_states.get(&button),
+            Some(KeyState::Pressed) | Some(KeyState::Held)
+        )
+    }
+
+    pub fn is_mouse_button_just_pressed(
+        &self,
+        button: MouseButton,
+    ) -> bool {
+        matches!(self.mouse_states.get(&button), Some(KeyState::Pressed))
+    }
+
+    pub fn is_mouse_button_just_released(
+        &self,
+        button: MouseButton,
+    ) -> bool {
+        matches!(self.mouse_states.get(&button), Some(KeyState::Released))
     }
 
     pub fn is_action_active(
@@ -144,3 +193,41 @@ impl InputManager {
         self.action_mappings.insert(action, keys);
     }
 }
+
+fn advance_states<K: Copy + Eq + Hash>(
+    states: &mut HashMap<K, KeyState>,
+    previous: &mut HashMap<K, bool>,
+) {
+    for (key, state) in states.iter_mut() {
+        let was_pressed = *previous.get(key).unwrap_or(&false);
+        let is_pressed = matches!(state, KeyState::Pressed | KeyState::Held);
+
+        *state = match (was_pressed, is_pressed) {
+            (true, true) => KeyState::Held,
+            (false, true) => KeyState::Pressed,
+            (true, false) => KeyState::Released,
+            (false, false) => KeyState::Idle,
+        };
+
+        previous.insert(*key, is_pressed);
+    }
+}
+
+fn apply_press<K: Eq + Hash>(
+    states: &mut HashMap<K, KeyState>,
+    key: K,
+    pressed: bool,
+) {
+    let current_state = states.entry(key).or_insert(KeyState::Idle);
+    *current_state = if pressed {
+        match *current_state {
+            KeyState::Idle | KeyState::Released => KeyState::Pressed,
+            KeyState::Pressed | KeyState::Held => KeyState::Held,
+        }
+    } else {
+        match *current_state {
+            KeyState::Pressed | KeyState::Held => KeyState::Released,
+            KeyState::Released | KeyState::Idle => KeyState::Idle,
+        }
+    };
+}
@@ -1,20 +1,53 @@
 pub use gl;
 
+pub mod audio;
+pub mod camera;
 pub mod core;
+pub mod debug;
+pub mod hot_reload;
 pub mod input;
+pub mod lighting;
+pub mod particle;
+pub mod random;
+pub mod render;
+pub mod shader;
 pub mod sprite;
+pub mod text;
 pub mod texture;
 pub mod tile;
 
+pub use audio::{
+    Attenuation, AudioManager, Bus, Falloff, MusicSettings, PlaySettings, Sound,
+};
+pub use camera::Camera;
 pub use core::Engine;
 pub use core::Game;
+pub use debug::DebugDraw;
+pub use hot_reload::HotReloader;
 pub use input::input_manager::InputAction;
 pub use input::input_manager::InputManager;
+pub use lighting::{LightId, LightRenderer, PointLight};
+pub use particle::{
+    ParticleEmitter, ParticleEmitterDefinition, ParticleSystem,
+};
+pub use random::Random;
+pub use render::{
+    PostEffect, PostProcessor, RenderTarget, ResizePolicy, Viewport,
+};
+pub use shader::{Material, ShaderProgram, UniformValue};
 pub use sprite::animation::AnimationProvider;
 pub use sprite::animation::AnimationSequence;
 pub use sprite::animation_sprite::AnimatedSprite;
 pub use sprite::sprite::{Rect, Sprite};
-pub use sprite::sprite_renderer::SpriteRenderer;
+pub use sprite::sprite_renderer::{SpriteQuad, SpriteRenderer};
+pub use text::{
+    BitmapFont, Font, TextAlign, TextRenderer, TextStyle, TrueTypeFont,
+};
+pub use texture::atlas::{AtlasBuilder, TextureAtlas};
+pub use texture::options::{
+    BakedColorKey, FilterMode, TextureOptions, WrapMode,
+};
+pub use texture::palette::{IndexedImage, Palette, PaletteTexture};
 pub use texture::texture::Texture;
 
 pub use tile::properties::*;
@@ -0,0 +1,59 @@
+use glam::Vec2;
+
+use crate::tile::properties::Color;
+
+/// Handle to a light added with `LightRenderer::add_light`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LightId(pub(crate) u64);
+
+/// Light that fades out towards `radius` around `position`.
+#[derive(Debug, Clone, Copy)]
+pub struct PointLight {
+    pub position: Vec2,
+    pub color: Color,
+    /// Brightness multiplier applied to `color`.
+    pub intensity: f32,
+    /// Distance in world units at which the light reaches zero.
+    pub radius: f32,
+    /// Blocked by occluders when shadows are enabled.
+    pub casts_shadows: bool,
+}
+
+impl PointLight {
+    pub fn new(
+        position: Vec2,
+        radius: f32,
+    ) -> Self {
+        Self {
+            position,
+            color: Color::WHITE,
+            intensity: 1.0,
+            radius,
+            casts_shadows: true,
+        }
+    }
+
+    pub fn with_color(
+        mut self,
+        color: Color,
+    ) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_intensity(
+        mut self,
+        intensity: f32,
+    ) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    pub fn with_shadows(
+        mut self,
+        casts_shadows: bool,
+    ) -> Self {
+        self.casts_shadows = casts_shadows;
+        self
+    }
+}
@@ -0,0 +1,358 @@
+use glam::{Mat4, Vec2, Vec4};
+
+use super::light::{LightId, PointLight};
+use super::shadow::{self, Segment};
+use crate::render::RenderTarget;
+use crate::sprite::sprite_renderer::{SpriteQuad, SpriteRenderer};
+use crate::texture::options::{FilterMode, TextureOptions};
+use crate::texture::texture::Texture;
+use crate::tile::properties::{CollisionType, Color, PropertyValue};
+use crate::tile::{Orientation, TileMap};
+
+// Resolution of the radial falloff texture shared by all lights
+const FALLOFF_TEXTURE_SIZE: u32 = 64;
+// Radius of tile lights without a `light_radius` property, in tiles
+const DEFAULT_TILE_LIGHT_RADIUS: f32 = 4.0;
+
+/// 2D lighting pass drawn on top of the world.
+///
+/// Lights are accumulated additively into a light map that starts out as
+/// the ambient color, which is then multiplied over everything drawn so far.
+/// Call [`LightRenderer::render`] after the tilemap and sprites and before
+/// UI that should stay unlit.
+///
+/// Tiles with `VisualProperties::light_source` become point lights and
+/// tiles with full collision block light when shadows are enabled.
+pub struct LightRenderer {
+    ambient: Color,
+    shadows_enabled: bool,
+    lights: Vec<(LightId, PointLight)>,
+    next_light_id: u64,
+    tile_lights: Vec<PointLight>,
+    tile_occluders: Vec<Segment>,
+    occluders: Vec<Segment>,
+    falloff_texture: Texture,
+    light_map: RenderTarget,
+    quads: Vec<SpriteQuad>,
+}
+
+impl LightRenderer {
+    pub fn new() -> Result<Self, String> {
+        let smooth = TextureOptions::default()
+            .with_filter(FilterMode::Linear, FilterMode::Linear);
+
+        Ok(Self {
+            ambient: Color::new(0.2, 0.2, 0.25, 1.0),
+            shadows_enabled: true,
+            lights: Vec::new(),
+            next_light_id: 0,
+            tile_lights: Vec::new(),
+            tile_occluders: Vec::new(),
+            occluders: Vec::new(),
+            falloff_texture: Self::create_falloff_texture(smooth)?,
+            light_map: RenderTarget::new(1, 1, smooth)?,
+            quads: Vec::new(),
+        })
+    }
+
+    /// Light level of areas no light reaches. White disables darkening.
+    pub fn set_ambient(
+        &mut self,
+        ambient: Color,
+    ) {
+        self.ambient = ambient;
+    }
+
+    pub fn ambient(&self) -> Color {
+        self.ambient
+    }
+
+    pub fn set_shadows_enabled(
+        &mut self,
+        enabled: bool,
+    ) {
+        self.shadows_enabled = enabled;
+    }
+
+    pub fn shadows_enabled(&self) -> bool {
+        self.shadows_enabled
+    }
+
+    pub fn add_light(
+        &mut self,
+        light: PointLight,
+    ) -> LightId {
+        let id = LightId(self.next_light_id);
+        self.next_light_id += 1;
+        self.lights.push((id, light));
+        id
+    }
+
+    pub fn light_mut(
+        &mut self,
+        id: LightId,
+    ) -> Option<&mut PointLight> {
+        self.lights
+            .iter_mut()
+            .find(|(light_id, _)| *light_id == id)
+            .map(|(_, light)| light)
+    }
+
+    pub fn remove_light(
+        &mut self,
+        id: LightId,
+    ) {
+        self.lights.retain(|(light_id, _)| *light_id != id);
+    }
+
+    /// Remove every light added with [`LightRenderer::add_light`]. Tile
+    /// lights are kept.
+    pub fn clear_lights(&mut self) {
+        self.lights.clear();
+    }
+
+    /// Block light with the rectangle `min..max`, e.g. a moving platform.
+    pub fn add_occluder(
+        &mut self,
+        min: Vec2,
+        max: Vec2,
+    ) {
+        self.occluders.extend(shadow::rect_edges(min, max));
+    }
+
+    /// Remove occluders added with [`LightRenderer::add_occluder`].
+    pub fn clear_occluders(&mut self) {
+        self.occluders.clear();
+    }
+
+    /// Rebuild tile lights and occluders from `tilemap`. Call again after
+    /// tiles change.
+    ///
+    /// A light tile shines from its center with `light_color` scaled by
+    /// `light_intensity`. Its radius comes from the `light_radius` custom
+    /// property in tiles, defaulting to four. Lights inside solid tiles do
+    /// not cast shadows, since their own tile would block them.
+    pub fn set_tilemap(
+        &mut self,
+        tilemap: &TileMap,
+    ) {
+        let tile_size = tilemap.world_tile_size();
+        let mut solid =
+            vec![vec![false; tilemap.width as usize]; tilemap.height as usize];
+        let mut lights = Vec::new();
+
+        for layer in tilemap.layers.values() {
+            for (y, row) in layer.tiles.iter().enumerate() {
+                for x in 0..row.len() {
+                    let Some(properties) =
+                        tilemap.properties_in(layer, x as u32, y as u32)
+                    else {
+                        continue;
+                    };
+                    let physics = &properties.physics;
+                    if physics.collision
+                        && physics.collision_type == CollisionType::Full
+                    {
+                        if let Some(cell) =
+                            solid.get_mut(y).and_then(|row| row.get_mut(x))
+                        {
+                            *cell = true;
+                        }
+                    }
+
+                    let visual = &properties.visual;
+                    if visual.light_source {
+                        let radius = match properties
+                            .custom_properties
+                            .get("light_radius")
+                        {
+                            Some(PropertyValue::Float(radius)) => *radius,
+                            Some(PropertyValue::Int(radius)) => *radius as f32,
+                            _ => DEFAULT_TILE_LIGHT_RADIUS,
+                        };
+                        let center = tilemap.tile_center(x as i32, y as i32);
+                        lights.push((
+                            x,
+                            y,
+                            PointLight::new(center, radius * tile_size.x)
+                                .with_color(
+                                    visual.light_color.unwrap_or(Color::WHITE),
+                                )
+                                .with_intensity(visual.light_intensity),
+                        ));
+                    }
+                }
+            }
+        }
+
+        self.tile_lights = lights
+            .into_iter()
+            .map(|(x, y, light)| {
+                let in_solid = solid
+                    .get(y)
+                    .and_then(|row| row.get(x))
+                    .copied()
+                    .unwrap_or(false);
+                light.with_shadows(!in_solid)
+            })
+            .collect();
+        // Shadow edges follow grid cell sides, so only orthogonal maps get
+        // tile occluders
+        self.tile_occluders = if tilemap.orientation == Orientation::Orthogonal
+        {
+            shadow::occluder_edges(&solid, tile_size)
+        } else {
+            Vec::new()
+        };
+    }
+
+    /// Texture holding the light map of the last frame.
+    pub fn light_map(&self) -> &Texture {
+        self.light_map.texture()
+    }
+
+    /// Draw the light map for the current viewport and multiply it over
+    /// what was drawn so far.
+    pub fn render(
+        &mut self,
+        renderer: &SpriteRenderer,
+        view_projection: &Mat4,
+    ) {
+        let mut viewport = [0; 4];
+        unsafe {
+            gl::GetIntegerv(gl::VIEWPORT, viewport.as_mut_ptr());
+        }
+        let (width, height) = (viewport[2].max(1), viewport[3].max(1));
+        if let Err(e) = self.light_map.resize(width as u32, height as u32) {
+            eprintln!("Failed to resize light map: {}", e);
+            return;
+        }
+
+        self.build_light_quads(view_projection);
+
+        self.light_map.bind();
+        self.light_map.clear(
+            self.ambient.r,
+            self.ambient.g,
+            self.ambient.b,
+            1.0,
+        );
+        unsafe {
+            gl::BlendFunc(gl::ONE, gl::ONE);
+        }
+        renderer.draw_quads(
+            &self.falloff_texture,
+            &self.quads,
+            view_projection,
+        );
+        self.light_map.unbind();
+
+        // Multiply the light map over the scene. The quad is given in
+        // normalized device coordinates and framebuffer textures start at
+        // the bottom left.
+        let screen = SpriteQuad::new(
+            Vec2::new(-1.0, 1.0),
+            Vec2::new(1.0, -1.0),
+            Vec2::new(0.0, 1.0),
+            Vec2::new(1.0, 0.0),
+            Vec4::ONE,
+        );
+        unsafe {
+            gl::BlendFunc(gl::DST_COLOR, gl::ZERO);
+        }
+        renderer.draw_quads(
+            self.light_map.texture(),
+            &[screen],
+            &Mat4::IDENTITY,
+        );
+        unsafe {
+            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+        }
+    }
+
+    fn build_light_quads(
+        &mut self,
+        view_projection: &Mat4,
+    ) {
+        // World space area covered by the viewport, to skip lights that
+        // cannot be seen
+        let inverse = view_projection.inverse();
+        let corner_a = inverse.project_point3(glam::Vec3::new(-1.0, -1.0, 0.0));
+        let corner_b = inverse.project_point3(glam::Vec3::new(1.0, 1.0, 0.0));
+        let view_min = corner_a.truncate().min(corner_b.truncate());
+        let view_max = corner_a.truncate().max(corner_b.truncate());
+
+        self.quads.clear();
+        let occluders: Vec<Segment> = self
+            .tile_occluders
+            .iter()
+            .chain(&self.occluders)
+            .copied()
+            .collect();
+        let lights = self
+            .tile_lights
+            .iter()
+            .chain(self.lights.iter().map(|(_, light)| light));
+
+        for light in lights {
+            let reach = Vec2::splat(light.radius);
+            let visible = (light.position + reach).cmpge(view_min).all()
+                && (light.position - reach).cmple(view_max).all();
+            if !visible || light.radius <= 0.0 || light.intensity <= 0.0 {
+                continue;
+            }
+
+            let color = (light.color.to_vec4() * light.intensity)
+                .truncate()
+                .extend(1.0);
+            let uv = |point: Vec2| {
+                (point - light.position) / (light.radius * 2.0)
+                    + Vec2::splat(0.5)
+            };
+
+            if !(self.shadows_enabled && light.casts_shadows) {
+                self.quads.push(SpriteQuad::new(
+                    light.position - reach,
+                    light.position + reach,
+                    Vec2::ZERO,
+                    Vec2::ONE,
+                    color,
+                ));
+                continue;
+            }
+
+            let polygon = shadow::visibility_polygon(
+                light.position,
+                light.radius,
+                &occluders,
+            );
+            for (i, &point) in polygon.iter().enumerate() {
+                let next = polygon[(i + 1) % polygon.len()];
+                self.quads.push(SpriteQuad {
+                    corners: [light.position, point, next, next],
+                    uvs: [uv(light.position), uv(point), uv(next), uv(next)],
+                    color,
+                });
+            }
+        }
+    }
+
+    /// White radial gradient fading to black at the edge of the texture.
+    fn create_falloff_texture(
+        options: TextureOptions
+    ) -> Result<Texture, String> {
+        let size = FALLOFF_TEXTURE_SIZE;
+        let center = size as f32 * 0.5;
+        let mut pixels = Vec::with_capacity((size * size * 4) as usize);
+        for y in 0..size {
+            for x in 0..size {
+                let offset = Vec2::new(x as f32 + 0.5, y as f32 + 0.5)
+                    - Vec2::splat(center);
+                let falloff = (1.0 - offset.length() / center).max(0.0);
+                let value = (falloff * falloff * 255.0).round() as u8;
+                pixels.extend_from_slice(&[value, value, value, 255]);
+            }
+        }
+        Texture::from_rgba_bytes(size, size, &pixels, options)
+    }
+}
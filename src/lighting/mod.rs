@@ -0,0 +1,6 @@
+pub mod light;
+pub mod light_renderer;
+mod shadow;
+
+pub use light::{LightId, PointLight};
+pub use light_renderer::LightRenderer;
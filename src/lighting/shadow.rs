@@ -0,0 +1,152 @@
+use glam::Vec2;
+
+/// Line segment that blocks light.
+pub(crate) type Segment = (Vec2, Vec2);
+
+// Angle offset used to cast rays just past each occluder corner
+const CORNER_EPSILON: f32 = 1e-4;
+
+/// Edges of solid cells that face an open cell, with runs of neighbouring
+/// edges merged into one segment. `solid[y][x]` covers a `cell_size`
+/// rectangle with its top left at `(x, y) * cell_size`.
+pub(crate) fn occluder_edges(
+    solid: &[Vec<bool>],
+    cell_size: Vec2,
+) -> Vec<Segment> {
+    let height = solid.len() as i32;
+    let width = solid.iter().map(|row| row.len()).max().unwrap_or(0) as i32;
+    let is_solid = |x: i32, y: i32| {
+        x >= 0
+            && y >= 0
+            && solid
+                .get(y as usize)
+                .and_then(|row| row.get(x as usize))
+                .copied()
+                .unwrap_or(false)
+    };
+    let point = |x: i32, y: i32| Vec2::new(x as f32, y as f32) * cell_size;
+
+    let mut edges = Vec::new();
+
+    // Horizontal edges: top (neighbour above) and bottom (neighbour below)
+    for (offset, edge_y) in [(-1, 0), (1, 1)] {
+        for y in 0..height {
+            let mut run_start = None;
+            for x in 0..=width {
+                let exposed = is_solid(x, y) && !is_solid(x, y + offset);
+                match (exposed, run_start) {
+                    (true, None) => run_start = Some(x),
+                    (false, Some(start)) => {
+                        edges.push((
+                            point(start, y + edge_y),
+                            point(x, y + edge_y),
+                        ));
+                        run_start = None;
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    // Vertical edges: left (neighbour to the left) and right
+    for (offset, edge_x) in [(-1, 0), (1, 1)] {
+        for x in 0..width {
+            let mut run_start = None;
+            for y in 0..=height {
+                let exposed = is_solid(x, y) && !is_solid(x + offset, y);
+                match (exposed, run_start) {
+                    (true, None) => run_start = Some(y),
+                    (false, Some(start)) => {
+                        edges.push((
+                            point(x + edge_x, start),
+                            point(x + edge_x, y),
+                        ));
+                        run_start = None;
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    edges
+}
+
+/// The four edges of the rectangle `min..max`.
+pub(crate) fn rect_edges(
+    min: Vec2,
+    max: Vec2,
+) -> [Segment; 4] {
+    let top_right = Vec2::new(max.x, min.y);
+    let bottom_left = Vec2::new(min.x, max.y);
+    [
+        (min, top_right),
+        (top_right, max),
+        (max, bottom_left),
+        (bottom_left, min),
+    ]
+}
+
+/// Distance along the ray `origin + direction * t` to `segment`.
+fn ray_hit(
+    origin: Vec2,
+    direction: Vec2,
+    (a, b): Segment,
+) -> Option<f32> {
+    let edge = b - a;
+    let denominator = direction.perp_dot(edge);
+    if denominator.abs() < 1e-8 {
+        return None;
+    }
+
+    let to_start = a - origin;
+    let t = to_start.perp_dot(edge) / denominator;
+    let u = to_start.perp_dot(direction) / denominator;
+    (t >= 0.0 && (0.0..=1.0).contains(&u)).then_some(t)
+}
+
+/// Area lit from `origin` within a square of half size `radius`, as a
+/// polygon ordered by angle around `origin`.
+pub(crate) fn visibility_polygon(
+    origin: Vec2,
+    radius: f32,
+    occluders: &[Segment],
+) -> Vec<Vec2> {
+    let bounds_min = origin - Vec2::splat(radius);
+    let bounds_max = origin + Vec2::splat(radius);
+
+    let mut segments: Vec<Segment> = occluders
+        .iter()
+        .copied()
+        .filter(|(a, b)| {
+            a.min(*b).cmple(bounds_max).all()
+                && a.max(*b).cmpge(bounds_min).all()
+        })
+        .collect();
+    segments.extend(rect_edges(bounds_min, bounds_max));
+
+    let mut angles: Vec<f32> = segments
+        .iter()
+        .flat_map(|&(a, b)| [a, b])
+        .flat_map(|point| {
+            let offset = point - origin;
+            let angle = offset.y.atan2(offset.x);
+            [angle - CORNER_EPSILON, angle, angle + CORNER_EPSILON]
+        })
+        .collect();
+    angles.sort_by(f32::total_cmp);
+    angles.dedup();
+
+    angles
+        .into_iter()
+        .filter_map(|angle| {
+            let direction = Vec2::from_angle(angle);
+            segments
+                .iter()
+                .filter_map(|&segment| ray_hit(origin, direction, segment))
+                .min_by(f32::total_cmp)
+                .map(|t| origin + direction * t)
+        })
+        .collect()
+}
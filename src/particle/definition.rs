@@ -0,0 +1,213 @@
+use glam::{Vec2, Vec4};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::tile::properties::Color;
+
+/// Extra particles spawned at once, `time` seconds after the emitter
+/// started.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Burst {
+    #[serde(default)]
+    pub time: f32,
+    pub count: u32,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ColorKeyframe {
+    /// Fraction of the particle's lifetime, 0.0 to 1.0.
+    pub time: f32,
+    pub color: Color,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct SizeKeyframe {
+    /// Fraction of the particle's lifetime, 0.0 to 1.0.
+    pub time: f32,
+    pub size: f32,
+}
+
+/// Frames of a sprite sheet laid out in a grid, numbered left to right and
+/// top to bottom.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParticleFrames {
+    pub frame_width: u32,
+    pub frame_height: u32,
+    pub frames: Vec<u32>,
+    /// Frames per second. Without it the frames are spread over the
+    /// particle's lifetime.
+    #[serde(default)]
+    pub fps: Option<f32>,
+    /// Start each particle on a random frame instead of the first.
+    #[serde(default)]
+    pub random_start: bool,
+}
+
+/// Data-driven description of a particle effect, usually loaded from JSON.
+///
+/// Ranges are `[min, max]` and each particle picks a random value in them.
+/// Vectors are `[x, y]` in world units with y pointing down.
+///
+/// ```
+/// use teengine::particle::ParticleEmitterDefinition;
+///
+/// let dust = ParticleEmitterDefinition::from_json(r#"{
+///     "spawn_rate": 0.0,
+///     "bursts": [{ "count": 12 }],
+///     "duration": 0.0,
+///     "lifetime": [0.3, 0.6],
+///     "velocity_min": [-40.0, -30.0],
+///     "velocity_max": [40.0, -5.0],
+///     "gravity": [0.0, 60.0],
+///     "size_over_life": [
+///         { "time": 0.0, "size": 6.0 },
+///         { "time": 1.0, "size": 1.0 }
+///     ]
+/// }"#).unwrap();
+/// assert_eq!(dust.bursts[0].count, 12);
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ParticleEmitterDefinition {
+    /// Particle texture. Without one particles are plain colored squares.
+    pub texture: Option<String>,
+    pub frames: Option<ParticleFrames>,
+    /// Particles per second while emitting.
+    pub spawn_rate: f32,
+    pub bursts: Vec<Burst>,
+    /// Seconds the emitter keeps spawning. `None` emits until stopped.
+    pub duration: Option<f32>,
+    /// Restart the bursts and duration when the duration ends.
+    pub looping: bool,
+    pub max_particles: usize,
+    pub lifetime: [f32; 2],
+    /// Size of the box around the emitter position particles spawn in.
+    pub spawn_area: [f32; 2],
+    pub velocity_min: [f32; 2],
+    pub velocity_max: [f32; 2],
+    pub gravity: [f32; 2],
+    /// Fraction of velocity lost per second.
+    pub drag: f32,
+    /// Initial rotation in degrees.
+    pub rotation: [f32; 2],
+    /// Rotation speed in degrees per second.
+    pub angular_velocity: [f32; 2],
+    pub color_over_life: Vec<ColorKeyframe>,
+    pub size_over_life: Vec<SizeKeyframe>,
+}
+
+impl Default for ParticleEmitterDefinition {
+    fn default() -> Self {
+        Self {
+            texture: None,
+            frames: None,
+            spawn_rate: 10.0,
+            bursts: Vec::new(),
+            duration: None,
+            looping: false,
+            max_particles: 1000,
+            lifetime: [1.0, 1.0],
+            spawn_area: [0.0, 0.0],
+            velocity_min: [0.0, 0.0],
+            velocity_max: [0.0, 0.0],
+            gravity: [0.0, 0.0],
+            drag: 0.0,
+            rotation: [0.0, 0.0],
+            angular_velocity: [0.0, 0.0],
+            color_over_life: Vec::new(),
+            size_over_life: Vec::new(),
+        }
+    }
+}
+
+impl ParticleEmitterDefinition {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path).map_err(|e| {
+            format!(
+                "Failed to read particle definition {}: {}",
+                path.display(),
+                e
+            )
+        })?;
+        Self::from_json(&json).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let mut definition: Self = serde_json::from_str(json)
+            .map_err(|e| format!("Invalid particle definition: {}", e))?;
+        definition
+            .color_over_life
+            .sort_by(|a, b| a.time.total_cmp(&b.time));
+        definition
+            .size_over_life
+            .sort_by(|a, b| a.time.total_cmp(&b.time));
+
+        if let Some(frames) = &definition.frames {
+            if frames.frame_width == 0 || frames.frame_height == 0 {
+                return Err("Particle frame size must not be zero".to_string());
+            }
+            if frames.frames.is_empty() {
+                return Err("Particle frames must not be empty".to_string());
+            }
+        }
+        Ok(definition)
+    }
+
+    /// Color at `t` (0.0 to 1.0) of a particle's life. White without
+    /// keyframes.
+    pub fn color_at(
+        &self,
+        t: f32,
+    ) -> Vec4 {
+        let keys = &self.color_over_life;
+        interpolate(keys, t, |key| key.time, |key| key.color.to_vec4())
+            .unwrap_or(Vec4::ONE)
+    }
+
+    /// Size in world units at `t` (0.0 to 1.0) of a particle's life. The
+    /// frame size, or 4.0 without frames, when no keyframes are given.
+    pub fn size_at(
+        &self,
+        t: f32,
+    ) -> f32 {
+        interpolate(&self.size_over_life, t, |key| key.time, |key| key.size)
+            .unwrap_or_else(|| {
+                self.frames
+                    .as_ref()
+                    .map_or(4.0, |frames| frames.frame_width as f32)
+            })
+    }
+
+    pub(crate) fn velocity_range(&self) -> (Vec2, Vec2) {
+        (Vec2::from(self.velocity_min), Vec2::from(self.velocity_max))
+    }
+}
+
+/// Linear interpolation between the keyframes around `t`.
+fn interpolate<K, V>(
+    keys: &[K],
+    t: f32,
+    time: impl Fn(&K) -> f32,
+    value: impl Fn(&K) -> V,
+) -> Option<V>
+where
+    V: std::ops::Mul<f32, Output = V> + std::ops::Add<Output = V>,
+{
+    let first = keys.first()?;
+    if t <= time(first) {
+        return Some(value(first));
+    }
+
+    for pair in keys.windows(2) {
+        let (start, end) = (time(&pair[0]), time(&pair[1]));
+        if t <= end {
+            let span = end - start;
+            let amount = if span > 0.0 { (t - start) / span } else { 1.0 };
+            return Some(
+                value(&pair[0]) * (1.0 - amount) + value(&pair[1]) * amount,
+            );
+        }
+    }
+
+    keys.last().map(value)
+}
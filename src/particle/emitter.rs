@@ -0,0 +1,340 @@
+use glam::{Mat4, Vec2};
+use std::path::Path;
+
+use super::definition::ParticleEmitterDefinition;
+use crate::random::Random;
+use crate::sprite::sprite_renderer::{SpriteQuad, SpriteRenderer};
+use crate::texture::options::TextureOptions;
+use crate::texture::texture::Texture;
+
+#[derive(Debug, Clone, Copy)]
+struct Particle {
+    position: Vec2,
+    velocity: Vec2,
+    age: f32,
+    lifetime: f32,
+    rotation: f32,
+    angular_velocity: f32,
+    first_frame: usize,
+}
+
+/// Spawns, simulates and draws particles described by a
+/// [`ParticleEmitterDefinition`].
+pub struct ParticleEmitter {
+    definition: ParticleEmitterDefinition,
+    texture: Texture,
+    frame_uvs: Vec<(Vec2, Vec2)>,
+    position: Vec2,
+    particles: Vec<Particle>,
+    random: Random,
+    emitting: bool,
+    elapsed: f32,
+    spawn_accumulator: f32,
+    next_burst: usize,
+    quads: Vec<SpriteQuad>,
+}
+
+impl ParticleEmitter {
+    /// Load a definition from JSON along with its texture.
+    pub fn load(
+        path: &Path,
+        position: Vec2,
+    ) -> Result<Self, String> {
+        Self::new(ParticleEmitterDefinition::load(path)?, position)
+    }
+
+    /// Create an emitter, loading the definition's texture if it has one.
+    pub fn new(
+        definition: ParticleEmitterDefinition,
+        position: Vec2,
+    ) -> Result<Self, String> {
+        let texture = match &definition.texture {
+            Some(path) => Texture::new(Path::new(path))?,
+            None => Texture::from_rgba_bytes(
+                1,
+                1,
+                &[255, 255, 255, 255],
+                TextureOptions::default(),
+            )?,
+        };
+        Ok(Self::with_texture(definition, texture, position))
+    }
+
+    /// Create an emitter drawing with `texture`, ignoring the definition's
+    /// texture path. Useful to share one texture between emitters.
+    pub fn with_texture(
+        mut definition: ParticleEmitterDefinition,
+        texture: Texture,
+        position: Vec2,
+    ) -> Self {
+        definition.bursts.sort_by(|a, b| a.time.total_cmp(&b.time));
+        let frame_uvs = Self::frame_uvs(&definition, &texture);
+        Self {
+            definition,
+            texture,
+            frame_uvs,
+            position,
+            particles: Vec::new(),
+            random: Random::from_time(),
+            emitting: true,
+            elapsed: 0.0,
+            spawn_accumulator: 0.0,
+            next_burst: 0,
+            quads: Vec::new(),
+        }
+    }
+
+    pub fn definition(&self) -> &ParticleEmitterDefinition {
+        &self.definition
+    }
+
+    pub fn position(&self) -> Vec2 {
+        self.position
+    }
+
+    /// Move the emitter. Particles already spawned stay where they are.
+    pub fn set_position(
+        &mut self,
+        position: Vec2,
+    ) {
+        self.position = position;
+    }
+
+    /// Reseed the random generator for a reproducible effect.
+    pub fn set_seed(
+        &mut self,
+        seed: u64,
+    ) {
+        self.random = Random::new(seed);
+    }
+
+    /// Restart emission from the beginning, including bursts.
+    pub fn start(&mut self) {
+        self.emitting = true;
+        self.elapsed = 0.0;
+        self.spawn_accumulator = 0.0;
+        self.next_burst = 0;
+    }
+
+    /// Stop spawning. Living particles finish their lifetime.
+    pub fn stop(&mut self) {
+        self.emitting = false;
+    }
+
+    pub fn is_emitting(&self) -> bool {
+        self.emitting
+    }
+
+    /// Returns `true` once the emitter stopped and every particle died.
+    pub fn is_finished(&self) -> bool {
+        !self.emitting && self.particles.is_empty()
+    }
+
+    pub fn particle_count(&self) -> usize {
+        self.particles.len()
+    }
+
+    /// Spawn `count` particles at once.
+    pub fn burst(
+        &mut self,
+        count: u32,
+    ) {
+        for _ in 0..count {
+            self.spawn();
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        delta_time: f32,
+    ) {
+        self.simulate(delta_time);
+        if self.emitting {
+            self.emit(delta_time);
+        }
+    }
+
+    /// Draw every particle in one batch.
+    pub fn render(
+        &mut self,
+        renderer: &SpriteRenderer,
+        projection: &Mat4,
+    ) {
+        if self.particles.is_empty() {
+            return;
+        }
+
+        self.quads.clear();
+        for particle in &self.particles {
+            let t = (particle.age / particle.lifetime).clamp(0.0, 1.0);
+            let half_size = Vec2::splat(self.definition.size_at(t) * 0.5);
+            let (uv_min, uv_max) = self.frame_uvs[self.frame_index(particle)];
+
+            let quad = SpriteQuad::new(
+                particle.position - half_size,
+                particle.position + half_size,
+                uv_min,
+                uv_max,
+                self.definition.color_at(t),
+            );
+            self.quads.push(if particle.rotation != 0.0 {
+                quad.rotated(particle.rotation.to_radians(), particle.position)
+            } else {
+                quad
+            });
+        }
+
+        renderer.draw_quads(&self.texture, &self.quads, projection);
+    }
+
+    fn simulate(
+        &mut self,
+        delta_time: f32,
+    ) {
+        let gravity = Vec2::from(self.definition.gravity);
+        let damping = (1.0 - self.definition.drag * delta_time).max(0.0);
+
+        self.particles.retain_mut(|particle| {
+            particle.age += delta_time;
+            if particle.age >= particle.lifetime {
+                return false;
+            }
+            particle.velocity =
+                (particle.velocity + gravity * delta_time) * damping;
+            particle.position += particle.velocity * delta_time;
+            particle.rotation += particle.angular_velocity * delta_time;
+            true
+        });
+    }
+
+    fn emit(
+        &mut self,
+        delta_time: f32,
+    ) {
+        let previous = self.elapsed;
+        self.elapsed += delta_time;
+        let duration = self.definition.duration;
+
+        let bursts: Vec<u32> = self
+            .definition
+            .bursts
+            .iter()
+            .skip(self.next_burst)
+            .take_while(|burst| burst.time <= self.elapsed)
+            .map(|burst| burst.count)
+            .collect();
+        self.next_burst += bursts.len();
+        for count in bursts {
+            self.burst(count);
+        }
+
+        let spawn_time = match duration {
+            Some(duration) => (duration.min(self.elapsed) - previous).max(0.0),
+            None => delta_time,
+        };
+        self.spawn_accumulator += spawn_time * self.definition.spawn_rate;
+        while self.spawn_accumulator >= 1.0 {
+            self.spawn_accumulator -= 1.0;
+            self.spawn();
+        }
+
+        if let Some(duration) = duration {
+            let bursts_done = self.next_burst >= self.definition.bursts.len();
+            if self.elapsed >= duration && bursts_done {
+                if self.definition.looping && duration > 0.0 {
+                    self.elapsed -= duration;
+                    self.next_burst = 0;
+                } else {
+                    self.emitting = false;
+                }
+            }
+        }
+    }
+
+    fn spawn(&mut self) {
+        if self.particles.len() >= self.definition.max_particles {
+            return;
+        }
+
+        let definition = &self.definition;
+        let random = &mut self.random;
+        let [lifetime_min, lifetime_max] = definition.lifetime;
+        let (velocity_min, velocity_max) = definition.velocity_range();
+        let area = Vec2::from(definition.spawn_area);
+
+        let offset = Vec2::new(
+            random.range_f32(-0.5, 0.5) * area.x,
+            random.range_f32(-0.5, 0.5) * area.y,
+        );
+        let velocity = Vec2::new(
+            random.range_f32(velocity_min.x, velocity_max.x),
+            random.range_f32(velocity_min.y, velocity_max.y),
+        );
+        let first_frame = match &definition.frames {
+            Some(frames) if frames.random_start => {
+                random.range_i32(0, frames.frames.len() as i32 - 1) as usize
+            }
+            _ => 0,
+        };
+
+        self.particles.push(Particle {
+            position: self.position + offset,
+            velocity,
+            age: 0.0,
+            lifetime: random.range_f32(lifetime_min, lifetime_max).max(0.001),
+            rotation: random
+                .range_f32(definition.rotation[0], definition.rotation[1]),
+            angular_velocity: random.range_f32(
+                definition.angular_velocity[0],
+                definition.angular_velocity[1],
+            ),
+            first_frame,
+        });
+    }
+
+    fn frame_index(
+        &self,
+        particle: &Particle,
+    ) -> usize {
+        let Some(frames) = &self.definition.frames else {
+            return 0;
+        };
+        let count = self.frame_uvs.len();
+        let step = match frames.fps {
+            Some(fps) => (particle.age * fps) as usize,
+            None => (particle.age / particle.lifetime * count as f32) as usize,
+        };
+        (particle.first_frame + step) % count
+    }
+
+    /// UV rectangles of the definition's frames, or the whole texture.
+    fn frame_uvs(
+        definition: &ParticleEmitterDefinition,
+        texture: &Texture,
+    ) -> Vec<(Vec2, Vec2)> {
+        let Some(frames) = &definition.frames else {
+            return vec![(Vec2::ZERO, Vec2::ONE)];
+        };
+
+        let texture_size =
+            Vec2::new(texture.width() as f32, texture.height() as f32);
+        let columns = (texture.width() / frames.frame_width).max(1);
+        let frame_size =
+            Vec2::new(frames.frame_width as f32, frames.frame_height as f32);
+
+        frames
+            .frames
+            .iter()
+            .map(|&frame| {
+                let position = Vec2::new(
+                    (frame % columns) as f32,
+                    (frame / columns) as f32,
+                ) * frame_size;
+                (
+                    position / texture_size,
+                    (position + frame_size) / texture_size,
+                )
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,10 @@
+pub mod definition;
+pub mod emitter;
+pub mod particle_system;
+
+pub use definition::{
+    Burst, ColorKeyframe, ParticleEmitterDefinition, ParticleFrames,
+    SizeKeyframe,
+};
+pub use emitter::ParticleEmitter;
+pub use particle_system::ParticleSystem;
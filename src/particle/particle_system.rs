@@ -0,0 +1,96 @@
+use glam::{Mat4, Vec2};
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::definition::ParticleEmitterDefinition;
+use super::emitter::ParticleEmitter;
+use crate::sprite::sprite_renderer::SpriteRenderer;
+use crate::texture::texture::Texture;
+
+/// Owns a set of emitters, updates them together and drops the ones that
+/// finished. Handy for fire-and-forget effects such as dust puffs.
+#[derive(Default)]
+pub struct ParticleSystem {
+    emitters: Vec<ParticleEmitter>,
+    textures: HashMap<String, Texture>,
+}
+
+impl ParticleSystem {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(
+        &mut self,
+        emitter: ParticleEmitter,
+    ) {
+        self.emitters.push(emitter);
+    }
+
+    /// Start an effect at `position`. Textures are loaded once and shared
+    /// between every emitter spawned from definitions that use them.
+    pub fn spawn(
+        &mut self,
+        definition: &ParticleEmitterDefinition,
+        position: Vec2,
+    ) -> Result<&mut ParticleEmitter, String> {
+        let emitter = match &definition.texture {
+            Some(path) => {
+                let texture = match self.textures.get(path) {
+                    Some(texture) => texture.clone(),
+                    None => {
+                        let texture = Texture::new(Path::new(path))?;
+                        self.textures.insert(path.clone(), texture.clone());
+                        texture
+                    }
+                };
+                ParticleEmitter::with_texture(
+                    definition.clone(),
+                    texture,
+                    position,
+                )
+            }
+            None => ParticleEmitter::new(definition.clone(), position)?,
+        };
+
+        self.emitters.push(emitter);
+        Ok(self.emitters.last_mut().expect("emitter was just added"))
+    }
+
+    pub fn emitters(&self) -> &[ParticleEmitter] {
+        &self.emitters
+    }
+
+    pub fn emitters_mut(&mut self) -> &mut [ParticleEmitter] {
+        &mut self.emitters
+    }
+
+    pub fn particle_count(&self) -> usize {
+        self.emitters.iter().map(|e| e.particle_count()).sum()
+    }
+
+    pub fn clear(&mut self) {
+        self.emitters.clear();
+    }
+
+    pub fn update(
+        &mut self,
+        delta_time: f32,
+    ) {
+        for emitter in &mut self.emitters {
+            emitter.update(delta_time);
+        }
+        self.emitters.retain(|emitter| !emitter.is_finished());
+    }
+
+    /// Draw every emitter, one batch each, in the order they were added.
+    pub fn render(
+        &mut self,
+        renderer: &SpriteRenderer,
+        projection: &Mat4,
+    ) {
+        for emitter in &mut self.emitters {
+            emitter.render(renderer, projection);
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Small seeded pseudo random generator (xorshift64*).
+///
+/// The same seed always produces the same sequence on every platform, so
+/// effects and generated content can be reproduced.
+#[derive(Debug, Clone)]
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        // Zero is a fixed point of xorshift; mix the seed so every value works
+        let mut random = Self {
+            state: seed ^ 0x9E37_79B9_7F4A_7C15,
+        };
+        if random.state == 0 {
+            random.state = 0x9E37_79B9_7F4A_7C15;
+        }
+        random.next_u64();
+        random
+    }
+
+    /// Seeded from the system clock.
+    pub fn from_time() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_nanos() as u64)
+            .unwrap_or(0);
+        Self::new(nanos)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Uniform value in `0.0..1.0`.
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Uniform value in `min..max`. Returns `min` if the range is empty.
+    pub fn range_f32(
+        &mut self,
+        min: f32,
+        max: f32,
+    ) -> f32 {
+        if max <= min {
+            return min;
+        }
+        min + (max - min) * self.next_f32()
+    }
+
+    /// Uniform integer in `min..=max`.
+    pub fn range_i32(
+        &mut self,
+        min: i32,
+        max: i32,
+    ) -> i32 {
+        if max <= min {
+            return min;
+        }
+        let span = (max as i64 - min as i64 + 1) as u64;
+        (min as i64 + (self.next_u64() % span) as i64) as i32
+    }
+
+    /// Returns `true` with the given probability.
+    pub fn chance(
+        &mut self,
+        probability: f32,
+    ) -> bool {
+        self.next_f32() < probability
+    }
+}
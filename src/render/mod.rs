@@ -0,0 +1,8 @@
+pub mod post_effects;
+pub mod post_process;
+pub mod render_target;
+pub mod viewport;
+
+pub use post_process::{integer_scaled_rect, PostEffect, PostProcessor};
+pub use render_target::RenderTarget;
+pub use viewport::{ResizePolicy, Viewport};
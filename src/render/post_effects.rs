@@ -0,0 +1,158 @@
+//! GLSL sources of the post-processing pass and the built-in effects.
+
+/// Fullscreen triangle generated from `gl_VertexID`; no vertex buffer is
+/// needed. Custom effects built with `PostEffect::from_shader` can use it
+/// as their vertex shader.
+pub const POST_VERTEX_SHADER: &str = r#"
+#version 330 core
+out vec2 TexCoord;
+
+void main() {
+    vec2 position = vec2((gl_VertexID << 1) & 2, gl_VertexID & 2);
+    TexCoord = position;
+    gl_Position = vec4(position * 2.0 - 1.0, 0.0, 1.0);
+}
+"#;
+
+/// Copies the input unchanged.
+pub const PASSTHROUGH_FRAGMENT_SHADER: &str = r#"
+#version 330 core
+in vec2 TexCoord;
+out vec4 FragColor;
+
+uniform sampler2D screenTexture;
+
+void main() {
+    FragColor = texture(screenTexture, TexCoord);
+}
+"#;
+
+/// Scanlines, barrel distortion and a slight RGB shift.
+pub const CRT_FRAGMENT_SHADER: &str = r#"
+#version 330 core
+in vec2 TexCoord;
+out vec4 FragColor;
+
+uniform sampler2D screenTexture;
+uniform vec2 sourceSize;
+uniform vec2 outputSize;
+uniform float curvature;
+uniform float scanlineStrength;
+
+void main() {
+    vec2 centered = TexCoord * 2.0 - 1.0;
+    centered *= 1.0 + curvature * dot(centered.yx, centered.yx);
+    vec2 uv = centered * 0.5 + 0.5;
+    if (uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0) {
+        FragColor = vec4(0.0, 0.0, 0.0, 1.0);
+        return;
+    }
+
+    vec2 shift = vec2(0.5 / outputSize.x, 0.0);
+    vec3 color = vec3(
+        texture(screenTexture, uv + shift).r,
+        texture(screenTexture, uv).g,
+        texture(screenTexture, uv - shift).b
+    );
+
+    float scanline = sin(uv.y * sourceSize.y * 3.14159265) * 0.5 + 0.5;
+    color *= mix(1.0, scanline, scanlineStrength);
+    FragColor = vec4(color, 1.0);
+}
+"#;
+
+/// Adds a blurred copy of the pixels brighter than `threshold`.
+pub const BLOOM_FRAGMENT_SHADER: &str = r#"
+#version 330 core
+in vec2 TexCoord;
+out vec4 FragColor;
+
+uniform sampler2D screenTexture;
+uniform vec2 sourceSize;
+uniform float threshold;
+uniform float intensity;
+uniform float radius;
+
+vec3 bright(vec2 uv) {
+    vec3 color = texture(screenTexture, uv).rgb;
+    float luminance = dot(color, vec3(0.2126, 0.7152, 0.0722));
+    return color * smoothstep(threshold, threshold + 0.1, luminance);
+}
+
+void main() {
+    vec4 base = texture(screenTexture, TexCoord);
+    vec2 texel = radius / sourceSize;
+
+    vec3 glow = vec3(0.0);
+    float total = 0.0;
+    for (int x = -3; x <= 3; x++) {
+        for (int y = -3; y <= 3; y++) {
+            float weight = exp(-float(x * x + y * y) / 8.0);
+            glow += bright(TexCoord + vec2(x, y) * texel) * weight;
+            total += weight;
+        }
+    }
+
+    FragColor = vec4(base.rgb + glow / total * intensity, base.a);
+}
+"#;
+
+/// Darkens the corners.
+pub const VIGNETTE_FRAGMENT_SHADER: &str = r#"
+#version 330 core
+in vec2 TexCoord;
+out vec4 FragColor;
+
+uniform sampler2D screenTexture;
+uniform float strength;
+uniform float softness;
+
+void main() {
+    vec4 color = texture(screenTexture, TexCoord);
+    float distance = length(TexCoord - 0.5) * 1.41421356;
+    float vignette = smoothstep(1.0, 1.0 - softness, distance);
+    FragColor = vec4(color.rgb * mix(1.0, vignette, strength), color.a);
+}
+"#;
+
+/// Brightness, contrast, saturation and tint.
+pub const COLOR_GRADING_FRAGMENT_SHADER: &str = r#"
+#version 330 core
+in vec2 TexCoord;
+out vec4 FragColor;
+
+uniform sampler2D screenTexture;
+uniform float brightness;
+uniform float contrast;
+uniform float saturation;
+uniform vec3 tint;
+
+void main() {
+    vec4 color = texture(screenTexture, TexCoord);
+    vec3 rgb = color.rgb + brightness;
+    rgb = (rgb - 0.5) * contrast + 0.5;
+    float luminance = dot(rgb, vec3(0.2126, 0.7152, 0.0722));
+    rgb = mix(vec3(luminance), rgb, saturation) * tint;
+    FragColor = vec4(clamp(rgb, 0.0, 1.0), color.a);
+}
+"#;
+
+/// Maps the luminance of each pixel to a color of a palette texture laid
+/// out as one row, darkest color first.
+pub const PALETTE_SWAP_FRAGMENT_SHADER: &str = r#"
+#version 330 core
+in vec2 TexCoord;
+out vec4 FragColor;
+
+uniform sampler2D screenTexture;
+uniform sampler2D palette;
+uniform float paletteSize;
+
+void main() {
+    vec4 color = texture(screenTexture, TexCoord);
+    float luminance = dot(color.rgb, vec3(0.2126, 0.7152, 0.0722));
+    float index = min(floor(luminance * paletteSize), paletteSize - 1.0);
+    vec3 mapped = texture(palette, vec2((index + 0.5) / paletteSize, 0.5)).rgb;
+    FragColor = vec4(mapped, color.a);
+}
+"#;
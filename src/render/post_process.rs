@@ -0,0 +1,405 @@
+use gl::types::*;
+use glam::{Vec2, Vec3};
+use std::cell::RefCell;
+use std::time::Instant;
+
+use super::post_effects;
+use super::render_target::RenderTarget;
+use crate::shader::{Material, ShaderProgram, UniformValue};
+use crate::texture::texture::Texture;
+
+/// Fullscreen fragment shader pass.
+///
+/// The fragment shader receives the output of the previous pass:
+///
+/// ```glsl
+/// in vec2 TexCoord;
+/// out vec4 FragColor;
+///
+/// uniform sampler2D screenTexture;
+/// uniform vec2 sourceSize;  // input size in pixels
+/// uniform vec2 outputSize;  // size of the area drawn to, in pixels
+/// uniform float time;       // seconds since the post processor was created
+/// ```
+///
+/// Additional uniforms and textures are set per effect.
+#[derive(Debug)]
+pub struct PostEffect {
+    name: String,
+    material: Material,
+    enabled: bool,
+}
+
+impl PostEffect {
+    /// Effect from a fragment shader source following the contract above.
+    pub fn new(
+        name: &str,
+        fragment_source: &str,
+    ) -> Result<Self, String> {
+        let program = ShaderProgram::from_source(
+            post_effects::POST_VERTEX_SHADER,
+            fragment_source,
+        )?;
+        Ok(Self::from_shader(name, program))
+    }
+
+    /// Effect from a complete program, e.g. one loaded with
+    /// `ShaderProgram::from_files` so it can be hot reloaded. Use
+    /// [`post_effects::POST_VERTEX_SHADER`] as its vertex shader.
+    pub fn from_shader(
+        name: &str,
+        program: ShaderProgram,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            material: Material::new(program),
+            enabled: true,
+        }
+    }
+
+    /// Scanlines and screen curvature.
+    pub fn crt() -> Result<Self, String> {
+        Ok(Self::new("crt", post_effects::CRT_FRAGMENT_SHADER)?
+            .with_uniform("curvature", 0.04)
+            .with_uniform("scanlineStrength", 0.35))
+    }
+
+    /// Glow around pixels brighter than `threshold` (0.0 to 1.0).
+    pub fn bloom(
+        threshold: f32,
+        intensity: f32,
+    ) -> Result<Self, String> {
+        Ok(Self::new("bloom", post_effects::BLOOM_FRAGMENT_SHADER)?
+            .with_uniform("threshold", threshold)
+            .with_uniform("intensity", intensity)
+            .with_uniform("radius", 1.5))
+    }
+
+    /// Darkened corners. `strength` 0.0 disables the effect.
+    pub fn vignette(strength: f32) -> Result<Self, String> {
+        Ok(
+            Self::new("vignette", post_effects::VIGNETTE_FRAGMENT_SHADER)?
+                .with_uniform("strength", strength)
+                .with_uniform("softness", 0.45),
+        )
+    }
+
+    /// Brightness, contrast and saturation adjustment plus an RGB tint.
+    /// Starts out neutral; change it with `brightness`, `contrast`,
+    /// `saturation` and `tint` uniforms.
+    pub fn color_grading() -> Result<Self, String> {
+        Ok(Self::new(
+            "color_grading",
+            post_effects::COLOR_GRADING_FRAGMENT_SHADER,
+        )?
+        .with_uniform("brightness", 0.0)
+        .with_uniform("contrast", 1.0)
+        .with_uniform("saturation", 1.0)
+        .with_uniform("tint", Vec3::ONE))
+    }
+
+    /// Recolor the screen with the colors of `palette`, a texture one pixel
+    /// high ordered from darkest to brightest.
+    pub fn palette_swap(palette: Texture) -> Result<Self, String> {
+        let size = palette.width() as f32;
+        Ok(Self::new(
+            "palette_swap",
+            post_effects::PALETTE_SWAP_FRAGMENT_SHADER,
+        )?
+        .with_texture("palette", palette)
+        .with_uniform("paletteSize", size))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn program(&self) -> &ShaderProgram {
+        self.material.shader()
+    }
+
+    pub fn uniform(
+        &self,
+        name: &str,
+    ) -> Option<UniformValue> {
+        self.material.uniform(name)
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn set_enabled(
+        &mut self,
+        enabled: bool,
+    ) {
+        self.enabled = enabled;
+    }
+
+    pub fn with_uniform(
+        mut self,
+        name: &str,
+        value: impl Into<UniformValue>,
+    ) -> Self {
+        self.set_uniform(name, value);
+        self
+    }
+
+    pub fn set_uniform(
+        &mut self,
+        name: &str,
+        value: impl Into<UniformValue>,
+    ) {
+        self.material.set_uniform(name, value);
+    }
+
+    /// Bind `texture` to the sampler uniform `name`.
+    pub fn with_texture(
+        mut self,
+        name: &str,
+        texture: Texture,
+    ) -> Self {
+        self.set_texture(name, texture);
+        self
+    }
+
+    pub fn set_texture(
+        &mut self,
+        name: &str,
+        texture: Texture,
+    ) {
+        self.material.set_texture(name, texture);
+    }
+
+    fn apply_uniforms(
+        &self,
+        source: &Texture,
+        output_size: Vec2,
+        time: f32,
+    ) {
+        let program = self.program();
+        program.set_int("screenTexture", 0);
+        program.set_vec2(
+            "sourceSize",
+            Vec2::new(source.width() as f32, source.height() as f32),
+        );
+        program.set_vec2("outputSize", output_size);
+        program.set_float("time", time);
+        self.material.apply(1);
+
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0);
+        }
+        source.bind();
+    }
+}
+
+/// Chain of [`PostEffect`]s applied to the frame before it is shown.
+///
+/// Intermediate passes render at the size of the input; the last pass
+/// draws straight into the output area so effects such as scanlines can
+/// work at window resolution.
+pub struct PostProcessor {
+    effects: RefCell<Vec<PostEffect>>,
+    passthrough: PostEffect,
+    targets: RefCell<Vec<RenderTarget>>,
+    vao: GLuint,
+    start_time: Instant,
+}
+
+impl PostProcessor {
+    pub fn new() -> Result<Self, String> {
+        let passthrough = PostEffect::new(
+            "passthrough",
+            post_effects::PASSTHROUGH_FRAGMENT_SHADER,
+        )?;
+        let mut vao = 0;
+        unsafe {
+            gl::GenVertexArrays(1, &mut vao);
+        }
+
+        Ok(Self {
+            effects: RefCell::new(Vec::new()),
+            passthrough,
+            targets: RefCell::new(Vec::new()),
+            vao,
+            start_time: Instant::now(),
+        })
+    }
+
+    /// Append an effect to the end of the chain.
+    pub fn add_effect(
+        &self,
+        effect: PostEffect,
+    ) {
+        self.effects.borrow_mut().push(effect);
+    }
+
+    pub fn remove_effect(
+        &self,
+        name: &str,
+    ) {
+        self.effects
+            .borrow_mut()
+            .retain(|effect| effect.name() != name);
+    }
+
+    pub fn clear_effects(&self) {
+        self.effects.borrow_mut().clear();
+    }
+
+    /// Change an effect in place, e.g. to animate a uniform. Returns `false`
+    /// if no effect has that name.
+    pub fn with_effect(
+        &self,
+        name: &str,
+        update: impl FnOnce(&mut PostEffect),
+    ) -> bool {
+        match self
+            .effects
+            .borrow_mut()
+            .iter_mut()
+            .find(|effect| effect.name() == name)
+        {
+            Some(effect) => {
+                update(effect);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn set_effect_enabled(
+        &self,
+        name: &str,
+        enabled: bool,
+    ) {
+        self.with_effect(name, |effect| effect.set_enabled(enabled));
+    }
+
+    pub fn has_active_effects(&self) -> bool {
+        self.effects
+            .borrow()
+            .iter()
+            .any(|effect| effect.is_enabled())
+    }
+
+    /// Run `source` through every enabled effect and draw the result into
+    /// `output` (x, y, width, height) of the currently bound framebuffer.
+    /// Without effects the source is copied unchanged.
+    pub fn process(
+        &self,
+        source: &Texture,
+        output: [i32; 4],
+    ) {
+        let effects = self.effects.borrow();
+        let active: Vec<&PostEffect> = effects
+            .iter()
+            .filter(|effect| effect.is_enabled())
+            .collect();
+        let time = self.start_time.elapsed().as_secs_f32();
+
+        if let Err(e) = self.prepare_targets(source, active.len()) {
+            eprintln!("Failed to create post processing targets: {}", e);
+            return;
+        }
+        let targets = self.targets.borrow();
+
+        unsafe {
+            gl::Disable(gl::BLEND);
+            gl::BindVertexArray(self.vao);
+        }
+
+        let mut input = source.clone();
+        for (i, effect) in active.iter().enumerate() {
+            if i + 1 == active.len() {
+                break;
+            }
+            let target = &targets[i % 2];
+            target.bind();
+            effect.program().use_program();
+            effect.apply_uniforms(
+                &input,
+                Vec2::new(target.width() as f32, target.height() as f32),
+                time,
+            );
+            unsafe {
+                gl::DrawArrays(gl::TRIANGLES, 0, 3);
+            }
+            target.unbind();
+            input = target.texture().clone();
+        }
+
+        let [x, y, width, height] = output;
+        unsafe {
+            gl::Viewport(x, y, width, height);
+        }
+        let output_size = Vec2::new(width as f32, height as f32);
+        let last = active.last().copied().unwrap_or(&self.passthrough);
+        last.program().use_program();
+        last.apply_uniforms(&input, output_size, time);
+
+        unsafe {
+            gl::DrawArrays(gl::TRIANGLES, 0, 3);
+            gl::BindVertexArray(0);
+            gl::Enable(gl::BLEND);
+        }
+    }
+
+    /// Make sure there are enough ping-pong targets of the source size for
+    /// the intermediate passes.
+    fn prepare_targets(
+        &self,
+        source: &Texture,
+        pass_count: usize,
+    ) -> Result<(), String> {
+        let needed = pass_count.saturating_sub(1).min(2);
+        let mut targets = self.targets.borrow_mut();
+        for target in targets.iter_mut() {
+            target.resize(source.width(), source.height())?;
+        }
+        while targets.len() < needed {
+            targets.push(RenderTarget::new(
+                source.width(),
+                source.height(),
+                *source.options(),
+            )?);
+        }
+        Ok(())
+    }
+}
+
+impl Drop for PostProcessor {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteVertexArrays(1, &self.vao);
+        }
+    }
+}
+
+/// Largest whole-number scale of `source` that fits in `window`, centered
+/// with black bars around it. Never scales below 1, so a window smaller
+/// than the source crops it.
+///
+/// ```
+/// use teengine::render::integer_scaled_rect;
+///
+/// // 320x180 in a 1366x768 window scales by 4 and is centered
+/// assert_eq!(integer_scaled_rect((320, 180), (1366, 768)), [43, 24, 1280, 720]);
+/// ```
+pub fn integer_scaled_rect(
+    source: (u32, u32),
+    window: (u32, u32),
+) -> [i32; 4] {
+    let scale = (window.0 / source.0.max(1))
+        .min(window.1 / source.1.max(1))
+        .max(1);
+    let width = (source.0 * scale) as i32;
+    let height = (source.1 * scale) as i32;
+    [
+        (window.0 as i32 - width) / 2,
+        (window.1 as i32 - height) / 2,
+        width,
+        height,
+    ]
+}
@@ -0,0 +1,200 @@
+use gl::types::*;
+use std::cell::Cell;
+
+use crate::texture::options::TextureOptions;
+use crate::texture::texture::Texture;
+
+/// Offscreen framebuffer with one or more RGBA color textures.
+///
+/// Everything drawn between [`RenderTarget::bind`] and
+/// [`RenderTarget::unbind`] ends up in the target's textures instead of the
+/// window. With several color attachments a fragment shader writes to each
+/// one through `layout(location = N) out vec4`. Texture origins are at the
+/// bottom left, as usual for OpenGL framebuffers.
+#[derive(Debug)]
+pub struct RenderTarget {
+    framebuffer: GLuint,
+    textures: Vec<Texture>,
+    options: TextureOptions,
+    previous_framebuffer: Cell<GLint>,
+    previous_viewport: Cell<[GLint; 4]>,
+}
+
+impl RenderTarget {
+    pub fn new(
+        width: u32,
+        height: u32,
+        options: TextureOptions,
+    ) -> Result<Self, String> {
+        Self::with_color_attachments(width, height, 1, options)
+    }
+
+    /// Target with `count` color textures of the same size, for shaders
+    /// that write several outputs at once.
+    pub fn with_color_attachments(
+        width: u32,
+        height: u32,
+        count: usize,
+        options: TextureOptions,
+    ) -> Result<Self, String> {
+        if count == 0 {
+            return Err("A render target needs at least one color attachment"
+                .to_string());
+        }
+
+        let mut target = Self {
+            framebuffer: 0,
+            textures: Self::create_textures(width, height, count, options)?,
+            options,
+            previous_framebuffer: Cell::new(0),
+            previous_viewport: Cell::new([0; 4]),
+        };
+
+        unsafe {
+            gl::GenFramebuffers(1, &mut target.framebuffer);
+        }
+        target.attach()?;
+        Ok(target)
+    }
+
+    /// First color texture.
+    pub fn texture(&self) -> &Texture {
+        &self.textures[0]
+    }
+
+    pub fn color_texture(
+        &self,
+        index: usize,
+    ) -> Option<&Texture> {
+        self.textures.get(index)
+    }
+
+    pub fn color_attachment_count(&self) -> usize {
+        self.textures.len()
+    }
+
+    pub fn width(&self) -> u32 {
+        self.texture().width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.texture().height()
+    }
+
+    /// Recreate the color textures at a new size. The contents are lost.
+    pub fn resize(
+        &mut self,
+        width: u32,
+        height: u32,
+    ) -> Result<(), String> {
+        if width.max(1) == self.width() && height.max(1) == self.height() {
+            return Ok(());
+        }
+        self.textures = Self::create_textures(
+            width,
+            height,
+            self.textures.len(),
+            self.options,
+        )?;
+        self.attach()
+    }
+
+    /// Redirect drawing into this target and set the viewport to cover it.
+    /// The previous framebuffer and viewport are restored by `unbind`.
+    pub fn bind(&self) {
+        unsafe {
+            let mut framebuffer = 0;
+            let mut viewport = [0; 4];
+            gl::GetIntegerv(gl::FRAMEBUFFER_BINDING, &mut framebuffer);
+            gl::GetIntegerv(gl::VIEWPORT, viewport.as_mut_ptr());
+            self.previous_framebuffer.set(framebuffer);
+            self.previous_viewport.set(viewport);
+
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.framebuffer);
+            gl::Viewport(0, 0, self.width() as i32, self.height() as i32);
+        }
+    }
+
+    pub fn unbind(&self) {
+        let [x, y, width, height] = self.previous_viewport.get();
+        unsafe {
+            gl::BindFramebuffer(
+                gl::FRAMEBUFFER,
+                self.previous_framebuffer.get() as GLuint,
+            );
+            gl::Viewport(x, y, width, height);
+        }
+    }
+
+    /// Fill every color texture with one color. The target must be bound.
+    pub fn clear(
+        &self,
+        r: f32,
+        g: f32,
+        b: f32,
+        a: f32,
+    ) {
+        unsafe {
+            gl::ClearColor(r, g, b, a);
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+        }
+    }
+
+    fn create_textures(
+        width: u32,
+        height: u32,
+        count: usize,
+        options: TextureOptions,
+    ) -> Result<Vec<Texture>, String> {
+        let width = width.max(1);
+        let height = height.max(1);
+        let pixels = vec![0; (width * height * 4) as usize];
+        (0..count)
+            .map(|_| Texture::from_rgba_bytes(width, height, &pixels, options))
+            .collect()
+    }
+
+    fn attach(&self) -> Result<(), String> {
+        unsafe {
+            let mut previous = 0;
+            gl::GetIntegerv(gl::FRAMEBUFFER_BINDING, &mut previous);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.framebuffer);
+
+            let mut draw_buffers = Vec::with_capacity(self.textures.len());
+            for (i, texture) in self.textures.iter().enumerate() {
+                let attachment = gl::COLOR_ATTACHMENT0 + i as GLenum;
+                gl::FramebufferTexture2D(
+                    gl::FRAMEBUFFER,
+                    attachment,
+                    gl::TEXTURE_2D,
+                    texture.id(),
+                    0,
+                );
+                draw_buffers.push(attachment);
+            }
+            gl::DrawBuffers(
+                draw_buffers.len() as GLsizei,
+                draw_buffers.as_ptr(),
+            );
+
+            let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, previous as GLuint);
+
+            if status != gl::FRAMEBUFFER_COMPLETE {
+                return Err(format!(
+                    "Framebuffer is incomplete (status 0x{:x})",
+                    status
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for RenderTarget {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteFramebuffers(1, &self.framebuffer);
+        }
+    }
+}
@@ -0,0 +1,226 @@
+use glam::Vec2;
+
+use super::post_process::integer_scaled_rect;
+
+/// How a design resolution is fitted into a window of another size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResizePolicy {
+    /// Fill the window and distort the aspect ratio.
+    Stretch,
+    /// Scale as much as fits while keeping the aspect ratio, with black
+    /// bars on the remaining sides.
+    #[default]
+    Letterbox,
+    /// Like `Letterbox`, but only by whole factors. The frame is rendered
+    /// at the design resolution and upscaled, so pixels stay square.
+    IntegerScale,
+    /// Keep the aspect ratio and show more of the world along the longer
+    /// axis instead of adding bars.
+    Expand,
+}
+
+impl ResizePolicy {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "stretch" => Some(Self::Stretch),
+            "letterbox" => Some(Self::Letterbox),
+            "integer" | "integer_scale" => Some(Self::IntegerScale),
+            "expand" => Some(Self::Expand),
+            _ => None,
+        }
+    }
+}
+
+/// Maps between the window, in physical pixels, and the view the game
+/// draws in.
+///
+/// Without a design resolution the view is the window size in logical
+/// pixels, so a game looks the same on HiDPI screens. With one, the view
+/// is the design size (or larger, for [`ResizePolicy::Expand`]) and
+/// `output_rect` is the part of the window it is shown in.
+///
+/// ```
+/// use glam::Vec2;
+/// use teengine::render::{ResizePolicy, Viewport};
+///
+/// let mut viewport = Viewport::new((1600, 900), 1.0);
+/// viewport.set_design_size(Some((320, 240)));
+/// viewport.set_policy(ResizePolicy::Letterbox);
+///
+/// // 4:3 in a 16:9 window gets bars on the left and right
+/// assert_eq!(viewport.output_rect(), [200, 0, 1200, 900]);
+/// assert_eq!(
+///     viewport.window_to_view(Vec2::new(800.0, 450.0)),
+///     Vec2::new(160.0, 120.0)
+/// );
+///
+/// viewport.set_policy(ResizePolicy::Expand);
+/// assert_eq!(viewport.view_size(), Vec2::new(426.0, 240.0));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    window_size: (u32, u32),
+    scale_factor: f64,
+    design_size: Option<(u32, u32)>,
+    policy: ResizePolicy,
+}
+
+impl Viewport {
+    pub fn new(
+        window_size: (u32, u32),
+        scale_factor: f64,
+    ) -> Self {
+        let mut viewport = Self {
+            window_size: (1, 1),
+            scale_factor: 1.0,
+            design_size: None,
+            policy: ResizePolicy::default(),
+        };
+        viewport.set_window_size(window_size);
+        viewport.set_scale_factor(scale_factor);
+        viewport
+    }
+
+    /// Window size in physical pixels.
+    pub fn window_size(&self) -> (u32, u32) {
+        self.window_size
+    }
+
+    pub fn set_window_size(
+        &mut self,
+        window_size: (u32, u32),
+    ) {
+        self.window_size = (window_size.0.max(1), window_size.1.max(1));
+    }
+
+    /// Physical pixels per logical pixel, as reported by the window.
+    pub fn scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
+
+    pub fn set_scale_factor(
+        &mut self,
+        scale_factor: f64,
+    ) {
+        if scale_factor > 0.0 {
+            self.scale_factor = scale_factor;
+        }
+    }
+
+    pub fn design_size(&self) -> Option<(u32, u32)> {
+        self.design_size
+    }
+
+    pub fn set_design_size(
+        &mut self,
+        design_size: Option<(u32, u32)>,
+    ) {
+        self.design_size =
+            design_size.map(|(width, height)| (width.max(1), height.max(1)));
+    }
+
+    pub fn policy(&self) -> ResizePolicy {
+        self.policy
+    }
+
+    pub fn set_policy(
+        &mut self,
+        policy: ResizePolicy,
+    ) {
+        self.policy = policy;
+    }
+
+    /// Window size in logical pixels.
+    pub fn logical_window_size(&self) -> Vec2 {
+        self.physical_window_size() / self.scale_factor as f32
+    }
+
+    /// Size of the area the game draws in, in view units. This is what
+    /// `Engine::projection` and the camera are set up for.
+    pub fn view_size(&self) -> Vec2 {
+        let Some(design) = self.design_vec() else {
+            return self.logical_window_size();
+        };
+        match self.policy {
+            ResizePolicy::Expand => {
+                let window = self.physical_window_size();
+                let scale = (window / design).min_element();
+                (window / scale).floor().max(design)
+            }
+            _ => design,
+        }
+    }
+
+    /// Part of the window the view is shown in, as `[x, y, width, height]`
+    /// in physical pixels from the bottom left, ready for `gl::Viewport`.
+    pub fn output_rect(&self) -> [i32; 4] {
+        let (width, height) = self.window_size;
+        let full = [0, 0, width as i32, height as i32];
+        let Some(design) = self.design_size else {
+            return full;
+        };
+        match self.policy {
+            ResizePolicy::Stretch | ResizePolicy::Expand => full,
+            ResizePolicy::IntegerScale => {
+                integer_scaled_rect(design, self.window_size)
+            }
+            ResizePolicy::Letterbox => {
+                let window = self.physical_window_size();
+                let design = Vec2::new(design.0 as f32, design.1 as f32);
+                let size = (design * (window / design).min_element()).round();
+                let offset = ((window - size) * 0.5).floor();
+                [
+                    offset.x as i32,
+                    offset.y as i32,
+                    size.x as i32,
+                    size.y as i32,
+                ]
+            }
+        }
+    }
+
+    /// Convert a window position in physical pixels, with the origin at
+    /// the top left like cursor events, to view units. Positions on the
+    /// bars fall outside `0..view_size`.
+    pub fn window_to_view(
+        &self,
+        position: Vec2,
+    ) -> Vec2 {
+        let (origin, size) = self.output_origin_and_size();
+        (position - origin) / size * self.view_size()
+    }
+
+    /// Inverse of [`Viewport::window_to_view`].
+    pub fn view_to_window(
+        &self,
+        position: Vec2,
+    ) -> Vec2 {
+        let (origin, size) = self.output_origin_and_size();
+        position / self.view_size() * size + origin
+    }
+
+    /// Whether the frame has to be drawn offscreen at the view size and
+    /// scaled up afterwards.
+    pub fn needs_offscreen(&self) -> bool {
+        self.design_size.is_some() && self.policy == ResizePolicy::IntegerScale
+    }
+
+    /// Top left corner and size of the output rect in window coordinates.
+    fn output_origin_and_size(&self) -> (Vec2, Vec2) {
+        let [x, y, width, height] = self.output_rect();
+        let top = self.window_size.1 as i32 - y - height;
+        (
+            Vec2::new(x as f32, top as f32),
+            Vec2::new(width.max(1) as f32, height.max(1) as f32),
+        )
+    }
+
+    fn physical_window_size(&self) -> Vec2 {
+        Vec2::new(self.window_size.0 as f32, self.window_size.1 as f32)
+    }
+
+    fn design_vec(&self) -> Option<Vec2> {
+        self.design_size
+            .map(|(width, height)| Vec2::new(width as f32, height as f32))
+    }
+}
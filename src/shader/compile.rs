@@ -0,0 +1,108 @@
+use gl::types::*;
+use std::ffi::CString;
+
+/// Compile one shader stage. On failure the shader is deleted and the
+/// driver's info log is returned, prefixed with the stage name.
+pub(crate) fn compile_shader(
+    shader_type: GLenum,
+    source: &str,
+) -> Result<GLuint, String> {
+    let stage = match shader_type {
+        gl::VERTEX_SHADER => "Vertex",
+        gl::FRAGMENT_SHADER => "Fragment",
+        _ => "Unknown",
+    };
+    let source = CString::new(source.as_bytes())
+        .map_err(|_| format!("{} shader source contains a nul byte", stage))?;
+
+    unsafe {
+        let shader = gl::CreateShader(shader_type);
+        gl::ShaderSource(shader, 1, &source.as_ptr(), std::ptr::null());
+        gl::CompileShader(shader);
+
+        let mut success = gl::FALSE as GLint;
+        gl::GetShaderiv(shader, gl::COMPILE_STATUS, &mut success);
+
+        if success != gl::TRUE as GLint {
+            let mut len = 0;
+            gl::GetShaderiv(shader, gl::INFO_LOG_LENGTH, &mut len);
+            let mut buffer = vec![0u8; len.max(1) as usize];
+            gl::GetShaderInfoLog(
+                shader,
+                len,
+                std::ptr::null_mut(),
+                buffer.as_mut_ptr() as *mut _,
+            );
+            gl::DeleteShader(shader);
+            return Err(format!(
+                "{} shader compilation failed: {}",
+                stage,
+                info_log(&buffer)
+            ));
+        }
+
+        Ok(shader)
+    }
+}
+
+/// Link a program and delete both shaders, whether linking succeeds or
+/// not.
+pub(crate) fn link_program(
+    vertex_shader: GLuint,
+    fragment_shader: GLuint,
+) -> Result<GLuint, String> {
+    unsafe {
+        let program = gl::CreateProgram();
+        gl::AttachShader(program, vertex_shader);
+        gl::AttachShader(program, fragment_shader);
+        gl::LinkProgram(program);
+
+        gl::DeleteShader(vertex_shader);
+        gl::DeleteShader(fragment_shader);
+
+        let mut success = gl::FALSE as GLint;
+        gl::GetProgramiv(program, gl::LINK_STATUS, &mut success);
+
+        if success != gl::TRUE as GLint {
+            let mut len = 0;
+            gl::GetProgramiv(program, gl::INFO_LOG_LENGTH, &mut len);
+            let mut buffer = vec![0u8; len.max(1) as usize];
+            gl::GetProgramInfoLog(
+                program,
+                len,
+                std::ptr::null_mut(),
+                buffer.as_mut_ptr() as *mut _,
+            );
+            gl::DeleteProgram(program);
+            return Err(format!(
+                "Program linking failed: {}",
+                info_log(&buffer)
+            ));
+        }
+
+        Ok(program)
+    }
+}
+
+/// Compile both stages and link them into a program.
+pub(crate) fn build_program(
+    vertex_source: &str,
+    fragment_source: &str,
+) -> Result<GLuint, String> {
+    let vertex_shader = compile_shader(gl::VERTEX_SHADER, vertex_source)?;
+    let fragment_shader =
+        match compile_shader(gl::FRAGMENT_SHADER, fragment_source) {
+            Ok(shader) => shader,
+            Err(e) => {
+                unsafe { gl::DeleteShader(vertex_shader) };
+                return Err(e);
+            }
+        };
+    link_program(vertex_shader, fragment_shader)
+}
+
+fn info_log(buffer: &[u8]) -> String {
+    String::from_utf8_lossy(buffer)
+        .trim_end_matches(['\0', '\n', ' '])
+        .to_string()
+}
@@ -0,0 +1,145 @@
+//! Fragment shaders for the built-in sprite and tile materials.
+//!
+//! They follow the material contract described on [`super::Material`] and
+//! honour the sprite color key, so they work with both renderers.
+
+/// Color key uniforms and `sampleImage`, which samples `image` and makes
+/// color-keyed texels transparent. Shared by every effect shader; expects
+/// `uniform sampler2D image` to be declared before it.
+macro_rules! sample_image {
+    () => {
+        r#"uniform vec3 colorKey;
+uniform bool useColorKey;
+uniform float threshold;
+
+vec4 sampleImage(vec2 uv) {
+    vec4 color = texture(image, uv);
+    if (useColorKey) {
+        vec3 diff = abs(color.rgb - colorKey);
+        if (max(max(diff.r, diff.g), diff.b) < threshold) {
+            color.a = 0.0;
+        }
+    }
+    return color;
+}
+"#
+    };
+}
+
+/// Blends the sprite towards `flashColor` by `flashAmount` (0.0 to 1.0),
+/// keeping its alpha. Typically used to flash white when hit.
+pub const HIT_FLASH_FRAGMENT_SHADER: &str = concat!(
+    r#"
+#version 330 core
+in vec2 TexCoords;
+in vec4 Color;
+out vec4 FragColor;
+
+uniform sampler2D image;
+uniform vec4 flashColor;
+uniform float flashAmount;
+
+"#,
+    sample_image!(),
+    r#"
+void main() {
+    vec4 color = sampleImage(TexCoords) * Color;
+    if (color.a <= 0.0) {
+        discard;
+    }
+    vec3 flashed = mix(color.rgb, flashColor.rgb, flashAmount * flashColor.a);
+    FragColor = vec4(flashed, color.a);
+}
+"#
+);
+
+/// Draws `outlineColor` on transparent pixels next to opaque ones, up to
+/// `outlineThickness` texels away. The outline stays inside the quad, so
+/// sprite frames need a transparent border.
+pub const OUTLINE_FRAGMENT_SHADER: &str = concat!(
+    r#"
+#version 330 core
+in vec2 TexCoords;
+in vec4 Color;
+out vec4 FragColor;
+
+uniform sampler2D image;
+uniform vec4 outlineColor;
+uniform float outlineThickness;
+
+"#,
+    sample_image!(),
+    r#"
+void main() {
+    vec4 color = sampleImage(TexCoords) * Color;
+    if (color.a > 0.5) {
+        FragColor = color;
+        return;
+    }
+
+    vec2 texel = outlineThickness / vec2(textureSize(image, 0));
+    float neighbours = 0.0;
+    for (int x = -1; x <= 1; x++) {
+        for (int y = -1; y <= 1; y++) {
+            neighbours = max(
+                neighbours,
+                sampleImage(TexCoords + vec2(x, y) * texel).a
+            );
+        }
+    }
+    if (neighbours <= 0.5) {
+        discard;
+    }
+    FragColor = vec4(outlineColor.rgb, outlineColor.a * Color.a);
+}
+"#
+);
+
+/// Eats the sprite away with value noise as `dissolveAmount` goes from 0.0
+/// to 1.0, with an `edgeColor` rim `edgeWidth` wide. `noiseScale` is the
+/// size of a noise cell in texels.
+pub const DISSOLVE_FRAGMENT_SHADER: &str = concat!(
+    r#"
+#version 330 core
+in vec2 TexCoords;
+in vec4 Color;
+out vec4 FragColor;
+
+uniform sampler2D image;
+uniform float dissolveAmount;
+uniform vec4 edgeColor;
+uniform float edgeWidth;
+uniform float noiseScale;
+
+"#,
+    sample_image!(),
+    r#"
+float hash(vec2 p) {
+    return fract(sin(dot(p, vec2(127.1, 311.7))) * 43758.5453);
+}
+
+float noise(vec2 p) {
+    vec2 cell = floor(p);
+    vec2 f = fract(p);
+    vec2 u = f * f * (3.0 - 2.0 * f);
+    return mix(
+        mix(hash(cell), hash(cell + vec2(1.0, 0.0)), u.x),
+        mix(hash(cell + vec2(0.0, 1.0)), hash(cell + vec2(1.0, 1.0)), u.x),
+        u.y
+    );
+}
+
+void main() {
+    vec4 color = sampleImage(TexCoords) * Color;
+    vec2 texelPosition = TexCoords * vec2(textureSize(image, 0));
+    float n = noise(texelPosition / max(noiseScale, 0.001));
+    if (color.a <= 0.0 || n < dissolveAmount) {
+        discard;
+    }
+    if (n < dissolveAmount + edgeWidth && dissolveAmount > 0.0) {
+        color.rgb = edgeColor.rgb;
+    }
+    FragColor = color;
+}
+"#
+);
@@ -0,0 +1,184 @@
+use gl::types::*;
+
+use super::effects;
+use super::program::ShaderProgram;
+use super::uniform::UniformValue;
+use crate::sprite::sprite_shaders::SPRITE_VERTEX_SHADER;
+use crate::texture::texture::Texture;
+use crate::tile::properties::Color;
+use crate::tile::tile_shaders::TILEMAP_VERTEX_SHADER;
+
+/// Shader plus the uniform values and textures it is drawn with.
+///
+/// Attach one to a `Sprite` or a `TileLayer` to draw it with a custom
+/// fragment shader. The fragment shader receives:
+///
+/// ```glsl
+/// in vec2 TexCoords;
+/// in vec4 Color;            // vertex tint; fog of war shade for tiles
+/// out vec4 FragColor;
+///
+/// uniform sampler2D image;  // sprite texture or tileset
+/// uniform vec3 colorKey;    // sprite color key, if `useColorKey` is set
+/// uniform bool useColorKey;
+/// uniform float threshold;
+/// ```
+///
+/// Extra textures are bound to units 1 and up. On sprites with a palette,
+/// unit 1 holds the palette as `palette` and extra textures start at 2.
+/// Clones share the shader but keep their own uniform values, so one
+/// program can drive many sprites with different parameters.
+///
+/// ```no_run
+/// use teengine::shader::{effects, Material};
+/// use teengine::tile::Color;
+///
+/// # fn example(sprite: &mut teengine::Sprite) -> Result<(), String> {
+/// let mut flash = Material::hit_flash(Color::WHITE)?;
+/// flash.set_uniform("flashAmount", 0.8);
+/// sprite.material = Some(flash);
+///
+/// // The same shaders work for tile layers with the tile vertex shader
+/// let water = Material::for_tiles(effects::DISSOLVE_FRAGMENT_SHADER)?
+///     .with_uniform("dissolveAmount", 0.3);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Material {
+    shader: ShaderProgram,
+    uniforms: Vec<(String, UniformValue)>,
+    textures: Vec<(String, Texture)>,
+}
+
+impl Material {
+    pub fn new(shader: ShaderProgram) -> Self {
+        Self {
+            shader,
+            uniforms: Vec::new(),
+            textures: Vec::new(),
+        }
+    }
+
+    /// Material for sprites from a fragment shader following the contract
+    /// above, paired with the built-in sprite vertex shader.
+    pub fn for_sprites(fragment_source: &str) -> Result<Self, String> {
+        ShaderProgram::from_source(SPRITE_VERTEX_SHADER, fragment_source)
+            .map(Self::new)
+    }
+
+    /// Material for tile layers, paired with the built-in tile vertex
+    /// shader.
+    pub fn for_tiles(fragment_source: &str) -> Result<Self, String> {
+        ShaderProgram::from_source(TILEMAP_VERTEX_SHADER, fragment_source)
+            .map(Self::new)
+    }
+
+    /// Sprite material that tints towards `color` by `flashAmount`, which
+    /// starts at 0.0.
+    pub fn hit_flash(color: Color) -> Result<Self, String> {
+        Ok(Self::for_sprites(effects::HIT_FLASH_FRAGMENT_SHADER)?
+            .with_uniform("flashColor", color.to_vec4())
+            .with_uniform("flashAmount", 0.0))
+    }
+
+    /// Sprite material drawing a `thickness` texel outline around opaque
+    /// pixels.
+    pub fn outline(
+        color: Color,
+        thickness: f32,
+    ) -> Result<Self, String> {
+        Ok(Self::for_sprites(effects::OUTLINE_FRAGMENT_SHADER)?
+            .with_uniform("outlineColor", color.to_vec4())
+            .with_uniform("outlineThickness", thickness))
+    }
+
+    /// Sprite material that dissolves the sprite as `dissolveAmount` goes
+    /// from 0.0 to 1.0.
+    pub fn dissolve(amount: f32) -> Result<Self, String> {
+        Ok(Self::for_sprites(effects::DISSOLVE_FRAGMENT_SHADER)?
+            .with_uniform("dissolveAmount", amount)
+            .with_uniform("edgeColor", Color::new(1.0, 0.6, 0.2, 1.0).to_vec4())
+            .with_uniform("edgeWidth", 0.08)
+            .with_uniform("noiseScale", 4.0))
+    }
+
+    pub fn shader(&self) -> &ShaderProgram {
+        &self.shader
+    }
+
+    pub fn with_uniform(
+        mut self,
+        name: &str,
+        value: impl Into<UniformValue>,
+    ) -> Self {
+        self.set_uniform(name, value);
+        self
+    }
+
+    pub fn set_uniform(
+        &mut self,
+        name: &str,
+        value: impl Into<UniformValue>,
+    ) {
+        let value = value.into();
+        match self.uniforms.iter_mut().find(|(n, _)| n == name) {
+            Some((_, current)) => *current = value,
+            None => self.uniforms.push((name.to_string(), value)),
+        }
+    }
+
+    pub fn uniform(
+        &self,
+        name: &str,
+    ) -> Option<UniformValue> {
+        self.uniforms
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| *value)
+    }
+
+    /// Bind `texture` to the sampler uniform `name`.
+    pub fn with_texture(
+        mut self,
+        name: &str,
+        texture: Texture,
+    ) -> Self {
+        self.set_texture(name, texture);
+        self
+    }
+
+    pub fn set_texture(
+        &mut self,
+        name: &str,
+        texture: Texture,
+    ) {
+        match self.textures.iter_mut().find(|(n, _)| n == name) {
+            Some((_, current)) => *current = texture,
+            None => self.textures.push((name.to_string(), texture)),
+        }
+    }
+
+    /// Upload the stored uniforms and bind the textures from `first_unit`
+    /// on. The shader must be in use.
+    pub(crate) fn apply(
+        &self,
+        first_unit: u32,
+    ) {
+        for (unit, (name, texture)) in self.textures.iter().enumerate() {
+            let unit = (unit as u32 + first_unit) as GLint;
+            unsafe {
+                gl::ActiveTexture(gl::TEXTURE0 + unit as GLenum);
+            }
+            texture.bind();
+            self.shader.set_int(name, unit);
+        }
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0);
+        }
+
+        for (name, value) in &self.uniforms {
+            self.shader.set_uniform(name, *value);
+        }
+    }
+}
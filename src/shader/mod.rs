@@ -0,0 +1,9 @@
+mod compile;
+pub mod effects;
+pub mod material;
+pub mod program;
+pub mod uniform;
+
+pub use material::Material;
+pub use program::*;
+pub use uniform::UniformValue;
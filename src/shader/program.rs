@@ -0,0 +1,264 @@
+use gl::types::*;
+use glam::{Mat4, Vec2, Vec3, Vec4};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use super::compile::build_program;
+use super::uniform::UniformValue;
+use crate::hot_reload::Reloadable;
+
+/// Linked GL shader program.
+///
+/// Clones share the same program. When the program was built from files it
+/// can be rebuilt in place with [`ShaderProgram::reload`]; renderers look up
+/// the current id on every draw, so they pick up the new program on the
+/// next frame.
+///
+/// Uniform locations are looked up once and cached until the next reload.
+/// The typed setters write to the program currently in use, so call
+/// [`ShaderProgram::use_program`] first. Uniforms the program does not
+/// have are ignored.
+#[derive(Debug, Clone)]
+pub struct ShaderProgram {
+    inner: Rc<ShaderProgramInner>,
+}
+
+#[derive(Debug)]
+pub(crate) struct ShaderProgramInner {
+    id: Cell<GLuint>,
+    locations: RefCell<HashMap<String, GLint>>,
+    vertex_path: Option<PathBuf>,
+    fragment_path: Option<PathBuf>,
+}
+
+impl ShaderProgram {
+    pub fn from_source(
+        vertex_source: &str,
+        fragment_source: &str,
+    ) -> Result<Self, String> {
+        let id = build_program(vertex_source, fragment_source)?;
+
+        Ok(Self {
+            inner: Rc::new(ShaderProgramInner {
+                id: Cell::new(id),
+                locations: RefCell::new(HashMap::new()),
+                vertex_path: None,
+                fragment_path: None,
+            }),
+        })
+    }
+
+    pub fn from_files(
+        vertex_path: &Path,
+        fragment_path: &Path,
+    ) -> Result<Self, String> {
+        let (vertex_source, fragment_source) =
+            Self::read_sources(vertex_path, fragment_path)?;
+        let id = build_program(&vertex_source, &fragment_source)?;
+
+        Ok(Self {
+            inner: Rc::new(ShaderProgramInner {
+                id: Cell::new(id),
+                locations: RefCell::new(HashMap::new()),
+                vertex_path: Some(vertex_path.to_path_buf()),
+                fragment_path: Some(fragment_path.to_path_buf()),
+            }),
+        })
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.inner.id.get()
+    }
+
+    pub fn use_program(&self) {
+        unsafe {
+            gl::UseProgram(self.id());
+        }
+    }
+
+    /// Location of the uniform `name`, or -1 if the program has none.
+    pub fn uniform_location(
+        &self,
+        name: &str,
+    ) -> GLint {
+        if let Some(&location) = self.inner.locations.borrow().get(name) {
+            return location;
+        }
+
+        let location = match CString::new(name) {
+            Ok(c_name) => unsafe {
+                gl::GetUniformLocation(self.id(), c_name.as_ptr())
+            },
+            Err(_) => -1,
+        };
+        self.inner
+            .locations
+            .borrow_mut()
+            .insert(name.to_string(), location);
+        location
+    }
+
+    pub fn set_bool(
+        &self,
+        name: &str,
+        value: bool,
+    ) {
+        self.set_int(name, value as i32);
+    }
+
+    pub fn set_int(
+        &self,
+        name: &str,
+        value: i32,
+    ) {
+        unsafe {
+            gl::Uniform1i(self.uniform_location(name), value);
+        }
+    }
+
+    pub fn set_float(
+        &self,
+        name: &str,
+        value: f32,
+    ) {
+        unsafe {
+            gl::Uniform1f(self.uniform_location(name), value);
+        }
+    }
+
+    pub fn set_vec2(
+        &self,
+        name: &str,
+        value: Vec2,
+    ) {
+        unsafe {
+            gl::Uniform2f(self.uniform_location(name), value.x, value.y);
+        }
+    }
+
+    pub fn set_vec3(
+        &self,
+        name: &str,
+        value: Vec3,
+    ) {
+        unsafe {
+            gl::Uniform3f(
+                self.uniform_location(name),
+                value.x,
+                value.y,
+                value.z,
+            );
+        }
+    }
+
+    pub fn set_vec4(
+        &self,
+        name: &str,
+        value: Vec4,
+    ) {
+        unsafe {
+            gl::Uniform4f(
+                self.uniform_location(name),
+                value.x,
+                value.y,
+                value.z,
+                value.w,
+            );
+        }
+    }
+
+    pub fn set_mat4(
+        &self,
+        name: &str,
+        value: &Mat4,
+    ) {
+        unsafe {
+            gl::UniformMatrix4fv(
+                self.uniform_location(name),
+                1,
+                gl::FALSE,
+                value.to_cols_array().as_ptr(),
+            );
+        }
+    }
+
+    pub fn set_uniform(
+        &self,
+        name: &str,
+        value: UniformValue,
+    ) {
+        match value {
+            UniformValue::Int(v) => self.set_int(name, v),
+            UniformValue::Float(v) => self.set_float(name, v),
+            UniformValue::Vec2(v) => self.set_vec2(name, v),
+            UniformValue::Vec3(v) => self.set_vec3(name, v),
+            UniformValue::Vec4(v) => self.set_vec4(name, v),
+            UniformValue::Mat4(v) => self.set_mat4(name, &v),
+        }
+    }
+
+    /// Recompile the program from its source files. If compiling or linking
+    /// fails the previous program stays in use.
+    pub fn reload(&self) -> Result<(), String> {
+        self.inner.reload()
+    }
+
+    pub(crate) fn inner(&self) -> &Rc<ShaderProgramInner> {
+        &self.inner
+    }
+
+    fn read_sources(
+        vertex_path: &Path,
+        fragment_path: &Path,
+    ) -> Result<(String, String), String> {
+        let vertex_source =
+            std::fs::read_to_string(vertex_path).map_err(|e| {
+                format!("Failed to read {}: {}", vertex_path.display(), e)
+            })?;
+        let fragment_source =
+            std::fs::read_to_string(fragment_path).map_err(|e| {
+                format!("Failed to read {}: {}", fragment_path.display(), e)
+            })?;
+        Ok((vertex_source, fragment_source))
+    }
+}
+
+impl Reloadable for ShaderProgramInner {
+    fn source_paths(&self) -> Vec<PathBuf> {
+        self.vertex_path
+            .iter()
+            .chain(self.fragment_path.iter())
+            .cloned()
+            .collect()
+    }
+
+    fn reload(&self) -> Result<(), String> {
+        let (Some(vertex_path), Some(fragment_path)) =
+            (&self.vertex_path, &self.fragment_path)
+        else {
+            return Err("Shader was not loaded from files".to_string());
+        };
+
+        let (vertex_source, fragment_source) =
+            ShaderProgram::read_sources(vertex_path, fragment_path)?;
+        let id = build_program(&vertex_source, &fragment_source)?;
+
+        let old = self.id.replace(id);
+        self.locations.borrow_mut().clear();
+        unsafe {
+            gl::DeleteProgram(old);
+        }
+        Ok(())
+    }
+}
+
+impl Drop for ShaderProgramInner {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteProgram(self.id.get());
+        }
+    }
+}
@@ -1,5 +1,6 @@
 pub mod animation;
 pub mod animation_sprite;
+#[allow(clippy::module_inception)]
 pub mod sprite;
 pub mod sprite_renderer;
 pub mod sprite_shaders;
@@ -1,4 +1,7 @@
+use crate::shader::Material;
 use crate::sprite::utils::color_key_util::ColorKey;
+use crate::texture::atlas::TextureAtlas;
+use crate::texture::palette::PaletteTexture;
 use crate::texture::texture::Texture;
 use glam::Vec2;
 
@@ -23,13 +26,23 @@ pub struct Sprite {
     pub position: Vec2,
     pub rotation: f32,
     pub tex_coords: [Vec2; 4],
+    /// Color discarded per fragment while drawing. Baking the key into the
+    /// texture with `TextureOptions::with_color_key` is cheaper and blends
+    /// edges correctly.
     pub color_key: Option<ColorKey>,
     pub sprite_size: Rect,
+    /// Custom shader the sprite is drawn with instead of the renderer's.
+    pub material: Option<Material>,
+    /// Palettes for an indexed `texture`. When set, the texture's red
+    /// channel is a palette index and the colors come from `palette_row`.
+    pub palette: Option<PaletteTexture>,
+    pub palette_row: u32,
     pixel_scale: f32,
     current_frame: usize,
     frames_per_row: usize,
     frames_per_column: usize,
     sheet_size: Rect,
+    uv_rect: (Vec2, Vec2),
 }
 
 impl Sprite {
@@ -46,31 +59,79 @@ impl Sprite {
         let frames_per_column =
             (sheet_size.height / sprite_size.height) as usize;
 
-        let color_key = if let Some(hex) = color_key {
-            Some(ColorKey::from_hex(hex, 0.01).unwrap())
-        } else {
-            None
-        };
+        let color_key = color_key.map(|hex| {
+            ColorKey::from_hex(hex, ColorKey::DEFAULT_THRESHOLD).unwrap()
+        });
 
         Self {
             texture,
             position,
             rotation,
             current_frame: 0,
-            tex_coords: Self::get_frame_coords(
-                0,
-                sprite_size.clone(),
-                sheet_size.clone(),
-            ),
+            tex_coords: Self::get_frame_coords(0, sprite_size, sheet_size),
             frames_per_row,
             frames_per_column,
             sprite_size,
             sheet_size,
             pixel_scale,
             color_key,
+            material: None,
+            palette: None,
+            palette_row: 0,
+            uv_rect: (Vec2::ZERO, Vec2::ONE),
         }
     }
 
+    /// Create a sprite from an image packed into a texture atlas. The packed
+    /// image is used as the sprite sheet, so `sprite_size` still selects
+    /// frames inside it.
+    pub fn from_atlas(
+        atlas: &TextureAtlas,
+        name: &str,
+        position: Vec2,
+        rotation: f32,
+        sprite_size: Rect,
+        pixel_scale: f32,
+        color_key: Option<&str>,
+    ) -> Result<Self, String> {
+        let region = atlas
+            .region(name)
+            .ok_or_else(|| format!("Atlas has no image named {}", name))?;
+
+        let mut sprite = Self::new(
+            atlas.texture().clone(),
+            position,
+            rotation,
+            sprite_size,
+            Rect::new(region.width as f32, region.height as f32),
+            pixel_scale,
+            color_key,
+        );
+        sprite.set_uv_rect(atlas.uv_rect(name).unwrap());
+        Ok(sprite)
+    }
+
+    /// Restrict the sprite sheet to a sub-rectangle of the texture, given as
+    /// normalized `(min, max)` texture coordinates.
+    pub fn set_uv_rect(
+        &mut self,
+        uv_rect: (Vec2, Vec2),
+    ) {
+        self.uv_rect = uv_rect;
+        self.tex_coords = self.frame_coords(self.current_frame);
+    }
+
+    /// Draw the indexed texture with `palettes`, starting on `row`.
+    pub fn with_palette(
+        mut self,
+        palettes: PaletteTexture,
+        row: u32,
+    ) -> Self {
+        self.palette = Some(palettes);
+        self.palette_row = row;
+        self
+    }
+
     /// Set color key from hex string
     ///
     /// # Arguments
@@ -80,15 +141,18 @@ impl Sprite {
     /// * `Result<(), String>` - Result of operation
     ///
     /// # Examples
-    /// ```
+    /// ```no_run
+    /// # fn example(sprite: &mut teengine::Sprite) {
     /// sprite.set_color_key_hex("#FFFFFF").unwrap();
+    /// # }
     /// ```
     #[allow(unused)]
     pub fn set_color_key_hex(
         &mut self,
         hex: &str,
     ) -> Result<(), String> {
-        self.color_key = Some(ColorKey::from_hex(hex, 0.5)?);
+        self.color_key =
+            Some(ColorKey::from_hex(hex, ColorKey::DEFAULT_THRESHOLD)?);
         Ok(())
     }
 
@@ -101,8 +165,10 @@ impl Sprite {
     /// * `Result<(), String>` - Result of operation
     ///
     /// # Examples
-    /// ```
+    /// ```no_run
+    /// # fn example(sprite: &mut teengine::Sprite) {
     /// sprite.set_color_key_threshold(0.5).unwrap();
+    /// # }
     /// ```
     #[allow(unused)]
     pub fn set_color_key_threshold(
@@ -158,11 +224,16 @@ impl Sprite {
     ) {
         if frame < self.frames_per_row * self.frames_per_column {
             self.current_frame = frame;
-            self.tex_coords = Self::get_frame_coords(
-                frame,
-                self.sprite_size.clone(),
-                self.sheet_size.clone(),
-            );
+            self.tex_coords = self.frame_coords(frame);
         }
     }
+
+    fn frame_coords(
+        &self,
+        frame: usize,
+    ) -> [Vec2; 4] {
+        let (min, max) = self.uv_rect;
+        Self::get_frame_coords(frame, self.sprite_size, self.sheet_size)
+            .map(|uv| min + uv * (max - min))
+    }
 }
@@ -1,29 +1,100 @@
+use crate::shader::{Material, ShaderProgram};
 use crate::sprite::sprite::Sprite;
 use crate::sprite::sprite_shaders;
+use crate::texture::palette::PaletteTexture;
+use crate::texture::texture::Texture;
 use gl::types::*;
-use glam::{Mat4, Vec3};
+use glam::{Mat4, Vec2, Vec3, Vec4};
+use std::cell::{Cell, RefCell};
+
+// position (2) + texture coordinates (2) + color (4)
+const FLOATS_PER_VERTEX: usize = 8;
+const VERTICES_PER_QUAD: usize = 6;
+
+/// Textured, tinted quad submitted to [`SpriteRenderer::draw_quads`].
+///
+/// Corners and UVs are ordered top left, top right, bottom right, bottom
+/// left. Positions are in world space.
+#[derive(Debug, Clone, Copy)]
+pub struct SpriteQuad {
+    pub corners: [Vec2; 4],
+    pub uvs: [Vec2; 4],
+    pub color: Vec4,
+}
+
+impl SpriteQuad {
+    /// Axis-aligned quad covering `min..max` and sampling `uv_min..uv_max`.
+    pub fn new(
+        min: Vec2,
+        max: Vec2,
+        uv_min: Vec2,
+        uv_max: Vec2,
+        color: Vec4,
+    ) -> Self {
+        Self {
+            corners: [
+                min,
+                Vec2::new(max.x, min.y),
+                max,
+                Vec2::new(min.x, max.y),
+            ],
+            uvs: [
+                uv_min,
+                Vec2::new(uv_max.x, uv_min.y),
+                uv_max,
+                Vec2::new(uv_min.x, uv_max.y),
+            ],
+            color,
+        }
+    }
+
+    /// Rotate the quad by `angle` radians around `origin`.
+    pub fn rotated(
+        mut self,
+        angle: f32,
+        origin: Vec2,
+    ) -> Self {
+        let rotation = Vec2::from_angle(angle);
+        for corner in &mut self.corners {
+            *corner = origin + rotation.rotate(*corner - origin);
+        }
+        self
+    }
+}
 
 pub struct SpriteRenderer {
-    program: GLuint,
+    program: ShaderProgram,
+    indexed_program: ShaderProgram,
     vao: GLuint,
     vbo: GLuint,
+    vbo_capacity: Cell<usize>,
+    batch_vertices: RefCell<Vec<f32>>,
+}
+
+impl Default for SpriteRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl SpriteRenderer {
     pub fn new() -> Self {
-        let vertex_shader = sprite_shaders::compile_shader(
-            gl::VERTEX_SHADER,
+        let program = ShaderProgram::from_source(
             sprite_shaders::SPRITE_VERTEX_SHADER,
-        );
-        let fragment_shader = sprite_shaders::compile_shader(
-            gl::FRAGMENT_SHADER,
             sprite_shaders::SPRITE_FRAGMENT_SHADER,
-        );
-        let program =
-            sprite_shaders::link_program(vertex_shader, fragment_shader);
+        )
+        .expect("Failed to build sprite shader");
+        let indexed_program = ShaderProgram::from_source(
+            sprite_shaders::SPRITE_VERTEX_SHADER,
+            sprite_shaders::INDEXED_SPRITE_FRAGMENT_SHADER,
+        )
+        .expect("Failed to build indexed sprite shader");
 
         let mut vao = 0;
         let mut vbo = 0;
+        let vbo_capacity = VERTICES_PER_QUAD * FLOATS_PER_VERTEX;
+        let stride =
+            (FLOATS_PER_VERTEX * std::mem::size_of::<f32>()) as GLsizei;
 
         unsafe {
             gl::GenVertexArrays(1, &mut vao);
@@ -34,10 +105,11 @@ impl SpriteRenderer {
             // Create and bind VBO
             gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
 
-            // Change vertex data structure (position and texture coordinates)
+            // Change vertex data structure (position, texture coordinates
+            // and color)
             gl::BufferData(
                 gl::ARRAY_BUFFER,
-                (24 * std::mem::size_of::<f32>()) as GLsizeiptr,
+                (vbo_capacity * std::mem::size_of::<f32>()) as GLsizeiptr,
                 std::ptr::null(),
                 gl::DYNAMIC_DRAW,
             );
@@ -48,7 +120,7 @@ impl SpriteRenderer {
                 2,
                 gl::FLOAT,
                 gl::FALSE,
-                4 * std::mem::size_of::<f32>() as GLsizei,
+                stride,
                 std::ptr::null(),
             );
             gl::EnableVertexAttribArray(0);
@@ -59,17 +131,49 @@ impl SpriteRenderer {
                 2,
                 gl::FLOAT,
                 gl::FALSE,
-                4 * std::mem::size_of::<f32>() as GLsizei,
+                stride,
                 (2 * std::mem::size_of::<f32>()) as *const () as *const _,
             );
             gl::EnableVertexAttribArray(1);
 
+            // Color attribute
+            gl::VertexAttribPointer(
+                2,
+                4,
+                gl::FLOAT,
+                gl::FALSE,
+                stride,
+                (4 * std::mem::size_of::<f32>()) as *const () as *const _,
+            );
+            gl::EnableVertexAttribArray(2);
+
             // Unbind
             gl::BindBuffer(gl::ARRAY_BUFFER, 0);
             gl::BindVertexArray(0);
         }
 
-        Self { program, vao, vbo }
+        Self {
+            program,
+            indexed_program,
+            vao,
+            vbo,
+            vbo_capacity: Cell::new(vbo_capacity),
+            batch_vertices: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Replace the shader used to draw sprites. The program must accept the
+    /// same attributes and uniforms as the built-in sprite shader. Sprites
+    /// with a material use the material's shader instead.
+    pub fn set_shader(
+        &mut self,
+        shader: ShaderProgram,
+    ) {
+        self.program = shader;
+    }
+
+    pub fn shader(&self) -> &ShaderProgram {
+        &self.program
     }
 
     pub fn draw_sprite(
@@ -77,113 +181,208 @@ impl SpriteRenderer {
         sprite: &Sprite,
         projection: &Mat4,
     ) {
-        unsafe {
-            gl::UseProgram(self.program);
+        let scale = Vec3::new(
+            sprite.sprite_size.width * sprite.get_pixel_scale(),
+            sprite.sprite_size.height * sprite.get_pixel_scale(),
+            1.0,
+        );
 
-            let scale = Vec3::new(
-                sprite.sprite_size.width * sprite.get_pixel_scale(),
-                sprite.sprite_size.height * sprite.get_pixel_scale(),
-                1.0,
-            );
+        let model = Mat4::from_scale_rotation_translation(
+            scale,
+            glam::Quat::from_rotation_z(sprite.rotation),
+            sprite.position.extend(0.0),
+        );
 
-            let model = Mat4::from_scale_rotation_translation(
-                scale,
-                glam::Quat::from_rotation_z(sprite.rotation),
-                sprite.position.extend(0.0),
-            );
+        let palette = sprite
+            .palette
+            .as_ref()
+            .map(|palette| (palette, sprite.palette_row));
+        let shader =
+            self.begin(sprite.material.as_ref(), palette, &model, projection);
+        match &sprite.color_key {
+            Some(color_key) => {
+                shader.set_bool("useColorKey", true);
+                shader.set_vec3("colorKey", color_key.color);
+                shader.set_float("threshold", color_key.threshold);
+            }
+            None => shader.set_bool("useColorKey", false),
+        }
+        sprite.texture.bind();
 
-            let model_loc = gl::GetUniformLocation(
-                self.program,
-                b"model\0".as_ptr() as *const _,
-            );
-            gl::UniformMatrix4fv(
-                model_loc,
-                1,
-                gl::FALSE,
-                model.to_cols_array().as_ptr(),
-            );
+        // Unit quad scaled by the model matrix
+        let quad = SpriteQuad {
+            corners: [
+                Vec2::new(0.0, 0.0),
+                Vec2::new(1.0, 0.0),
+                Vec2::new(1.0, 1.0),
+                Vec2::new(0.0, 1.0),
+            ],
+            uvs: [
+                sprite.tex_coords[3],
+                sprite.tex_coords[2],
+                sprite.tex_coords[1],
+                sprite.tex_coords[0],
+            ],
+            color: Vec4::ONE,
+        };
 
-            let projection_loc = gl::GetUniformLocation(
-                self.program,
-                b"projection\0".as_ptr() as *const _,
-            );
-            gl::UniformMatrix4fv(
-                projection_loc,
-                1,
-                gl::FALSE,
-                projection.to_cols_array().as_ptr(),
-            );
+        let mut vertices = self.batch_vertices.borrow_mut();
+        vertices.clear();
+        Self::push_quad(&mut vertices, &quad);
+        self.upload_and_draw(&vertices);
+    }
 
-            let use_color_key_loc = gl::GetUniformLocation(
-                self.program,
-                b"useColorKey\0".as_ptr() as *const _,
-            );
+    /// Draw many quads sharing one texture with a single draw call.
+    ///
+    /// Used by text, particles and debug drawing; quads are already in
+    /// world space so no model matrix is applied.
+    pub fn draw_quads(
+        &self,
+        texture: &Texture,
+        quads: &[SpriteQuad],
+        projection: &Mat4,
+    ) {
+        if quads.is_empty() {
+            return;
+        }
 
-            if let Some(color_key) = &sprite.color_key {
-                let color_key_loc = gl::GetUniformLocation(
-                    self.program,
-                    b"colorKey\0".as_ptr() as *const _,
-                );
-                let threshold_loc = gl::GetUniformLocation(
-                    self.program,
-                    b"threshold\0".as_ptr() as *const _,
-                );
+        let shader = self.begin(None, None, &Mat4::IDENTITY, projection);
+        shader.set_bool("useColorKey", false);
+        texture.bind();
 
-                gl::Uniform1i(use_color_key_loc, 1);
-                gl::Uniform3f(
-                    color_key_loc,
-                    color_key.color.x,
-                    color_key.color.y,
-                    color_key.color.z,
-                );
-                gl::Uniform1f(threshold_loc, color_key.threshold);
-            } else {
-                gl::Uniform1i(use_color_key_loc, 0);
-            }
+        let mut vertices = self.batch_vertices.borrow_mut();
+        vertices.clear();
+        for quad in quads {
+            Self::push_quad(&mut vertices, quad);
+        }
+        self.upload_and_draw(&vertices);
+    }
 
+    /// Like [`SpriteRenderer::draw_quads`], but drawn with a custom
+    /// material.
+    pub fn draw_quads_with_material(
+        &self,
+        material: &Material,
+        texture: &Texture,
+        quads: &[SpriteQuad],
+        projection: &Mat4,
+    ) {
+        if quads.is_empty() {
+            return;
+        }
+
+        let shader =
+            self.begin(Some(material), None, &Mat4::IDENTITY, projection);
+        shader.set_bool("useColorKey", false);
+        texture.bind();
+
+        let mut vertices = self.batch_vertices.borrow_mut();
+        vertices.clear();
+        for quad in quads {
+            Self::push_quad(&mut vertices, quad);
+        }
+        self.upload_and_draw(&vertices);
+    }
+
+    /// Put the material's shader, or the renderer's own, in use and set
+    /// the uniforms shared by every draw. Texture unit 0 is left active
+    /// for the caller to bind the image.
+    fn begin<'a>(
+        &'a self,
+        material: Option<&'a Material>,
+        palette: Option<(&PaletteTexture, u32)>,
+        model: &Mat4,
+        projection: &Mat4,
+    ) -> &'a ShaderProgram {
+        let shader = match (material, palette) {
+            (Some(material), _) => material.shader(),
+            (None, Some(_)) => &self.indexed_program,
+            (None, None) => &self.program,
+        };
+        shader.use_program();
+        shader.set_mat4("model", model);
+        shader.set_mat4("projection", projection);
+        shader.set_int("image", 0);
+
+        let mut first_unit = 1;
+        if let Some((palette, row)) = palette {
+            unsafe {
+                gl::ActiveTexture(gl::TEXTURE1);
+            }
+            palette.texture().bind();
+            shader.set_int("palette", 1);
+            shader.set_int("paletteRow", row as i32);
+            first_unit = 2;
+        }
+        if let Some(material) = material {
+            material.apply(first_unit);
+        }
+        unsafe {
             gl::ActiveTexture(gl::TEXTURE0);
-            sprite.texture.bind();
-
-            // Update vertex data
-            let vertices: [f32; 24] = [
-                // Pos
-                // Texture coordinate
-                0.0,
-                1.0,
-                sprite.tex_coords[0].x,
-                sprite.tex_coords[0].y,
-                1.0,
-                0.0,
-                sprite.tex_coords[2].x,
-                sprite.tex_coords[2].y,
-                0.0,
-                0.0,
-                sprite.tex_coords[3].x,
-                sprite.tex_coords[3].y,
-                0.0,
-                1.0,
-                sprite.tex_coords[0].x,
-                sprite.tex_coords[0].y,
-                1.0,
-                1.0,
-                sprite.tex_coords[1].x,
-                sprite.tex_coords[1].y,
-                1.0,
-                0.0,
-                sprite.tex_coords[2].x,
-                sprite.tex_coords[2].y,
-            ];
+        }
+        shader
+    }
 
+    fn push_quad(
+        vertices: &mut Vec<f32>,
+        quad: &SpriteQuad,
+    ) {
+        // Two triangles: top left, bottom right, bottom left and top left,
+        // top right, bottom right
+        for i in [0, 2, 3, 0, 1, 2] {
+            vertices.extend_from_slice(&[
+                quad.corners[i].x,
+                quad.corners[i].y,
+                quad.uvs[i].x,
+                quad.uvs[i].y,
+                quad.color.x,
+                quad.color.y,
+                quad.color.z,
+                quad.color.w,
+            ]);
+        }
+    }
+
+    fn upload_and_draw(
+        &self,
+        vertices: &[f32],
+    ) {
+        unsafe {
             gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+
+            if vertices.len() > self.vbo_capacity.get() {
+                let capacity = vertices.len().next_power_of_two();
+                gl::BufferData(
+                    gl::ARRAY_BUFFER,
+                    (capacity * std::mem::size_of::<f32>()) as GLsizeiptr,
+                    std::ptr::null(),
+                    gl::DYNAMIC_DRAW,
+                );
+                self.vbo_capacity.set(capacity);
+            }
+
             gl::BufferSubData(
                 gl::ARRAY_BUFFER,
                 0,
-                (vertices.len() * std::mem::size_of::<f32>()) as GLsizeiptr,
+                std::mem::size_of_val(vertices) as GLsizeiptr,
                 vertices.as_ptr() as *const _,
             );
 
             gl::BindVertexArray(self.vao);
-            gl::DrawArrays(gl::TRIANGLES, 0, 6);
+            gl::DrawArrays(
+                gl::TRIANGLES,
+                0,
+                (vertices.len() / FLOATS_PER_VERTEX) as GLsizei,
+            );
+        }
+    }
+}
+
+impl Drop for SpriteRenderer {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteBuffers(1, &self.vbo);
+            gl::DeleteVertexArrays(1, &self.vao);
         }
     }
 }
@@ -1,76 +1,11 @@
-use gl::types::*;
-use std::ffi::CString;
-
-pub fn compile_shader(
-    shader_type: GLenum,
-    source: &str,
-) -> GLuint {
-    unsafe {
-        let shader = gl::CreateShader(shader_type);
-        let c_str = CString::new(source.as_bytes()).unwrap();
-        gl::ShaderSource(shader, 1, &c_str.as_ptr(), std::ptr::null());
-        gl::CompileShader(shader);
-
-        let mut success = gl::FALSE as GLint;
-        gl::GetShaderiv(shader, gl::COMPILE_STATUS, &mut success);
-        if success != gl::TRUE as GLint {
-            let mut info_log = Vec::with_capacity(512);
-            let mut len = 0;
-            gl::GetShaderInfoLog(
-                shader,
-                512,
-                &mut len,
-                info_log.as_mut_ptr() as *mut GLchar,
-            );
-            info_log.set_len(len as usize);
-            panic!(
-                "Shader compilation failed: {}",
-                String::from_utf8_lossy(&info_log)
-            );
-        }
-        shader
-    }
-}
-
-pub fn link_program(
-    vertex_shader: GLuint,
-    fragment_shader: GLuint,
-) -> GLuint {
-    unsafe {
-        let program = gl::CreateProgram();
-        gl::AttachShader(program, vertex_shader);
-        gl::AttachShader(program, fragment_shader);
-        gl::LinkProgram(program);
-
-        let mut success = gl::FALSE as GLint;
-        gl::GetProgramiv(program, gl::LINK_STATUS, &mut success);
-        if success != gl::TRUE as GLint {
-            let mut info_log = Vec::with_capacity(512);
-            let mut len = 0;
-            gl::GetProgramInfoLog(
-                program,
-                512,
-                &mut len,
-                info_log.as_mut_ptr() as *mut GLchar,
-            );
-            info_log.set_len(len as usize);
-            panic!(
-                "Program linking failed: {}",
-                String::from_utf8_lossy(&info_log)
-            );
-        }
-        gl::DeleteShader(vertex_shader);
-        gl::DeleteShader(fragment_shader);
-        program
-    }
-}
-
 pub const SPRITE_VERTEX_SHADER: &str = r#"
     #version 330 core
     layout (location = 0) in vec2 aPos;
     layout (location = 1) in vec2 aTexCoords;
+    layout (location = 2) in vec4 aColor;
 
     out vec2 TexCoords;
+    out vec4 Color;
 
     uniform mat4 model;
     uniform mat4 projection;
@@ -78,6 +13,7 @@ pub const SPRITE_VERTEX_SHADER: &str = r#"
     void main()
     {
         TexCoords = aTexCoords;
+        Color = aColor;
         gl_Position = projection * model * vec4(aPos, 0.0, 1.0);
     }
 "#;
@@ -85,6 +21,7 @@ pub const SPRITE_VERTEX_SHADER: &str = r#"
 pub const SPRITE_FRAGMENT_SHADER: &str = r#"
     #version 330 core
     in vec2 TexCoords;
+    in vec4 Color;
     out vec4 FragColor;
 
     uniform sampler2D image;
@@ -105,6 +42,30 @@ pub const SPRITE_FRAGMENT_SHADER: &str = r#"
             }
         }
 
-        FragColor = texColor;
+        FragColor = texColor * Color;
+    }
+"#;
+
+/// Fragment shader for palette-indexed sprites. The red channel of `image`
+/// holds a palette index, looked up in row `paletteRow` of `palette`.
+pub const INDEXED_SPRITE_FRAGMENT_SHADER: &str = r#"
+    #version 330 core
+    in vec2 TexCoords;
+    in vec4 Color;
+    out vec4 FragColor;
+
+    uniform sampler2D image;
+    uniform sampler2D palette;
+    uniform int paletteRow;
+
+    void main()
+    {
+        int index = int(texture(image, TexCoords).r * 255.0 + 0.5);
+        vec4 color = texelFetch(palette, ivec2(index, paletteRow), 0);
+        if (color.a <= 0.0) {
+            discard;
+        }
+
+        FragColor = color * Color;
     }
 "#;
@@ -7,6 +7,10 @@ pub struct ColorKey {
 }
 
 impl ColorKey {
+    /// Threshold used when none is given, tight enough to key only the
+    /// exact color in 8-bit images.
+    pub const DEFAULT_THRESHOLD: f32 = 0.01;
+
     /// Generate ColorKey from hex code
     ///
     /// # Arguments
@@ -18,6 +22,7 @@ impl ColorKey {
     ///
     /// # Examples
     /// ```
+    /// # use teengine::sprite::utils::color_key_util::ColorKey;
     /// let color_key = ColorKey::from_hex("#FFFFFF", 0.5).unwrap();
     /// ```
     #[allow(unused)]
@@ -62,6 +67,7 @@ impl ColorKey {
     ///
     /// # Examples
     /// ```
+    /// # use teengine::sprite::utils::color_key_util::ColorKey;
     /// let color_key = ColorKey::from_rgb(255, 255, 255, 0.5).unwrap();
     /// ```
     #[allow(unused)]
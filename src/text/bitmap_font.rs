@@ -0,0 +1,190 @@
+use glam::Vec2;
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::font::{Font, Glyph};
+use crate::texture::options::TextureOptions;
+use crate::texture::texture::Texture;
+
+#[derive(Debug, Clone, Copy)]
+struct BitmapChar {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    x_offset: i32,
+    y_offset: i32,
+    x_advance: i32,
+    page: usize,
+}
+
+/// Font loaded from an AngelCode BMFont descriptor in the text format.
+///
+/// Page images are loaded relative to the `.fnt` file.
+#[derive(Debug)]
+pub struct BitmapFont {
+    line_height: f32,
+    pages: Vec<Texture>,
+    chars: HashMap<u32, BitmapChar>,
+    kernings: HashMap<(u32, u32), i32>,
+}
+
+impl BitmapFont {
+    pub fn new(path: &Path) -> Result<Self, String> {
+        Self::with_options(path, TextureOptions::default())
+    }
+
+    pub fn with_options(
+        path: &Path,
+        options: TextureOptions,
+    ) -> Result<Self, String> {
+        let source = std::fs::read_to_string(path).map_err(|e| {
+            format!("Failed to read font {}: {}", path.display(), e)
+        })?;
+        let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+        let mut line_height = 0.0;
+        let mut page_files: Vec<(usize, String)> = Vec::new();
+        let mut chars = HashMap::new();
+        let mut kernings = HashMap::new();
+
+        for line in source.lines() {
+            let (tag, attributes) = Self::parse_line(line);
+            let int = |key: &str| -> Result<i32, String> {
+                attributes
+                    .get(key)
+                    .ok_or_else(|| format!("Missing {} in '{}'", key, line))?
+                    .parse::<i32>()
+                    .map_err(|e| {
+                        format!("Invalid {} in '{}': {}", key, line, e)
+                    })
+            };
+
+            match tag {
+                "common" => line_height = int("lineHeight")? as f32,
+                "page" => {
+                    let file = attributes
+                        .get("file")
+                        .ok_or_else(|| format!("Missing file in '{}'", line))?;
+                    page_files.push((int("id")? as usize, file.clone()));
+                }
+                "char" => {
+                    chars.insert(
+                        int("id")? as u32,
+                        BitmapChar {
+                            x: int("x")? as u32,
+                            y: int("y")? as u32,
+                            width: int("width")? as u32,
+                            height: int("height")? as u32,
+                            x_offset: int("xoffset")?,
+                            y_offset: int("yoffset")?,
+                            x_advance: int("xadvance")?,
+                            page: int("page")? as usize,
+                        },
+                    );
+                }
+                "kerning" => {
+                    kernings.insert(
+                        (int("first")? as u32, int("second")? as u32),
+                        int("amount")?,
+                    );
+                }
+                _ => (),
+            }
+        }
+
+        page_files.sort_by_key(|(id, _)| *id);
+        let pages = page_files
+            .iter()
+            .map(|(_, file)| {
+                Texture::with_options(&base_dir.join(file), options)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if pages.is_empty() {
+            return Err(format!("Font {} has no pages", path.display()));
+        }
+
+        Ok(Self {
+            line_height,
+            pages,
+            chars,
+            kernings,
+        })
+    }
+
+    /// Split a descriptor line into its tag and `key=value` attributes.
+    /// Values may be quoted and contain spaces.
+    fn parse_line(line: &str) -> (&str, HashMap<&str, String>) {
+        let line = line.trim();
+        let (tag, mut rest) = line.split_once(' ').unwrap_or((line, ""));
+        let mut attributes = HashMap::new();
+
+        loop {
+            rest = rest.trim_start();
+            let Some((key, after_key)) = rest.split_once('=') else {
+                break;
+            };
+
+            let (value, remaining) =
+                if let Some(quoted) = after_key.strip_prefix('"') {
+                    let end = quoted.find('"').unwrap_or(quoted.len());
+                    (&quoted[..end], quoted.get(end + 1..).unwrap_or(""))
+                } else {
+                    let end = after_key.find(' ').unwrap_or(after_key.len());
+                    (&after_key[..end], &after_key[end..])
+                };
+
+            attributes.insert(key.trim(), value.to_string());
+            rest = remaining;
+        }
+
+        (tag, attributes)
+    }
+}
+
+impl Font for BitmapFont {
+    fn line_height(&self) -> f32 {
+        self.line_height
+    }
+
+    fn glyph(
+        &mut self,
+        ch: char,
+    ) -> Option<Glyph> {
+        let data = self.chars.get(&(ch as u32))?;
+        let texture = self.pages.get(data.page)?;
+        let texture_size =
+            Vec2::new(texture.width() as f32, texture.height() as f32);
+
+        let position = Vec2::new(data.x as f32, data.y as f32);
+        let size = Vec2::new(data.width as f32, data.height as f32);
+
+        Some(Glyph {
+            page: data.page,
+            uv_min: position / texture_size,
+            uv_max: (position + size) / texture_size,
+            size,
+            offset: Vec2::new(data.x_offset as f32, data.y_offset as f32),
+            advance: data.x_advance as f32,
+        })
+    }
+
+    fn kerning(
+        &self,
+        left: char,
+        right: char,
+    ) -> f32 {
+        self.kernings
+            .get(&(left as u32, right as u32))
+            .copied()
+            .unwrap_or(0) as f32
+    }
+
+    fn page_texture(
+        &self,
+        page: usize,
+    ) -> Option<&Texture> {
+        self.pages.get(page)
+    }
+}
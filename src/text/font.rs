@@ -0,0 +1,51 @@
+use glam::Vec2;
+
+use crate::texture::texture::Texture;
+
+/// Location and metrics of one glyph in a font's page textures.
+#[derive(Debug, Clone, Copy)]
+pub struct Glyph {
+    /// Index of the page texture holding the glyph.
+    pub page: usize,
+    pub uv_min: Vec2,
+    pub uv_max: Vec2,
+    /// Size of the glyph quad in pixels.
+    pub size: Vec2,
+    /// Offset from the pen position at the top of the line to the top left
+    /// of the glyph quad.
+    pub offset: Vec2,
+    /// Horizontal distance to the next pen position.
+    pub advance: f32,
+}
+
+/// Source of glyphs for text layout and rendering.
+pub trait Font {
+    /// Distance between two baselines, in pixels.
+    fn line_height(&self) -> f32;
+
+    /// Glyph for `ch`, or `None` if the font does not contain it.
+    fn glyph(
+        &mut self,
+        ch: char,
+    ) -> Option<Glyph>;
+
+    /// Extra horizontal advance between `left` and `right`.
+    fn kerning(
+        &self,
+        left: char,
+        right: char,
+    ) -> f32;
+
+    fn page_texture(
+        &self,
+        page: usize,
+    ) -> Option<&Texture>;
+
+    /// Called before `text` is laid out so fonts that rasterize on demand
+    /// can make sure every glyph is available at once.
+    fn prepare(
+        &mut self,
+        _text: &str,
+    ) {
+    }
+}
@@ -0,0 +1,269 @@
+use glam::Vec2;
+
+use super::font::{Font, Glyph};
+use crate::tile::properties::Color;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TextStyle {
+    pub color: Color,
+    /// Multiplier applied to the font's pixel size.
+    pub scale: f32,
+    pub align: TextAlign,
+    /// Wrap lines that would grow wider than this, in pixels.
+    pub max_width: Option<f32>,
+    /// Multiplier applied to the font's line height.
+    pub line_spacing: f32,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            color: Color::WHITE,
+            scale: 1.0,
+            align: TextAlign::Left,
+            max_width: None,
+            line_spacing: 1.0,
+        }
+    }
+}
+
+impl TextStyle {
+    pub fn with_color(
+        mut self,
+        color: Color,
+    ) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_scale(
+        mut self,
+        scale: f32,
+    ) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn with_align(
+        mut self,
+        align: TextAlign,
+    ) -> Self {
+        self.align = align;
+        self
+    }
+
+    pub fn with_max_width(
+        mut self,
+        max_width: f32,
+    ) -> Self {
+        self.max_width = Some(max_width);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PositionedGlyph {
+    pub ch: char,
+    pub glyph: Glyph,
+    /// Top left of the scaled glyph quad, relative to the text origin.
+    pub position: Vec2,
+    /// Size of the scaled glyph quad.
+    pub size: Vec2,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TextLayout {
+    pub glyphs: Vec<PositionedGlyph>,
+    /// Size of the laid out text block.
+    pub size: Vec2,
+}
+
+/// Characters that allow a line break before and after them even without
+/// whitespace (Han ideographs, kana and fullwidth forms). Korean separates
+/// words with spaces, so Hangul wraps at whitespace like Latin text.
+fn is_cjk_break_char(ch: char) -> bool {
+    matches!(ch as u32,
+        0x3040..=0x30FF // Hiragana, Katakana
+        | 0x3400..=0x4DBF // CJK Extension A
+        | 0x4E00..=0x9FFF // CJK Unified Ideographs
+        | 0xF900..=0xFAFF // CJK Compatibility Ideographs
+        | 0xFF00..=0xFFEF // Halfwidth and Fullwidth Forms
+    )
+}
+
+fn advance(
+    font: &mut dyn Font,
+    previous: Option<char>,
+    ch: char,
+) -> f32 {
+    let kerning = previous.map_or(0.0, |previous| font.kerning(previous, ch));
+    font.glyph(ch).map_or(0.0, |glyph| glyph.advance) + kerning
+}
+
+/// Split one paragraph into lines no wider than `max_width` (unscaled).
+fn wrap_paragraph(
+    font: &mut dyn Font,
+    chars: &[char],
+    max_width: Option<f32>,
+) -> Vec<(usize, usize)> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+
+    loop {
+        let mut end = start;
+        let mut x = 0.0;
+        let mut last_break = None;
+        let mut previous = None;
+
+        while end < chars.len() {
+            let ch = chars[end];
+            let width = advance(font, previous, ch);
+
+            let overflows = max_width.is_some_and(|max| x + width > max);
+            if overflows && !ch.is_whitespace() && end > start {
+                break;
+            }
+
+            if is_cjk_break_char(ch) && end > start {
+                last_break = Some(end);
+            }
+            x += width;
+            end += 1;
+            if ch.is_whitespace() || is_cjk_break_char(ch) {
+                last_break = Some(end);
+            }
+            previous = Some(ch);
+        }
+
+        // Prefer breaking at the last opportunity over splitting a word
+        if end < chars.len() {
+            if let Some(break_at) = last_break.filter(|&b| b > start) {
+                end = break_at;
+            }
+        }
+
+        let mut line_end = end;
+        while line_end > start && chars[line_end - 1].is_whitespace() {
+            line_end -= 1;
+        }
+        lines.push((start, line_end));
+
+        if end >= chars.len() {
+            break;
+        }
+
+        start = end;
+        while start < chars.len() && chars[start].is_whitespace() {
+            start += 1;
+        }
+        if start >= chars.len() {
+            break;
+        }
+    }
+
+    lines
+}
+
+/// Lay out `text` with its top left corner at the origin.
+///
+/// Lines are broken at `\n` and, when `style.max_width` is set, wrapped at
+/// whitespace, between CJK ideographs, or inside words that are wider than
+/// a whole line. Alignment is relative to `max_width` if set, otherwise to
+/// the widest line.
+pub fn layout_text(
+    font: &mut dyn Font,
+    text: &str,
+    style: &TextStyle,
+) -> TextLayout {
+    font.prepare(text);
+
+    let scale = style.scale;
+    let max_width = style.max_width.map(|width| width / scale);
+    let line_advance = font.line_height() * style.line_spacing * scale;
+
+    let mut lines: Vec<Vec<char>> = Vec::new();
+    for paragraph in text.split('\n') {
+        let chars: Vec<char> =
+            paragraph.trim_end_matches('\r').chars().collect();
+        for (start, end) in wrap_paragraph(font, &chars, max_width) {
+            lines.push(chars[start..end].to_vec());
+        }
+    }
+
+    let line_widths: Vec<f32> = lines
+        .iter()
+        .map(|line| {
+            let mut previous = None;
+            line.iter()
+                .map(|&ch| {
+                    let width = advance(font, previous, ch);
+                    previous = Some(ch);
+                    width
+                })
+                .sum::<f32>()
+                * scale
+        })
+        .collect();
+    let block_width = style
+        .max_width
+        .unwrap_or_else(|| line_widths.iter().cloned().fold(0.0, f32::max));
+
+    let mut layout = TextLayout {
+        glyphs: Vec::new(),
+        size: Vec2::new(
+            line_widths.iter().cloned().fold(0.0, f32::max),
+            lines.len() as f32 * line_advance,
+        ),
+    };
+
+    for (index, (line, width)) in lines.iter().zip(&line_widths).enumerate() {
+        let mut pen_x = match style.align {
+            TextAlign::Left => 0.0,
+            TextAlign::Center => ((block_width - width) * 0.5).floor(),
+            TextAlign::Right => block_width - width,
+        };
+        let pen_y = index as f32 * line_advance;
+        let mut previous = None;
+
+        for &ch in line {
+            if let Some(previous) = previous {
+                pen_x += font.kerning(previous, ch) * scale;
+            }
+            previous = Some(ch);
+
+            let Some(glyph) = font.glyph(ch) else {
+                continue;
+            };
+
+            if glyph.size.x > 0.0 && glyph.size.y > 0.0 {
+                layout.glyphs.push(PositionedGlyph {
+                    ch,
+                    glyph,
+                    position: (Vec2::new(pen_x, pen_y) + glyph.offset * scale)
+                        .round(),
+                    size: glyph.size * scale,
+                });
+            }
+            pen_x += glyph.advance * scale;
+        }
+    }
+
+    layout
+}
+
+/// Size of `text` laid out with `style`, without drawing it.
+pub fn measure_text(
+    font: &mut dyn Font,
+    text: &str,
+    style: &TextStyle,
+) -> Vec2 {
+    layout_text(font, text, style).size
+}
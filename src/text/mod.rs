@@ -0,0 +1,11 @@
+pub mod bitmap_font;
+pub mod font;
+pub mod layout;
+pub mod text_renderer;
+pub mod truetype_font;
+
+pub use bitmap_font::*;
+pub use font::*;
+pub use layout::*;
+pub use text_renderer::*;
+pub use truetype_font::*;
@@ -0,0 +1,61 @@
+use glam::{Mat4, Vec2};
+use std::cell::RefCell;
+
+use super::font::Font;
+use super::layout::{layout_text, TextStyle};
+use crate::sprite::sprite_renderer::{SpriteQuad, SpriteRenderer};
+
+/// Draws text as batches of glyph quads through the [`SpriteRenderer`],
+/// one draw call per font page.
+#[derive(Default)]
+pub struct TextRenderer {
+    quads: RefCell<Vec<(usize, SpriteQuad)>>,
+    page_quads: RefCell<Vec<SpriteQuad>>,
+}
+
+impl TextRenderer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Draw `text` with its top left corner at `position`.
+    pub fn draw_text(
+        &self,
+        sprite_renderer: &SpriteRenderer,
+        font: &mut dyn Font,
+        text: &str,
+        position: Vec2,
+        style: &TextStyle,
+        projection: &Mat4,
+    ) {
+        let layout = layout_text(font, text, style);
+        let color = style.color.to_vec4();
+
+        let mut quads = self.quads.borrow_mut();
+        quads.clear();
+        quads.extend(layout.glyphs.iter().map(|glyph| {
+            let min = position + glyph.position;
+            (
+                glyph.glyph.page,
+                SpriteQuad::new(
+                    min,
+                    min + glyph.size,
+                    glyph.glyph.uv_min,
+                    glyph.glyph.uv_max,
+                    color,
+                ),
+            )
+        }));
+        quads.sort_by_key(|(page, _)| *page);
+
+        let mut page_quads = self.page_quads.borrow_mut();
+        for chunk in quads.chunk_by(|a, b| a.0 == b.0) {
+            let Some(texture) = font.page_texture(chunk[0].0) else {
+                continue;
+            };
+            page_quads.clear();
+            page_quads.extend(chunk.iter().map(|(_, quad)| *quad));
+            sprite_renderer.draw_quads(texture, &page_quads, projection);
+        }
+    }
+}
@@ -0,0 +1,226 @@
+use glam::Vec2;
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::font::{Font, Glyph};
+use crate::texture::atlas::MaxRectsPacker;
+use crate::texture::options::{FilterMode, TextureOptions};
+use crate::texture::texture::Texture;
+
+// Empty pixels kept between glyphs so linear filtering does not bleed
+const GLYPH_PADDING: u32 = 1;
+
+/// TrueType/OpenType font rasterized at one pixel size into a glyph atlas.
+///
+/// Glyphs are rasterized the first time they are drawn, so large character
+/// sets such as Korean Hangul only cost atlas space for the characters that
+/// are actually used. When the atlas fills up it is cleared and refilled
+/// with the glyphs of the text being drawn.
+pub struct TrueTypeFont {
+    font: fontdue::Font,
+    size: f32,
+    ascent: f32,
+    line_height: f32,
+    texture: Texture,
+    packer: MaxRectsPacker,
+    glyphs: HashMap<char, Option<Glyph>>,
+}
+
+impl TrueTypeFont {
+    pub fn new(
+        path: &Path,
+        size: f32,
+    ) -> Result<Self, String> {
+        let bytes = std::fs::read(path).map_err(|e| {
+            format!("Failed to read font {}: {}", path.display(), e)
+        })?;
+        Self::from_bytes(&bytes, size, 1024)
+    }
+
+    /// Load a font from memory with a square glyph atlas of `atlas_size`
+    /// pixels.
+    pub fn from_bytes(
+        bytes: &[u8],
+        size: f32,
+        atlas_size: u32,
+    ) -> Result<Self, String> {
+        let font = fontdue::Font::from_bytes(
+            bytes,
+            fontdue::FontSettings {
+                scale: size,
+                ..fontdue::FontSettings::default()
+            },
+        )
+        .map_err(|e| format!("Failed to parse font: {}", e))?;
+
+        let (ascent, line_height) = font
+            .horizontal_line_metrics(size)
+            .map(|metrics| (metrics.ascent, metrics.new_line_size))
+            .unwrap_or((size, size));
+
+        let texture = Texture::from_rgba_bytes(
+            atlas_size,
+            atlas_size,
+            &vec![0; (atlas_size * atlas_size * 4) as usize],
+            TextureOptions::default()
+                .with_filter(FilterMode::Linear, FilterMode::Linear),
+        )?;
+
+        Ok(Self {
+            font,
+            size,
+            ascent: ascent.ceil(),
+            line_height: line_height.ceil(),
+            texture,
+            packer: MaxRectsPacker::new(atlas_size, atlas_size),
+            glyphs: HashMap::new(),
+        })
+    }
+
+    pub fn size(&self) -> f32 {
+        self.size
+    }
+
+    /// Rasterize every character of `text` ahead of time.
+    pub fn preload(
+        &mut self,
+        text: &str,
+    ) {
+        for ch in text.chars() {
+            self.glyph(ch);
+        }
+    }
+
+    fn clear_atlas(&mut self) {
+        self.packer =
+            MaxRectsPacker::new(self.packer.width(), self.packer.height());
+        self.glyphs.clear();
+    }
+
+    /// Rasterize `ch` into the atlas. Returns `Err` when the atlas is full.
+    fn rasterize(
+        &mut self,
+        ch: char,
+    ) -> Result<Option<Glyph>, ()> {
+        if !ch.is_whitespace() && self.font.lookup_glyph_index(ch) == 0 {
+            return Ok(None);
+        }
+
+        let (metrics, coverage) = self.font.rasterize(ch, self.size);
+        let width = metrics.width as u32;
+        let height = metrics.height as u32;
+
+        let mut glyph = Glyph {
+            page: 0,
+            uv_min: Vec2::ZERO,
+            uv_max: Vec2::ZERO,
+            size: Vec2::new(width as f32, height as f32),
+            offset: Vec2::new(
+                metrics.xmin as f32,
+                self.ascent - (metrics.ymin + metrics.height as i32) as f32,
+            ),
+            advance: metrics.advance_width,
+        };
+
+        if width == 0 || height == 0 {
+            return Ok(Some(glyph));
+        }
+
+        let rect = self
+            .packer
+            .insert(width + GLYPH_PADDING, height + GLYPH_PADDING)
+            .ok_or(())?;
+
+        let pixels: Vec<u8> = coverage
+            .iter()
+            .flat_map(|&alpha| [255, 255, 255, alpha])
+            .collect();
+        if let Err(e) = self
+            .texture
+            .update_region(rect.x, rect.y, width, height, &pixels)
+        {
+            eprintln!("Failed to upload glyph {:?}: {}", ch, e);
+            return Ok(None);
+        }
+
+        let atlas_size = Vec2::new(
+            self.texture.width() as f32,
+            self.texture.height() as f32,
+        );
+        glyph.uv_min = Vec2::new(rect.x as f32, rect.y as f32) / atlas_size;
+        glyph.uv_max =
+            Vec2::new((rect.x + width) as f32, (rect.y + height) as f32)
+                / atlas_size;
+        Ok(Some(glyph))
+    }
+}
+
+impl Font for TrueTypeFont {
+    fn line_height(&self) -> f32 {
+        self.line_height
+    }
+
+    fn glyph(
+        &mut self,
+        ch: char,
+    ) -> Option<Glyph> {
+        if let Some(glyph) = self.glyphs.get(&ch) {
+            return *glyph;
+        }
+
+        let glyph = match self.rasterize(ch) {
+            Ok(glyph) => glyph,
+            Err(()) => {
+                self.clear_atlas();
+                self.rasterize(ch).ok().flatten()
+            }
+        };
+        self.glyphs.insert(ch, glyph);
+        glyph
+    }
+
+    fn kerning(
+        &self,
+        left: char,
+        right: char,
+    ) -> f32 {
+        self.font
+            .horizontal_kern(left, right, self.size)
+            .unwrap_or(0.0)
+    }
+
+    fn page_texture(
+        &self,
+        page: usize,
+    ) -> Option<&Texture> {
+        (page == 0).then_some(&self.texture)
+    }
+
+    fn prepare(
+        &mut self,
+        text: &str,
+    ) {
+        let missing = text.chars().any(|ch| !self.glyphs.contains_key(&ch));
+        if !missing {
+            return;
+        }
+
+        // If the text does not fit next to the cached glyphs, start over
+        // with an empty atlas so every glyph of this text is valid at once.
+        for ch in text.chars() {
+            if self.glyphs.contains_key(&ch) {
+                continue;
+            }
+            match self.rasterize(ch) {
+                Ok(glyph) => {
+                    self.glyphs.insert(ch, glyph);
+                }
+                Err(()) => {
+                    self.clear_atlas();
+                    self.preload(text);
+                    return;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,517 @@
+use glam::Vec2;
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use super::options::TextureOptions;
+use super::texture::Texture;
+
+/// Pixel rectangle inside an atlas image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AtlasRegion {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl AtlasRegion {
+    /// Normalized `(min, max)` texture coordinates of the region.
+    pub fn uv_rect(
+        &self,
+        atlas_width: u32,
+        atlas_height: u32,
+    ) -> (Vec2, Vec2) {
+        (
+            Vec2::new(
+                self.x as f32 / atlas_width as f32,
+                self.y as f32 / atlas_height as f32,
+            ),
+            Vec2::new(
+                (self.x + self.width) as f32 / atlas_width as f32,
+                (self.y + self.height) as f32 / atlas_height as f32,
+            ),
+        )
+    }
+
+    fn intersects(
+        &self,
+        other: &AtlasRegion,
+    ) -> bool {
+        self.x < other.x + other.width
+            && other.x < self.x + self.width
+            && self.y < other.y + other.height
+            && other.y < self.y + self.height
+    }
+
+    fn contains(
+        &self,
+        other: &AtlasRegion,
+    ) -> bool {
+        other.x >= self.x
+            && other.y >= self.y
+            && other.x + other.width <= self.x + self.width
+            && other.y + other.height <= self.y + self.height
+    }
+}
+
+/// Rectangle bin packer using the MaxRects algorithm with the
+/// best-short-side-fit heuristic.
+#[derive(Debug, Clone)]
+pub struct MaxRectsPacker {
+    width: u32,
+    height: u32,
+    free_rects: Vec<AtlasRegion>,
+}
+
+impl MaxRectsPacker {
+    pub fn new(
+        width: u32,
+        height: u32,
+    ) -> Self {
+        Self {
+            width,
+            height,
+            free_rects: vec![AtlasRegion {
+                x: 0,
+                y: 0,
+                width,
+                height,
+            }],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Place a `width` x `height` rectangle, returning its position or
+    /// `None` if it does not fit.
+    pub fn insert(
+        &mut self,
+        width: u32,
+        height: u32,
+    ) -> Option<AtlasRegion> {
+        if width == 0 || height == 0 {
+            return None;
+        }
+
+        let mut best: Option<(AtlasRegion, u32, u32)> = None;
+        for free in &self.free_rects {
+            if free.width < width || free.height < height {
+                continue;
+            }
+
+            let leftover_x = free.width - width;
+            let leftover_y = free.height - height;
+            let short_side = leftover_x.min(leftover_y);
+            let long_side = leftover_x.max(leftover_y);
+
+            let is_better = match best {
+                Some((_, best_short, best_long)) => {
+                    (short_side, long_side) < (best_short, best_long)
+                }
+                None => true,
+            };
+            if is_better {
+                best = Some((
+                    AtlasRegion {
+                        x: free.x,
+                        y: free.y,
+                        width,
+                        height,
+                    },
+                    short_side,
+                    long_side,
+                ));
+            }
+        }
+
+        let (placed, _, _) = best?;
+        self.split_free_rects(&placed);
+        self.prune_free_rects();
+        Some(placed)
+    }
+
+    fn split_free_rects(
+        &mut self,
+        placed: &AtlasRegion,
+    ) {
+        let mut new_rects = Vec::new();
+
+        self.free_rects.retain(|free| {
+            if !free.intersects(placed) {
+                return true;
+            }
+
+            // left
+            if placed.x > free.x {
+                new_rects.push(AtlasRegion {
+                    width: placed.x - free.x,
+                    ..*free
+                });
+            }
+            // right
+            if placed.x + placed.width < free.x + free.width {
+                new_rects.push(AtlasRegion {
+                    x: placed.x + placed.width,
+                    width: free.x + free.width - (placed.x + placed.width),
+                    ..*free
+                });
+            }
+            // top
+            if placed.y > free.y {
+                new_rects.push(AtlasRegion {
+                    height: placed.y - free.y,
+                    ..*free
+                });
+            }
+            // bottom
+            if placed.y + placed.height < free.y + free.height {
+                new_rects.push(AtlasRegion {
+                    y: placed.y + placed.height,
+                    height: free.y + free.height
+                        - (placed.y + placed.height),
+                    ..*free
+                });
+            }
+
+            false
+        });
+
+        self.free_rects.extend(new_rects);
+    }
+
+    fn prune_free_rects(&mut self) {
+        let mut i = 0;
+        while i < self.free_rects.len() {
+            let mut removed = false;
+            let mut j = i + 1;
+            while j < self.free_rects.len() {
+                if self.free_rects[j].contains(&self.free_rects[i]) {
+                    self.free_rects.swap_remove(i);
+                    removed = true;
+                    break;
+                }
+                if self.free_rects[i].contains(&self.free_rects[j]) {
+                    self.free_rects.swap_remove(j);
+                } else {
+                    j += 1;
+                }
+            }
+            if !removed {
+                i += 1;
+            }
+        }
+    }
+}
+
+/// Atlas description written next to the atlas image.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AtlasLayout {
+    pub image_path: String,
+    pub width: u32,
+    pub height: u32,
+    pub regions: BTreeMap<String, AtlasRegion>,
+}
+
+/// Packs many images into one atlas image.
+///
+/// Each image is surrounded by `extrude` pixels copied from its own edges,
+/// which stops filtering from bleeding in neighbouring images, and
+/// separated from the next by `padding` transparent pixels.
+pub struct AtlasBuilder {
+    padding: u32,
+    extrude: u32,
+    max_size: u32,
+    images: Vec<(String, RgbaImage)>,
+}
+
+impl Default for AtlasBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AtlasBuilder {
+    pub fn new() -> Self {
+        Self {
+            padding: 2,
+            extrude: 1,
+            max_size: 4096,
+            images: Vec::new(),
+        }
+    }
+
+    pub fn with_padding(
+        mut self,
+        padding: u32,
+    ) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    pub fn with_extrude(
+        mut self,
+        extrude: u32,
+    ) -> Self {
+        self.extrude = extrude;
+        self
+    }
+
+    pub fn with_max_size(
+        mut self,
+        max_size: u32,
+    ) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Add an image under `name`. Empty images and names that were
+    /// already added are rejected.
+    pub fn add_image(
+        &mut self,
+        name: &str,
+        image: RgbaImage,
+    ) -> Result<(), String> {
+        if image.width() == 0 || image.height() == 0 {
+            return Err(format!("Image {} is empty", name));
+        }
+        if self.images.iter().any(|(other, _)| other == name) {
+            return Err(format!("Duplicate atlas image name: {}", name));
+        }
+        self.images.push((name.to_string(), image));
+        Ok(())
+    }
+
+    /// Add an image file, named after its file stem.
+    pub fn add_file(
+        &mut self,
+        path: &Path,
+    ) -> Result<(), String> {
+        let name = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .ok_or_else(|| format!("Invalid image path: {}", path.display()))?;
+        let image = image::open(path)
+            .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?
+            .into_rgba8();
+        self.add_image(name, image)
+            .map_err(|e| format!("Failed to add {}: {}", path.display(), e))
+    }
+
+    /// Pack the added images and compose the atlas image.
+    ///
+    /// The atlas starts at the smallest power-of-two size that could hold
+    /// every image and grows until everything fits or `max_size` is reached.
+    pub fn build_image(
+        &self,
+        image_path: &str,
+    ) -> Result<(RgbaImage, AtlasLayout), String> {
+        let border = self.extrude * 2 + self.padding;
+
+        // Pack big images first, they are the hardest to place
+        let mut order: Vec<usize> = (0..self.images.len()).collect();
+        order.sort_by_key(|&i| {
+            let image = &self.images[i].1;
+            std::cmp::Reverse((
+                image.width().max(image.height()),
+                image.width() * image.height(),
+            ))
+        });
+
+        let total_area: u64 = self
+            .images
+            .iter()
+            .map(|(_, image)| {
+                (image.width() + border) as u64
+                    * (image.height() + border) as u64
+            })
+            .sum();
+        let mut width =
+            ((total_area as f64).sqrt().ceil() as u32).next_power_of_two();
+        let mut height = width;
+
+        let placements = loop {
+            if width > self.max_size || height > self.max_size {
+                return Err(format!(
+                    "Images do not fit in a {0}x{0} atlas",
+                    self.max_size
+                ));
+            }
+
+            let mut packer = MaxRectsPacker::new(width, height);
+            let placements: Option<Vec<(usize, AtlasRegion)>> = order
+                .iter()
+                .map(|&i| {
+                    let image = &self.images[i].1;
+                    packer
+                        .insert(image.width() + border, image.height() + border)
+                        .map(|rect| (i, rect))
+                })
+                .collect();
+
+            match placements {
+                Some(placements) => break placements,
+                None if width <= height => width *= 2,
+                None => height *= 2,
+            }
+        };
+
+        let mut atlas = RgbaImage::new(width, height);
+        let mut regions = BTreeMap::new();
+
+        for (i, rect) in placements {
+            let (name, image) = &self.images[i];
+            let x = rect.x + self.extrude;
+            let y = rect.y + self.extrude;
+            self.blit_extruded(&mut atlas, image, x, y);
+
+            regions.insert(
+                name.clone(),
+                AtlasRegion {
+                    x,
+                    y,
+                    width: image.width(),
+                    height: image.height(),
+                },
+            );
+        }
+
+        Ok((
+            atlas,
+            AtlasLayout {
+                image_path: image_path.to_string(),
+                width,
+                height,
+                regions,
+            },
+        ))
+    }
+
+    /// Pack the images and upload the atlas to the GPU.
+    pub fn build(
+        &self,
+        options: TextureOptions,
+    ) -> Result<TextureAtlas, String> {
+        let (image, layout) = self.build_image("")?;
+        let texture = Texture::from_rgba_bytes(
+            image.width(),
+            image.height(),
+            image.as_raw(),
+            options,
+        )?;
+        Ok(TextureAtlas { texture, layout })
+    }
+
+    /// Pack the images and write the atlas PNG and its JSON layout.
+    ///
+    /// The layout is written next to the image with a `.json` extension.
+    pub fn write(
+        &self,
+        image_path: &Path,
+    ) -> Result<AtlasLayout, String> {
+        let file_name = image_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| {
+                format!("Invalid atlas path: {}", image_path.display())
+            })?;
+        let (image, layout) = self.build_image(file_name)?;
+
+        image.save(image_path).map_err(|e| {
+            format!("Failed to write {}: {}", image_path.display(), e)
+        })?;
+
+        let json = serde_json::to_string_pretty(&layout)
+            .map_err(|e| format!("Failed to serialize atlas layout: {}", e))?;
+        let layout_path = image_path.with_extension("json");
+        std::fs::write(&layout_path, json).map_err(|e| {
+            format!("Failed to write {}: {}", layout_path.display(), e)
+        })?;
+
+        Ok(layout)
+    }
+
+    fn blit_extruded(
+        &self,
+        atlas: &mut RgbaImage,
+        image: &RgbaImage,
+        x: u32,
+        y: u32,
+    ) {
+        let extrude = self.extrude as i64;
+        let (w, h) = (image.width() as i64, image.height() as i64);
+
+        for dy in -extrude..h + extrude {
+            for dx in -extrude..w + extrude {
+                let src_x = dx.clamp(0, w - 1) as u32;
+                let src_y = dy.clamp(0, h - 1) as u32;
+                atlas.put_pixel(
+                    (x as i64 + dx) as u32,
+                    (y as i64 + dy) as u32,
+                    *image.get_pixel(src_x, src_y),
+                );
+            }
+        }
+    }
+}
+
+/// Atlas texture together with the location of every packed image.
+#[derive(Debug, Clone)]
+pub struct TextureAtlas {
+    texture: Texture,
+    layout: AtlasLayout,
+}
+
+impl TextureAtlas {
+    /// Load an atlas written by [`AtlasBuilder::write`] or the
+    /// `atlas_packer` tool. The image path in the layout is resolved
+    /// relative to the JSON file.
+    pub fn load(
+        layout_path: &Path,
+        options: TextureOptions,
+    ) -> Result<Self, String> {
+        let file = std::fs::File::open(layout_path)
+            .map_err(|e| format!("Failed to open atlas layout: {}", e))?;
+        let layout: AtlasLayout = serde_json::from_reader(file)
+            .map_err(|e| format!("Failed to parse atlas layout: {}", e))?;
+
+        let image_path = layout_path
+            .parent()
+            .map(|dir| dir.join(&layout.image_path))
+            .unwrap_or_else(|| PathBuf::from(&layout.image_path));
+        let texture = Texture::with_options(&image_path, options)?;
+
+        Ok(Self { texture, layout })
+    }
+
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    pub fn layout(&self) -> &AtlasLayout {
+        &self.layout
+    }
+
+    pub fn region(
+        &self,
+        name: &str,
+    ) -> Option<&AtlasRegion> {
+        self.layout.regions.get(name)
+    }
+
+    /// Normalized `(min, max)` texture coordinates of a packed image.
+    pub fn uv_rect(
+        &self,
+        name: &str,
+    ) -> Option<(Vec2, Vec2)> {
+        self.region(name)
+            .map(|region| region.uv_rect(self.layout.width, self.layout.height))
+    }
+}
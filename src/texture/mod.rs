@@ -1 +1,5 @@
+pub mod atlas;
+pub mod options;
+pub mod palette;
+#[allow(clippy::module_inception)]
 pub mod texture;
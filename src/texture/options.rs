@@ -0,0 +1,274 @@
+use gl::types::*;
+
+use crate::sprite::utils::color_key_util::ColorKey;
+
+/// Texture filtering used when a texture is minified or magnified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FilterMode {
+    #[default]
+    Nearest,
+    Linear,
+    NearestMipmapNearest,
+    LinearMipmapNearest,
+    NearestMipmapLinear,
+    LinearMipmapLinear,
+}
+
+impl FilterMode {
+    pub fn uses_mipmaps(self) -> bool {
+        !matches!(self, FilterMode::Nearest | FilterMode::Linear)
+    }
+
+    pub(crate) fn gl_min_filter(self) -> GLenum {
+        match self {
+            FilterMode::Nearest => gl::NEAREST,
+            FilterMode::Linear => gl::LINEAR,
+            FilterMode::NearestMipmapNearest => gl::NEAREST_MIPMAP_NEAREST,
+            FilterMode::LinearMipmapNearest => gl::LINEAR_MIPMAP_NEAREST,
+            FilterMode::NearestMipmapLinear => gl::NEAREST_MIPMAP_LINEAR,
+            FilterMode::LinearMipmapLinear => gl::LINEAR_MIPMAP_LINEAR,
+        }
+    }
+
+    /// Magnification has no mipmaps, so only the texel filter is used.
+    pub(crate) fn gl_mag_filter(self) -> GLenum {
+        match self {
+            FilterMode::Nearest
+            | FilterMode::NearestMipmapNearest
+            | FilterMode::NearestMipmapLinear => gl::NEAREST,
+            FilterMode::Linear
+            | FilterMode::LinearMipmapNearest
+            | FilterMode::LinearMipmapLinear => gl::LINEAR,
+        }
+    }
+}
+
+/// What happens when texture coordinates leave the 0..1 range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WrapMode {
+    #[default]
+    ClampToEdge,
+    Repeat,
+    MirroredRepeat,
+}
+
+impl WrapMode {
+    pub(crate) fn gl_wrap(self) -> GLenum {
+        match self {
+            WrapMode::ClampToEdge => gl::CLAMP_TO_EDGE,
+            WrapMode::Repeat => gl::REPEAT,
+            WrapMode::MirroredRepeat => gl::MIRRORED_REPEAT,
+        }
+    }
+}
+
+/// Sampling and format settings applied when a texture is uploaded.
+///
+/// The default matches the engine's pixel-art look: nearest filtering,
+/// clamped edges, linear (non-sRGB) storage and straight alpha.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TextureOptions {
+    pub min_filter: FilterMode,
+    pub mag_filter: FilterMode,
+    pub wrap_s: WrapMode,
+    pub wrap_t: WrapMode,
+    /// Store the texture as sRGB so sampling returns linear colors.
+    pub srgb: bool,
+    /// Multiply color channels by alpha on load. Draw premultiplied
+    /// textures with `glBlendFunc(GL_ONE, GL_ONE_MINUS_SRC_ALPHA)`.
+    pub premultiply_alpha: bool,
+    /// Make pixels of this color transparent on load.
+    pub color_key: Option<BakedColorKey>,
+}
+
+impl TextureOptions {
+    /// Crisp, unfiltered pixels without mipmaps.
+    pub fn pixel_art() -> Self {
+        Self::default()
+    }
+
+    /// Trilinear filtering for smooth scaling.
+    pub fn smooth() -> Self {
+        Self {
+            min_filter: FilterMode::LinearMipmapLinear,
+            mag_filter: FilterMode::Linear,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_filter(
+        mut self,
+        min_filter: FilterMode,
+        mag_filter: FilterMode,
+    ) -> Self {
+        self.min_filter = min_filter;
+        self.mag_filter = mag_filter;
+        self
+    }
+
+    pub fn with_wrap(
+        mut self,
+        wrap: WrapMode,
+    ) -> Self {
+        self.wrap_s = wrap;
+        self.wrap_t = wrap;
+        self
+    }
+
+    pub fn with_srgb(
+        mut self,
+        srgb: bool,
+    ) -> Self {
+        self.srgb = srgb;
+        self
+    }
+
+    pub fn with_premultiplied_alpha(
+        mut self,
+        premultiply_alpha: bool,
+    ) -> Self {
+        self.premultiply_alpha = premultiply_alpha;
+        self
+    }
+
+    /// Bake `color_key` into the alpha channel on load, so the texture
+    /// needs no per-fragment keying and blends and filters cleanly.
+    pub fn with_color_key(
+        mut self,
+        color_key: impl Into<BakedColorKey>,
+    ) -> Self {
+        self.color_key = Some(color_key.into());
+        self
+    }
+
+    pub(crate) fn internal_format(&self) -> GLenum {
+        if self.srgb {
+            gl::SRGB8_ALPHA8
+        } else {
+            gl::RGBA8
+        }
+    }
+}
+
+/// Color made transparent when a texture is loaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BakedColorKey {
+    pub color: [u8; 3],
+    /// Largest difference in any channel still treated as the key color.
+    pub tolerance: u8,
+}
+
+impl BakedColorKey {
+    /// `ColorKey::DEFAULT_THRESHOLD` in 0-255 steps.
+    pub const DEFAULT_TOLERANCE: u8 = 3;
+
+    pub fn new(
+        color: [u8; 3],
+        tolerance: u8,
+    ) -> Self {
+        Self { color, tolerance }
+    }
+
+    /// Key from a hex color code such as `"#FF00FF"`.
+    pub fn from_hex(
+        hex: &str,
+        tolerance: u8,
+    ) -> Result<Self, String> {
+        let key = ColorKey::from_hex(hex, 0.0)?;
+        Ok(Self::new(to_bytes(key.color.to_array()), tolerance))
+    }
+
+    fn matches(
+        &self,
+        pixel: &[u8],
+    ) -> bool {
+        pixel[3] > 0
+            && pixel[..3]
+                .iter()
+                .zip(self.color)
+                .all(|(&channel, key)| channel.abs_diff(key) <= self.tolerance)
+    }
+}
+
+/// Converts the shader threshold, a fraction of the channel range, to a
+/// tolerance in 0-255 steps.
+impl From<ColorKey> for BakedColorKey {
+    fn from(key: ColorKey) -> Self {
+        Self::new(
+            to_bytes(key.color.to_array()),
+            (key.threshold.clamp(0.0, 1.0) * 255.0).round() as u8,
+        )
+    }
+}
+
+fn to_bytes(color: [f32; 3]) -> [u8; 3] {
+    color.map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8)
+}
+
+/// Make pixels matching `key` transparent in tightly packed RGBA8 data
+/// `width` pixels wide.
+///
+/// Keyed pixels take the average color of their visible neighbours, so
+/// linear filtering and mipmaps fade edges to transparent instead of
+/// bleeding the key color in as a fringe.
+///
+/// ```
+/// use teengine::texture::options::{bake_color_key, BakedColorKey};
+///
+/// let magenta = BakedColorKey::from_hex("#FF00FF", 0).unwrap();
+/// let mut pixels = vec![255, 0, 255, 255, 10, 20, 30, 255];
+/// bake_color_key(&mut pixels, 2, magenta);
+/// assert_eq!(pixels, [10, 20, 30, 0, 10, 20, 30, 255]);
+/// ```
+pub fn bake_color_key(
+    data: &mut [u8],
+    width: u32,
+    key: BakedColorKey,
+) {
+    let keyed: Vec<bool> = data
+        .chunks_exact(4)
+        .map(|pixel| key.matches(pixel))
+        .collect();
+    if !keyed.contains(&true) {
+        return;
+    }
+
+    let width = width.max(1) as usize;
+    let height = keyed.len() / width;
+    let source = data.to_vec();
+    for (i, _) in keyed.iter().enumerate().filter(|(_, &keyed)| keyed) {
+        let (x, y) = (i % width, i / width);
+        let mut sum = [0u32; 3];
+        let mut count = 0;
+        for ny in y.saturating_sub(1)..(y + 2).min(height) {
+            for nx in x.saturating_sub(1)..(x + 2).min(width) {
+                let n = ny * width + nx;
+                if keyed[n] || source[n * 4 + 3] == 0 {
+                    continue;
+                }
+                for (total, &channel) in sum.iter_mut().zip(&source[n * 4..]) {
+                    *total += channel as u32;
+                }
+                count += 1;
+            }
+        }
+
+        let pixel = &mut data[i * 4..i * 4 + 4];
+        for (channel, total) in pixel.iter_mut().zip(sum) {
+            *channel = (total + count / 2)
+                .checked_div(count)
+                .map_or(0, |average| average as u8);
+        }
+        pixel[3] = 0;
+    }
+}
+
+/// Multiply the color channels of tightly packed RGBA8 pixels by alpha.
+pub fn premultiply_alpha(data: &mut [u8]) {
+    for pixel in data.chunks_exact_mut(4) {
+        let alpha = pixel[3] as u32;
+        for channel in &mut pixel[..3] {
+            *channel = ((*channel as u32 * alpha + 127) / 255) as u8;
+        }
+    }
+}
@@ -0,0 +1,338 @@
+use image::{Rgba, RgbaImage};
+use std::path::Path;
+
+use super::options::TextureOptions;
+use super::texture::Texture;
+use crate::sprite::utils::color_key_util::ColorKey;
+
+/// Most colors an indexed image can use, including the transparent one.
+pub const MAX_PALETTE_COLORS: usize = 256;
+
+const TRANSPARENT: [u8; 4] = [0, 0, 0, 0];
+
+/// Ordered list of RGBA colors. Index 0 is always fully transparent.
+///
+/// ```
+/// use image::{Rgba, RgbaImage};
+/// use teengine::texture::palette::{IndexedImage, Palette};
+///
+/// // A red goblin and its green recolor
+/// let mut red = RgbaImage::new(2, 1);
+/// red.put_pixel(0, 0, Rgba([200, 0, 0, 255]));
+/// let mut green = red.clone();
+/// green.put_pixel(0, 0, Rgba([0, 200, 0, 255]));
+///
+/// let palette = Palette::extract(&red, None).unwrap();
+/// assert_eq!(palette.colors(), &[[0, 0, 0, 0], [200, 0, 0, 255]]);
+///
+/// let indexed = IndexedImage::from_rgba(&red, &palette, None).unwrap();
+/// assert_eq!(indexed.indices(), &[1, 0]);
+///
+/// let variant = Palette::from_variant(&indexed, &green).unwrap();
+/// assert_eq!(variant.colors()[1], [0, 200, 0, 255]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Palette {
+    colors: Vec<[u8; 4]>,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            colors: vec![TRANSPARENT],
+        }
+    }
+}
+
+impl Palette {
+    /// Palette of a transparent entry followed by `colors`.
+    pub fn new(colors: &[[u8; 4]]) -> Result<Self, String> {
+        let mut palette = Self::default();
+        for &color in colors {
+            palette.push(color)?;
+        }
+        Ok(palette)
+    }
+
+    /// Unique colors of `image` in the order they first appear, scanning
+    /// rows from the top. Transparent pixels and pixels matching
+    /// `color_key` map to index 0.
+    pub fn extract(
+        image: &RgbaImage,
+        color_key: Option<&ColorKey>,
+    ) -> Result<Self, String> {
+        let mut palette = Self::default();
+        for pixel in image.pixels() {
+            if is_transparent(pixel, color_key) {
+                continue;
+            }
+            if palette.index_of(pixel.0).is_none() {
+                palette.push(pixel.0)?;
+            }
+        }
+        Ok(palette)
+    }
+
+    /// Palette matching `indexed` whose colors are taken from `variant`, a
+    /// recolored copy of the same image. Each index gets the variant color
+    /// at the first pixel using it.
+    pub fn from_variant(
+        indexed: &IndexedImage,
+        variant: &RgbaImage,
+    ) -> Result<Self, String> {
+        if variant.dimensions() != (indexed.width, indexed.height) {
+            return Err(format!(
+                "Variant is {}x{} but the indexed image is {}x{}",
+                variant.width(),
+                variant.height(),
+                indexed.width,
+                indexed.height
+            ));
+        }
+
+        let count = indexed
+            .indices
+            .iter()
+            .max()
+            .map_or(1, |&max| max as usize + 1);
+        let mut colors: Vec<Option<[u8; 4]>> = vec![None; count];
+        colors[0] = Some(TRANSPARENT);
+        for (&index, pixel) in indexed.indices.iter().zip(variant.pixels()) {
+            let color = &mut colors[index as usize];
+            if color.is_none() {
+                *color = Some(pixel.0);
+            }
+        }
+
+        Ok(Self {
+            colors: colors
+                .into_iter()
+                .map(|color| color.unwrap_or(TRANSPARENT))
+                .collect(),
+        })
+    }
+
+    pub fn colors(&self) -> &[[u8; 4]] {
+        &self.colors
+    }
+
+    pub fn len(&self) -> usize {
+        self.colors.len()
+    }
+
+    /// Always false; the transparent entry is always present.
+    pub fn is_empty(&self) -> bool {
+        self.colors.is_empty()
+    }
+
+    pub fn index_of(
+        &self,
+        color: [u8; 4],
+    ) -> Option<u8> {
+        self.colors
+            .iter()
+            .position(|&c| c == color)
+            .map(|index| index as u8)
+    }
+
+    /// Lay `palettes` out as the rows of an image, padded with transparent
+    /// pixels to the longest one. This is the format
+    /// [`PaletteTexture::load`] reads.
+    pub fn to_image(palettes: &[Palette]) -> RgbaImage {
+        let width = palettes.iter().map(Palette::len).max().unwrap_or(1);
+        let mut image =
+            RgbaImage::new(width as u32, palettes.len().max(1) as u32);
+        for (y, palette) in palettes.iter().enumerate() {
+            for (x, &color) in palette.colors.iter().enumerate() {
+                image.put_pixel(x as u32, y as u32, Rgba(color));
+            }
+        }
+        image
+    }
+
+    fn push(
+        &mut self,
+        color: [u8; 4],
+    ) -> Result<(), String> {
+        if self.colors.len() >= MAX_PALETTE_COLORS {
+            return Err(format!(
+                "Palette has more than {} colors",
+                MAX_PALETTE_COLORS
+            ));
+        }
+        self.colors.push(color);
+        Ok(())
+    }
+}
+
+/// Image storing a palette index per pixel instead of a color.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedImage {
+    width: u32,
+    height: u32,
+    indices: Vec<u8>,
+}
+
+impl IndexedImage {
+    /// Map every pixel of `image` to its index in `palette`. Transparent
+    /// pixels and pixels matching `color_key` become index 0.
+    pub fn from_rgba(
+        image: &RgbaImage,
+        palette: &Palette,
+        color_key: Option<&ColorKey>,
+    ) -> Result<Self, String> {
+        let indices = image
+            .enumerate_pixels()
+            .map(|(x, y, pixel)| {
+                if is_transparent(pixel, color_key) {
+                    return Ok(0);
+                }
+                palette.index_of(pixel.0).ok_or_else(|| {
+                    format!(
+                        "Pixel ({}, {}) has color {:?}, which is not in the \
+                         palette",
+                        x, y, pixel.0
+                    )
+                })
+            })
+            .collect::<Result<_, String>>()?;
+
+        Ok(Self {
+            width: image.width(),
+            height: image.height(),
+            indices,
+        })
+    }
+
+    /// Load a regular image and index it with its own extracted palette.
+    pub fn open(
+        path: &Path,
+        color_key: Option<&ColorKey>,
+    ) -> Result<(Self, Palette), String> {
+        let image = image::open(path)
+            .map_err(|e| format!("Failed to load {}: {}", path.display(), e))?
+            .into_rgba8();
+        let palette = Palette::extract(&image, color_key)?;
+        let indexed = Self::from_rgba(&image, &palette, color_key)?;
+        Ok((indexed, palette))
+    }
+
+    /// Read an image written by [`IndexedImage::to_rgba`], with the index
+    /// in the red channel.
+    pub fn open_encoded(path: &Path) -> Result<Self, String> {
+        let image = image::open(path)
+            .map_err(|e| format!("Failed to load {}: {}", path.display(), e))?
+            .into_rgba8();
+        Ok(Self {
+            width: image.width(),
+            height: image.height(),
+            indices: image.pixels().map(|pixel| pixel.0[0]).collect(),
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Indices row by row from the top left.
+    pub fn indices(&self) -> &[u8] {
+        &self.indices
+    }
+
+    /// Encode the indices in the red channel of an opaque image, the
+    /// layout the indexed sprite shader expects.
+    pub fn to_rgba(&self) -> RgbaImage {
+        let mut image = RgbaImage::new(self.width, self.height);
+        for (pixel, &index) in image.pixels_mut().zip(&self.indices) {
+            *pixel = Rgba([index, 0, 0, 255]);
+        }
+        image
+    }
+
+    /// Upload the encoded indices. Indexed textures are always sampled
+    /// with nearest filtering.
+    pub fn to_texture(&self) -> Result<Texture, String> {
+        Texture::from_rgba_bytes(
+            self.width,
+            self.height,
+            self.to_rgba().as_raw(),
+            TextureOptions::pixel_art(),
+        )
+    }
+}
+
+/// Lookup texture holding one palette per row.
+///
+/// Assign it to `Sprite::palette` together with an indexed sprite texture
+/// and pick the row with `Sprite::palette_row`, so color variants of a
+/// sprite share one image.
+#[derive(Debug, Clone)]
+pub struct PaletteTexture {
+    texture: Texture,
+}
+
+impl PaletteTexture {
+    pub fn new(palettes: &[Palette]) -> Result<Self, String> {
+        if palettes.is_empty() {
+            return Err(
+                "A palette texture needs at least one palette".to_string()
+            );
+        }
+        let image = Palette::to_image(palettes);
+        Self::from_image(&image)
+    }
+
+    /// Load a palette image with one palette per row, as written by the
+    /// `extract_palette` tool.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let image = image::open(path)
+            .map_err(|e| format!("Failed to load {}: {}", path.display(), e))?
+            .into_rgba8();
+        Self::from_image(&image)
+    }
+
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    /// Number of palettes.
+    pub fn rows(&self) -> u32 {
+        self.texture.height()
+    }
+
+    /// Colors per palette.
+    pub fn colors_per_row(&self) -> u32 {
+        self.texture.width()
+    }
+
+    fn from_image(image: &RgbaImage) -> Result<Self, String> {
+        let texture = Texture::from_rgba_bytes(
+            image.width(),
+            image.height(),
+            image.as_raw(),
+            TextureOptions::pixel_art(),
+        )?;
+        Ok(Self { texture })
+    }
+}
+
+fn is_transparent(
+    pixel: &Rgba<u8>,
+    color_key: Option<&ColorKey>,
+) -> bool {
+    if pixel.0[3] == 0 {
+        return true;
+    }
+    color_key.is_some_and(|key| {
+        let color = glam::Vec3::new(
+            pixel.0[0] as f32,
+            pixel.0[1] as f32,
+            pixel.0[2] as f32,
+        ) / 255.0;
+        (color - key.color).abs().max_element() < key.threshold
+    })
+}
@@ -1,28 +1,222 @@
 use gl::types::*;
-use image::GenericImageView;
-use std::path::Path;
+use image::{DynamicImage, GenericImageView};
+use std::cell::Cell;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
+use super::options::{bake_color_key, premultiply_alpha, TextureOptions};
+use crate::hot_reload::Reloadable;
+
+/// Handle to an OpenGL texture.
+///
+/// Cloning a `Texture` shares the underlying GL texture, so every `Sprite`
+/// or `Tileset` holding a clone sees the new pixels when the texture is
+/// reloaded in place.
 #[derive(Debug, Clone)]
 pub struct Texture {
+    inner: Rc<TextureInner>,
+}
+
+#[derive(Debug)]
+pub(crate) struct TextureInner {
     id: GLuint,
-    width: u32,
-    height: u32,
+    width: Cell<u32>,
+    height: Cell<u32>,
+    options: TextureOptions,
+    path: Option<PathBuf>,
 }
 
 impl Texture {
     pub fn new(path: &Path) -> Result<Self, String> {
+        Self::with_options(path, TextureOptions::default())
+    }
+
+    pub fn with_options(
+        path: &Path,
+        options: TextureOptions,
+    ) -> Result<Self, String> {
         let img = image::open(path).map_err(|e| e.to_string())?;
         let (width, height) = img.dimensions();
-        let data = img.into_rgba8().into_raw();
+        let texture = Self::create(
+            width,
+            height,
+            img.into_rgba8().into_raw(),
+            options,
+            Some(path.to_path_buf()),
+        );
+        Ok(texture)
+    }
+
+    /// Create a texture from tightly packed RGBA8 pixels, e.g. generated at
+    /// runtime.
+    pub fn from_rgba_bytes(
+        width: u32,
+        height: u32,
+        data: &[u8],
+        options: TextureOptions,
+    ) -> Result<Self, String> {
+        let expected = rgba_len(width, height)?;
+        if data.len() != expected {
+            return Err(format!(
+                "Expected {} bytes of RGBA data for a {}x{} texture, got {}",
+                expected,
+                width,
+                height,
+                data.len()
+            ));
+        }
+
+        Ok(Self::create(width, height, data.to_vec(), options, None))
+    }
+
+    pub fn from_image(
+        image: &DynamicImage,
+        options: TextureOptions,
+    ) -> Self {
+        let (width, height) = image.dimensions();
+        Self::create(width, height, image.to_rgba8().into_raw(), options, None)
+    }
+
+    /// Re-read the image this texture was loaded from and upload it into
+    /// the same GL texture. On failure the current pixels are kept.
+    pub fn reload(&self) -> Result<(), String> {
+        self.inner.reload()
+    }
+
+    /// Overwrite a `width` x `height` block of pixels starting at `x`, `y`
+    /// with tightly packed RGBA8 data.
+    pub fn update_region(
+        &self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        data: &[u8],
+    ) -> Result<(), String> {
+        let inside = x
+            .checked_add(width)
+            .is_some_and(|right| right <= self.width())
+            && y.checked_add(height)
+                .is_some_and(|bottom| bottom <= self.height());
+        if !inside {
+            return Err("Region is outside the texture".to_string());
+        }
+        let expected = rgba_len(width, height)?;
+        if data.len() != expected {
+            return Err(format!(
+                "Expected {} bytes of RGBA data, got {}",
+                expected,
+                data.len()
+            ));
+        }
+
+        let mut data = data.to_vec();
+        if let Some(key) = self.inner.options.color_key {
+            bake_color_key(&mut data, width, key);
+        }
+        if self.inner.options.premultiply_alpha {
+            premultiply_alpha(&mut data);
+        }
+
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.inner.id);
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            gl::TexSubImage2D(
+                gl::TEXTURE_2D,
+                0,
+                x as i32,
+                y as i32,
+                width as i32,
+                height as i32,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                data.as_ptr() as *const _,
+            );
+            if self.inner.options.min_filter.uses_mipmaps() {
+                gl::GenerateMipmap(gl::TEXTURE_2D);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn bind(&self) {
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.inner.id);
+        }
+    }
+
+    /// Raw GL texture name.
+    pub fn id(&self) -> GLuint {
+        self.inner.id
+    }
+
+    #[allow(unused)]
+    pub fn width(&self) -> u32 {
+        self.inner.width.get()
+    }
+
+    #[allow(unused)]
+    pub fn height(&self) -> u32 {
+        self.inner.height.get()
+    }
+
+    pub fn options(&self) -> &TextureOptions {
+        &self.inner.options
+    }
+
+    /// Image file this texture was loaded from, if any.
+    pub fn path(&self) -> Option<&Path> {
+        self.inner.path.as_deref()
+    }
+
+    pub(crate) fn inner(&self) -> &Rc<TextureInner> {
+        &self.inner
+    }
 
+    fn create(
+        width: u32,
+        height: u32,
+        data: Vec<u8>,
+        options: TextureOptions,
+        path: Option<PathBuf>,
+    ) -> Self {
         let mut id = 0;
         unsafe {
             gl::GenTextures(1, &mut id);
+        }
+        Self::upload(id, width, height, data, &options);
+
+        Self {
+            inner: Rc::new(TextureInner {
+                id,
+                width: Cell::new(width),
+                height: Cell::new(height),
+                options,
+                path,
+            }),
+        }
+    }
+
+    fn upload(
+        id: GLuint,
+        width: u32,
+        height: u32,
+        mut data: Vec<u8>,
+        options: &TextureOptions,
+    ) {
+        if let Some(key) = options.color_key {
+            bake_color_key(&mut data, width, key);
+        }
+        if options.premultiply_alpha {
+            premultiply_alpha(&mut data);
+        }
+
+        unsafe {
             gl::BindTexture(gl::TEXTURE_2D, id);
             gl::TexImage2D(
                 gl::TEXTURE_2D,
                 0,
-                gl::RGBA as i32,
+                options.internal_format() as i32,
                 width as i32,
                 height as i32,
                 0,
@@ -30,53 +224,78 @@ impl Texture {
                 gl::UNSIGNED_BYTE,
                 data.as_ptr() as *const _,
             );
-            gl::GenerateMipmap(gl::TEXTURE_2D);
+            // Mipmaps are only sampled by the mipmap min filters
+            if options.min_filter.uses_mipmaps() {
+                gl::GenerateMipmap(gl::TEXTURE_2D);
+            }
             gl::TexParameteri(
                 gl::TEXTURE_2D,
                 gl::TEXTURE_WRAP_S,
-                gl::CLAMP_TO_EDGE as i32,
+                options.wrap_s.gl_wrap() as i32,
             );
             gl::TexParameteri(
                 gl::TEXTURE_2D,
                 gl::TEXTURE_WRAP_T,
-                gl::CLAMP_TO_EDGE as i32,
+                options.wrap_t.gl_wrap() as i32,
             );
             gl::TexParameteri(
                 gl::TEXTURE_2D,
                 gl::TEXTURE_MIN_FILTER,
-                gl::NEAREST as i32,
+                options.min_filter.gl_min_filter() as i32,
             );
             gl::TexParameteri(
                 gl::TEXTURE_2D,
                 gl::TEXTURE_MAG_FILTER,
-                gl::NEAREST as i32,
+                options.mag_filter.gl_mag_filter() as i32,
             );
         }
-
-        Ok(Self { id, width, height })
     }
+}
 
-    pub fn bind(&self) {
-        unsafe {
-            gl::BindTexture(gl::TEXTURE_2D, self.id);
-        }
+impl Reloadable for TextureInner {
+    fn source_paths(&self) -> Vec<PathBuf> {
+        self.path.iter().cloned().collect()
     }
 
-    #[allow(unused)]
-    pub fn width(&self) -> u32 {
-        self.width
-    }
+    fn reload(&self) -> Result<(), String> {
+        let path = self
+            .path
+            .as_deref()
+            .ok_or("Texture was not loaded from a file")?;
+        let img = image::open(path).map_err(|e| e.to_string())?;
+        let (width, height) = img.dimensions();
 
-    #[allow(unused)]
-    pub fn height(&self) -> u32 {
-        self.height
+        Texture::upload(
+            self.id,
+            width,
+            height,
+            img.into_rgba8().into_raw(),
+            &self.options,
+        );
+        self.width.set(width);
+        self.height.set(height);
+        Ok(())
     }
 }
 
-impl Drop for Texture {
+impl Drop for TextureInner {
     fn drop(&mut self) {
         unsafe {
             gl::DeleteTextures(1, &self.id);
         }
     }
 }
+
+/// Byte length of tightly packed RGBA8 pixels for a `width` x `height`
+/// block.
+fn rgba_len(
+    width: u32,
+    height: u32,
+) -> Result<usize, String> {
+    (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|pixels| pixels.checked_mul(4))
+        .ok_or_else(|| {
+            format!("Texture size {}x{} is too large", width, height)
+        })
+}
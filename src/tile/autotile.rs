@@ -0,0 +1,444 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use super::TileInstance;
+
+/// Neighbour bits of a 4-bit mask.
+pub const EDGE_NORTH: u8 = 1;
+pub const EDGE_EAST: u8 = 2;
+pub const EDGE_SOUTH: u8 = 4;
+pub const EDGE_WEST: u8 = 8;
+
+/// Neighbour bits of an 8-bit blob mask, clockwise from north.
+pub const BLOB_NORTH: u8 = 1;
+pub const BLOB_NORTH_EAST: u8 = 2;
+pub const BLOB_EAST: u8 = 4;
+pub const BLOB_SOUTH_EAST: u8 = 8;
+pub const BLOB_SOUTH: u8 = 16;
+pub const BLOB_SOUTH_WEST: u8 = 32;
+pub const BLOB_WEST: u8 = 64;
+pub const BLOB_NORTH_WEST: u8 = 128;
+
+// Neighbour offsets in blob bit order
+const BLOB_OFFSETS: [(i64, i64); 8] = [
+    (0, -1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+];
+
+/// Terrain set as written in the tileset JSON under `terrain_sets`.
+///
+/// ```json
+/// { "name": "walls", "type": "bitmask4", "masks": { "0": 16, "5": 21 } }
+/// { "name": "cliffs", "type": "blob47", "connect_border": true,
+///   "masks": { "0": 32, "255": 78 } }
+/// { "name": "coast", "type": "wang", "terrains": ["grass", "water"],
+///   "corners": { "40": [1, 1, 2, 2], "41": [2, 2, 2, 2] } }
+/// ```
+///
+/// Bitmask sets map the mask of same-set neighbours to a local tile ID,
+/// see the `EDGE_*` and `BLOB_*` bits. Wang sets list the terrain at the
+/// top left, top right, bottom right and bottom left corners of each
+/// tile, where 1 is the first entry of `terrains` and 0 means no terrain,
+/// as in Tiled.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerrainSetData {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub terrain_type: TerrainType,
+    /// Bitmask sets: treat cells outside the layer as part of the set.
+    #[serde(default)]
+    pub connect_border: bool,
+    /// Bitmask sets: local tile ID for each neighbour mask.
+    #[serde(default)]
+    pub masks: HashMap<u8, u32>,
+    /// Wang sets: terrain names.
+    #[serde(default)]
+    pub terrains: Vec<String>,
+    /// Wang sets: corner terrains of each local tile ID.
+    #[serde(default)]
+    pub corners: HashMap<u32, [u8; 4]>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TerrainType {
+    /// Tiles keyed by a 4-bit mask of the edge neighbours.
+    Bitmask4,
+    /// Tiles keyed by an 8-bit mask reduced with [`reduce_blob_mask`],
+    /// which leaves the 47 shapes of the classic blob tileset.
+    Blob47,
+    /// Corner based Wang set.
+    Wang,
+}
+
+/// Drop the corner bits of a blob mask whose two adjacent edges are not
+/// both set, since those corners cannot change the tile's look.
+///
+/// ```
+/// use std::collections::HashSet;
+/// use teengine::tile::autotile::{reduce_blob_mask, BLOB_NORTH_EAST};
+///
+/// assert_eq!(reduce_blob_mask(BLOB_NORTH_EAST), 0);
+///
+/// let shapes: HashSet<u8> = (0..=255).map(reduce_blob_mask).collect();
+/// assert_eq!(shapes.len(), 47);
+/// ```
+pub fn reduce_blob_mask(mask: u8) -> u8 {
+    let mut reduced = mask;
+    for corner in [1, 3, 5, 7] {
+        let before = 1 << (corner - 1);
+        let after = 1 << ((corner + 1) % 8);
+        if mask & before == 0 || mask & after == 0 {
+            reduced &= !(1 << corner);
+        }
+    }
+    reduced
+}
+
+/// Terrain set of a tileset resolved to global tile IDs, ready to be added
+/// to a `TileLayer` with `TileLayer::add_terrain`.
+///
+/// ```
+/// use teengine::tile::{TerrainRules, TileInstance, TileLayer};
+///
+/// // Horizontal walls: 10 alone, 11 and 13 as the west and east ends,
+/// // 12 in the middle
+/// let data = serde_json::from_str(
+///     r#"{ "name": "walls", "type": "bitmask4",
+///          "masks": { "0": 10, "2": 11, "10": 12, "8": 13 } }"#,
+/// )
+/// .unwrap();
+/// let rules = TerrainRules::new(&data, 0);
+/// let mut layer = TileLayer::new(4, 1).with_terrain(rules);
+///
+/// for x in 0..3 {
+///     layer.set_tile(x, 0, TileInstance::new(10));
+/// }
+/// let ids: Vec<_> = (0..3).map(|x| layer.get_tile(x, 0).unwrap().id).collect();
+/// assert_eq!(ids, [11, 12, 13]);
+///
+/// layer.clear_tile(1, 0);
+/// assert_eq!(layer.get_tile(0, 0).unwrap().id, 10);
+/// ```
+#[derive(Debug, Clone)]
+pub struct TerrainRules {
+    name: String,
+    kind: TerrainKind,
+}
+
+#[derive(Debug, Clone)]
+enum TerrainKind {
+    Bitmask {
+        blob: bool,
+        tiles: HashMap<u8, u32>,
+        members: HashSet<u32>,
+        connect_border: bool,
+    },
+    Wang {
+        terrains: Vec<String>,
+        // sorted by ID so best matches are picked deterministically
+        tiles: Vec<(u32, [u8; 4])>,
+    },
+}
+
+impl TerrainRules {
+    /// Resolve `data` for a tileset starting at `first_gid`.
+    pub fn new(
+        data: &TerrainSetData,
+        first_gid: u32,
+    ) -> Self {
+        let kind = match data.terrain_type {
+            TerrainType::Bitmask4 | TerrainType::Blob47 => {
+                let tiles: HashMap<u8, u32> = data
+                    .masks
+                    .iter()
+                    .map(|(&mask, &id)| (mask, first_gid + id))
+                    .collect();
+                TerrainKind::Bitmask {
+                    blob: data.terrain_type == TerrainType::Blob47,
+                    members: tiles.values().copied().collect(),
+                    tiles,
+                    connect_border: data.connect_border,
+                }
+            }
+            TerrainType::Wang => {
+                let mut tiles: Vec<(u32, [u8; 4])> = data
+                    .corners
+                    .iter()
+                    .map(|(&id, &corners)| (first_gid + id, corners))
+                    .collect();
+                tiles.sort_by_key(|&(id, _)| id);
+                TerrainKind::Wang {
+                    terrains: data.terrains.clone(),
+                    tiles,
+                }
+            }
+        };
+
+        Self {
+            name: data.name.clone(),
+            kind,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether `gid` is one of the set's tiles.
+    pub fn contains(
+        &self,
+        gid: u32,
+    ) -> bool {
+        match &self.kind {
+            TerrainKind::Bitmask { members, .. } => members.contains(&gid),
+            TerrainKind::Wang { tiles, .. } => {
+                tiles.binary_search_by_key(&gid, |&(id, _)| id).is_ok()
+            }
+        }
+    }
+
+    /// Terrain names of a Wang set, empty for bitmask sets.
+    pub fn terrains(&self) -> &[String] {
+        match &self.kind {
+            TerrainKind::Wang { terrains, .. } => terrains,
+            TerrainKind::Bitmask { .. } => &[],
+        }
+    }
+
+    /// Tile for a neighbour mask of a bitmask set. Blob masks are reduced
+    /// first and fall back to their edge bits if the set lacks the shape.
+    pub fn bitmask_tile(
+        &self,
+        mask: u8,
+    ) -> Option<u32> {
+        let TerrainKind::Bitmask { blob, tiles, .. } = &self.kind else {
+            return None;
+        };
+        if !blob {
+            return tiles.get(&(mask & 0x0F)).copied();
+        }
+        let mask = reduce_blob_mask(mask);
+        let edges = BLOB_NORTH | BLOB_EAST | BLOB_SOUTH | BLOB_WEST;
+        tiles
+            .get(&mask)
+            .or_else(|| tiles.get(&(mask & edges)))
+            .copied()
+    }
+
+    /// Corner terrains of a Wang tile.
+    pub fn wang_corners(
+        &self,
+        gid: u32,
+    ) -> Option<[u8; 4]> {
+        let TerrainKind::Wang { tiles, .. } = &self.kind else {
+            return None;
+        };
+        tiles
+            .binary_search_by_key(&gid, |&(id, _)| id)
+            .ok()
+            .map(|index| tiles[index].1)
+    }
+
+    /// Wang tile with exactly these corners, or else the one matching the
+    /// most of them.
+    pub fn wang_tile(
+        &self,
+        corners: [u8; 4],
+    ) -> Option<u32> {
+        let TerrainKind::Wang { tiles, .. } = &self.kind else {
+            return None;
+        };
+        let score = |candidate: &[u8; 4]| {
+            candidate
+                .iter()
+                .zip(&corners)
+                .filter(|(a, b)| a == b)
+                .count()
+        };
+        tiles
+            .iter()
+            .max_by_key(|(id, candidate)| {
+                (score(candidate), std::cmp::Reverse(*id))
+            })
+            .map(|&(id, _)| id)
+    }
+
+    /// Recompute the tiles around (`x`, `y`) after that cell changed from
+    /// `previous` to its current tile.
+    pub(crate) fn update_around(
+        &self,
+        tiles: &mut [Vec<Option<TileInstance>>],
+        x: u32,
+        y: u32,
+        previous: Option<u32>,
+    ) {
+        let current = cell(tiles, x as i64, y as i64).map(|tile| tile.id);
+        let touches_set =
+            |id: Option<u32>| id.is_some_and(|id| self.contains(id));
+        if !touches_set(current) && !touches_set(previous) {
+            return;
+        }
+
+        match &self.kind {
+            TerrainKind::Bitmask { .. } => {
+                for (dx, dy) in std::iter::once((0, 0)).chain(BLOB_OFFSETS) {
+                    self.update_bitmask_cell(
+                        tiles,
+                        x as i64 + dx,
+                        y as i64 + dy,
+                    );
+                }
+            }
+            TerrainKind::Wang { .. } => {
+                // Erasing a Wang tile paints its corners with no terrain
+                let painted = current
+                    .and_then(|id| self.wang_corners(id))
+                    .unwrap_or([0; 4]);
+                for (dx, dy) in BLOB_OFFSETS {
+                    self.update_wang_cell(
+                        tiles, x as i64, y as i64, dx, dy, painted,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Recompute every tile of a bitmask set. Wang tiles hold their own
+    /// corners and are left as they are.
+    pub(crate) fn refresh(
+        &self,
+        tiles: &mut [Vec<Option<TileInstance>>],
+    ) {
+        if !matches!(self.kind, TerrainKind::Bitmask { .. }) {
+            return;
+        }
+        for y in 0..tiles.len() {
+            for x in 0..tiles[y].len() {
+                self.update_bitmask_cell(tiles, x as i64, y as i64);
+            }
+        }
+    }
+
+    fn update_bitmask_cell(
+        &self,
+        tiles: &mut [Vec<Option<TileInstance>>],
+        x: i64,
+        y: i64,
+    ) {
+        let TerrainKind::Bitmask { connect_border, .. } = self.kind else {
+            return;
+        };
+        if !cell(tiles, x, y).is_some_and(|tile| self.contains(tile.id)) {
+            return;
+        }
+
+        let mut mask = 0;
+        for (bit, (dx, dy)) in BLOB_OFFSETS.iter().enumerate() {
+            let (nx, ny) = (x + dx, y + dy);
+            let connected = if in_bounds(tiles, nx, ny) {
+                cell(tiles, nx, ny).is_some_and(|tile| self.contains(tile.id))
+            } else {
+                connect_border
+            };
+            if connected {
+                mask |= 1 << bit;
+            }
+        }
+        // The 4-bit layout packs the edge bits of the blob layout
+        let mask = match self.kind {
+            TerrainKind::Bitmask { blob: false, .. } => {
+                let edge = |bit: u8, value: u8| {
+                    if mask & bit != 0 {
+                        value
+                    } else {
+                        0
+                    }
+                };
+                edge(BLOB_NORTH, EDGE_NORTH)
+                    | edge(BLOB_EAST, EDGE_EAST)
+                    | edge(BLOB_SOUTH, EDGE_SOUTH)
+                    | edge(BLOB_WEST, EDGE_WEST)
+            }
+            _ => mask,
+        };
+
+        if let Some(id) = self.bitmask_tile(mask) {
+            self.set_id(tiles, x, y, id);
+        }
+    }
+
+    fn update_wang_cell(
+        &self,
+        tiles: &mut [Vec<Option<TileInstance>>],
+        x: i64,
+        y: i64,
+        dx: i64,
+        dy: i64,
+        painted: [u8; 4],
+    ) {
+        let (nx, ny) = (x + dx, y + dy);
+        let Some(mut corners) =
+            cell(tiles, nx, ny).and_then(|tile| self.wang_corners(tile.id))
+        else {
+            return;
+        };
+
+        // Corner k of a cell sits on vertex (x + CX[k], y + CY[k])
+        const CX: [i64; 4] = [0, 1, 1, 0];
+        const CY: [i64; 4] = [0, 0, 1, 1];
+        for k in 0..4 {
+            let vertex = (nx + CX[k], ny + CY[k]);
+            if let Some(shared) =
+                (0..4).find(|&p| (x + CX[p], y + CY[p]) == vertex)
+            {
+                corners[k] = painted[shared];
+            }
+        }
+
+        if let Some(id) = self.wang_tile(corners) {
+            self.set_id(tiles, nx, ny, id);
+        }
+    }
+
+    fn set_id(
+        &self,
+        tiles: &mut [Vec<Option<TileInstance>>],
+        x: i64,
+        y: i64,
+        id: u32,
+    ) {
+        let Some(tile) = tiles[y as usize][x as usize].as_mut() else {
+            return;
+        };
+        tile.id = id;
+    }
+}
+
+fn in_bounds(
+    tiles: &[Vec<Option<TileInstance>>],
+    x: i64,
+    y: i64,
+) -> bool {
+    y >= 0
+        && (y as usize) < tiles.len()
+        && x >= 0
+        && (x as usize) < tiles[y as usize].len()
+}
+
+fn cell(
+    tiles: &[Vec<Option<TileInstance>>],
+    x: i64,
+    y: i64,
+) -> Option<&TileInstance> {
+    if !in_bounds(tiles, x, y) {
+        return None;
+    }
+    tiles[y as usize][x as usize].as_ref()
+}
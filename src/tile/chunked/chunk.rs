@@ -0,0 +1,94 @@
+use crate::tile::TileInstance;
+
+/// Square block of tiles of a `ChunkedLayer`, row by row from the top
+/// left. Positions are local to the chunk.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chunk {
+    size: u32,
+    tiles: Vec<Option<TileInstance>>,
+}
+
+impl Chunk {
+    /// Empty chunk of `size` by `size` tiles.
+    pub fn new(size: u32) -> Self {
+        Self {
+            size,
+            tiles: vec![None; size as usize * size as usize],
+        }
+    }
+
+    /// Chunk holding `tiles`, which must have `size * size` entries.
+    pub fn from_tiles(
+        size: u32,
+        tiles: Vec<Option<TileInstance>>,
+    ) -> Result<Self, String> {
+        if tiles.len() != size as usize * size as usize {
+            return Err(format!(
+                "Chunk of size {} needs {} tiles, got {}",
+                size,
+                size * size,
+                tiles.len()
+            ));
+        }
+        Ok(Self { size, tiles })
+    }
+
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    pub fn tiles(&self) -> &[Option<TileInstance>] {
+        &self.tiles
+    }
+
+    pub fn get(
+        &self,
+        x: u32,
+        y: u32,
+    ) -> Option<TileInstance> {
+        *self.tiles.get(self.index(x, y)?)?
+    }
+
+    pub fn set(
+        &mut self,
+        x: u32,
+        y: u32,
+        tile: Option<TileInstance>,
+    ) {
+        if let Some(index) = self.index(x, y) {
+            self.tiles[index] = tile;
+        }
+    }
+
+    /// Put `tile` in every cell.
+    pub fn fill(
+        &mut self,
+        tile: TileInstance,
+    ) {
+        self.tiles.fill(Some(tile));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiles.iter().all(Option::is_none)
+    }
+
+    /// Placed tiles with their local position.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, u32, TileInstance)> + '_ {
+        let size = self.size as usize;
+        self.tiles
+            .iter()
+            .enumerate()
+            .filter_map(move |(index, tile)| {
+                Some(((index % size) as u32, (index / size) as u32, (*tile)?))
+            })
+    }
+
+    fn index(
+        &self,
+        x: u32,
+        y: u32,
+    ) -> Option<usize> {
+        (x < self.size && y < self.size)
+            .then_some(y as usize * self.size as usize + x as usize)
+    }
+}
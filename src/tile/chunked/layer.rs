@@ -0,0 +1,300 @@
+use std::collections::{HashMap, HashSet};
+
+use super::{Chunk, ChunkSource};
+use crate::shader::Material;
+use crate::tile::TileInstance;
+
+/// Chunk position: chunk (0, 0) covers tiles (0, 0) up to
+/// (`chunk_size - 1`, `chunk_size - 1`), chunk (-1, 0) the tiles just left
+/// of it.
+pub type ChunkCoord = (i32, i32);
+
+const DEFAULT_LOAD_MARGIN: u32 = 1;
+
+/// Tile layer without bounds, kept in memory one chunk at a time.
+///
+/// Tile positions are signed. [`ChunkedLayer::stream`] loads the chunks
+/// around a view from the layer's [`ChunkSource`] and unloads those that
+/// left it, handing edited chunks back to the source to store. Chunks
+/// still edited when the layer is dropped are saved then.
+///
+/// ```
+/// use teengine::tile::chunked::{Chunk, ChunkedLayer, ProceduralChunkSource};
+/// use teengine::tile::TileInstance;
+///
+/// // Chunks left of x = 0 are water (1), the rest grass (0)
+/// let source = ProceduralChunkSource::new(|(x, _), size| {
+///     let mut chunk = Chunk::new(size);
+///     chunk.fill(TileInstance::new(if x < 0 { 1 } else { 0 }));
+///     chunk
+/// });
+/// let mut layer =
+///     ChunkedLayer::new(16, Box::new(source)).with_load_margin(0);
+///
+/// layer.stream((-20, -5), (10, 5)).unwrap();
+/// assert_eq!(layer.get_tile(-20, 0).map(|tile| tile.id), Some(1));
+/// assert_eq!(layer.get_tile(3, -1).map(|tile| tile.id), Some(0));
+///
+/// // Moving far away unloads the chunks left behind
+/// layer.stream((500, 500), (520, 510)).unwrap();
+/// assert!(layer.get_tile(-20, 0).is_none());
+/// ```
+pub struct ChunkedLayer {
+    pub visible: bool,
+    /// Custom shader the layer is drawn with instead of the renderer's.
+    pub material: Option<Material>,
+    chunk_size: u32,
+    load_margin: u32,
+    chunks: HashMap<ChunkCoord, Chunk>,
+    edited: HashSet<ChunkCoord>,
+    source: Box<dyn ChunkSource>,
+}
+
+impl ChunkedLayer {
+    pub fn new(
+        chunk_size: u32,
+        source: Box<dyn ChunkSource>,
+    ) -> Self {
+        Self {
+            visible: true,
+            material: None,
+            chunk_size: chunk_size.max(1),
+            load_margin: DEFAULT_LOAD_MARGIN,
+            chunks: HashMap::new(),
+            edited: HashSet::new(),
+            source,
+        }
+    }
+
+    /// Chunks loaded beyond the view on every side, so they are ready
+    /// before they scroll into sight. Chunks are unloaded one chunk
+    /// further out, so moving back and forth over a border does not
+    /// reload them.
+    pub fn with_load_margin(
+        mut self,
+        chunks: u32,
+    ) -> Self {
+        self.load_margin = chunks;
+        self
+    }
+
+    pub fn chunk_size(&self) -> u32 {
+        self.chunk_size
+    }
+
+    /// Chunk containing tile (`x`, `y`).
+    pub fn chunk_coord(
+        &self,
+        x: i32,
+        y: i32,
+    ) -> ChunkCoord {
+        let size = self.chunk_size as i32;
+        (x.div_euclid(size), y.div_euclid(size))
+    }
+
+    /// Tile at the top left corner of the chunk `coord`.
+    pub fn chunk_origin(
+        &self,
+        (x, y): ChunkCoord,
+    ) -> (i32, i32) {
+        let size = self.chunk_size as i32;
+        (x * size, y * size)
+    }
+
+    pub fn chunk(
+        &self,
+        coord: ChunkCoord,
+    ) -> Option<&Chunk> {
+        self.chunks.get(&coord)
+    }
+
+    pub fn is_resident(
+        &self,
+        coord: ChunkCoord,
+    ) -> bool {
+        self.chunks.contains_key(&coord)
+    }
+
+    /// Chunks currently in memory.
+    pub fn resident_chunks(
+        &self
+    ) -> impl Iterator<Item = (ChunkCoord, &Chunk)> {
+        self.chunks.iter().map(|(&coord, chunk)| (coord, chunk))
+    }
+
+    pub fn resident_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Tile at (`x`, `y`), `None` if the cell is empty or its chunk is not
+    /// loaded.
+    pub fn get_tile(
+        &self,
+        x: i32,
+        y: i32,
+    ) -> Option<TileInstance> {
+        let coord = self.chunk_coord(x, y);
+        let (local_x, local_y) = self.local(x, y, coord);
+        self.chunks.get(&coord)?.get(local_x, local_y)
+    }
+
+    /// Place `tile` at (`x`, `y`), loading its chunk if needed.
+    pub fn set_tile(
+        &mut self,
+        x: i32,
+        y: i32,
+        tile: TileInstance,
+    ) -> Result<(), String> {
+        self.replace_tile(x, y, Some(tile))
+    }
+
+    pub fn clear_tile(
+        &mut self,
+        x: i32,
+        y: i32,
+    ) -> Result<(), String> {
+        self.replace_tile(x, y, None)
+    }
+
+    fn replace_tile(
+        &mut self,
+        x: i32,
+        y: i32,
+        tile: Option<TileInstance>,
+    ) -> Result<(), String> {
+        let coord = self.chunk_coord(x, y);
+        let (local_x, local_y) = self.local(x, y, coord);
+        self.load_chunk(coord)?;
+        if let Some(chunk) = self.chunks.get_mut(&coord) {
+            chunk.set(local_x, local_y, tile);
+            self.edited.insert(coord);
+        }
+        Ok(())
+    }
+
+    /// Make the chunk `coord` resident, from the source or empty.
+    pub fn load_chunk(
+        &mut self,
+        coord: ChunkCoord,
+    ) -> Result<(), String> {
+        if self.chunks.contains_key(&coord) {
+            return Ok(());
+        }
+        let chunk = self
+            .source
+            .load_chunk(coord, self.chunk_size)?
+            .unwrap_or_else(|| Chunk::new(self.chunk_size));
+        self.chunks.insert(coord, chunk);
+        Ok(())
+    }
+
+    /// Drop the chunk `coord` from memory, saving it first if it was
+    /// edited. The chunk stays loaded if saving fails.
+    pub fn unload_chunk(
+        &mut self,
+        coord: ChunkCoord,
+    ) -> Result<(), String> {
+        self.save_chunk(coord)?;
+        self.chunks.remove(&coord);
+        Ok(())
+    }
+
+    fn save_chunk(
+        &mut self,
+        coord: ChunkCoord,
+    ) -> Result<(), String> {
+        if !self.edited.contains(&coord) {
+            return Ok(());
+        }
+        if let Some(chunk) = self.chunks.get(&coord) {
+            self.source.save_chunk(coord, chunk)?;
+        }
+        self.edited.remove(&coord);
+        Ok(())
+    }
+
+    /// Hand every edited chunk to the source, keeping them loaded.
+    pub fn save_all(&mut self) -> Result<(), String> {
+        let mut edited: Vec<ChunkCoord> = self.edited.iter().copied().collect();
+        edited.sort_unstable();
+        for coord in edited {
+            self.save_chunk(coord)?;
+        }
+        Ok(())
+    }
+
+    pub fn unload_all(&mut self) -> Result<(), String> {
+        self.save_all()?;
+        self.chunks.clear();
+        Ok(())
+    }
+
+    /// Load the chunks covering the tiles from `min` to `max` (inclusive)
+    /// plus the load margin, and unload those further out.
+    pub fn stream(
+        &mut self,
+        min: (i32, i32),
+        max: (i32, i32),
+    ) -> Result<(), String> {
+        let first = self.chunk_coord(min.0.min(max.0), min.1.min(max.1));
+        let last = self.chunk_coord(min.0.max(max.0), min.1.max(max.1));
+        let margin = self.load_margin as i32;
+
+        let keep = margin + 1;
+        let mut far: Vec<ChunkCoord> = self
+            .chunks
+            .keys()
+            .copied()
+            .filter(|&(x, y)| {
+                x < first.0 - keep
+                    || x > last.0 + keep
+                    || y < first.1 - keep
+                    || y > last.1 + keep
+            })
+            .collect();
+        far.sort_unstable();
+        for coord in far {
+            self.unload_chunk(coord)?;
+        }
+
+        for y in first.1 - margin..=last.1 + margin {
+            for x in first.0 - margin..=last.0 + margin {
+                self.load_chunk((x, y))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn local(
+        &self,
+        x: i32,
+        y: i32,
+        coord: ChunkCoord,
+    ) -> (u32, u32) {
+        let (origin_x, origin_y) = self.chunk_origin(coord);
+        ((x - origin_x) as u32, (y - origin_y) as u32)
+    }
+}
+
+impl std::fmt::Debug for ChunkedLayer {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.debug_struct("ChunkedLayer")
+            .field("visible", &self.visible)
+            .field("chunk_size", &self.chunk_size)
+            .field("load_margin", &self.load_margin)
+            .field("resident_chunks", &self.chunks.len())
+            .field("edited_chunks", &self.edited.len())
+            .finish()
+    }
+}
+
+impl Drop for ChunkedLayer {
+    fn drop(&mut self) {
+        if let Err(e) = self.save_all() {
+            eprintln!("Failed to save edited chunks: {}", e);
+        }
+    }
+}
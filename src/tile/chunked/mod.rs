@@ -0,0 +1,41 @@
+//! Unbounded tile layers split into chunks that stream in and out around
+//! the camera.
+//!
+//! A [`ChunkedLayer`] keeps only the chunks near the view resident. Chunks
+//! come from a [`ChunkSource`]: a [`ProceduralChunkSource`] generates them,
+//! a [`FileChunkSource`] reads and writes them as JSON files and can fall
+//! back to a generator for chunks never saved.
+//!
+//! ```no_run
+//! use teengine::tile::chunked::{
+//!     Chunk, ChunkedLayer, FileChunkSource, ProceduralChunkSource,
+//! };
+//! use teengine::tile::TileInstance;
+//! # fn example(
+//! #     map: &mut teengine::tile::TileMap,
+//! #     camera: &teengine::Camera,
+//! # ) -> Result<(), String> {
+//! let generator = ProceduralChunkSource::new(|_coord, size| {
+//!     let mut chunk = Chunk::new(size);
+//!     chunk.fill(TileInstance::new(0));
+//!     chunk
+//! });
+//! let source =
+//!     FileChunkSource::new("saves/world").with_fallback(Box::new(generator));
+//! let layer = ChunkedLayer::new(32, Box::new(source));
+//! map.add_chunked_layer("world".to_string(), layer);
+//!
+//! // Every frame
+//! let (min, max) = camera.visible_rect();
+//! map.update_chunks(min, max)?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod chunk;
+pub mod layer;
+pub mod source;
+
+pub use chunk::Chunk;
+pub use layer::{ChunkCoord, ChunkedLayer};
+pub use source::{ChunkSource, FileChunkSource, ProceduralChunkSource};
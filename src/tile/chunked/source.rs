@@ -0,0 +1,188 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{Chunk, ChunkCoord};
+use crate::tile::{TileFlags, TileInstance};
+
+/// Where a `ChunkedLayer` gets its chunks from and stores edited ones.
+pub trait ChunkSource {
+    /// Chunk at `coord` with `size` by `size` tiles, or `None` if the
+    /// source has nothing there and the chunk starts out empty.
+    fn load_chunk(
+        &mut self,
+        coord: ChunkCoord,
+        size: u32,
+    ) -> Result<Option<Chunk>, String>;
+
+    /// Keep an edited chunk that is being unloaded. Sources that cannot
+    /// store chunks drop the edits.
+    fn save_chunk(
+        &mut self,
+        _coord: ChunkCoord,
+        _chunk: &Chunk,
+    ) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Generates chunks with a function of their coordinate and size. Edits
+/// are lost when a chunk unloads; wrap it in a [`FileChunkSource`] to keep
+/// them.
+pub struct ProceduralChunkSource {
+    generate: Box<dyn FnMut(ChunkCoord, u32) -> Chunk>,
+}
+
+impl ProceduralChunkSource {
+    /// `generate` must return the same chunk for the same coordinate, so
+    /// a chunk looks the same every time it streams back in.
+    pub fn new(
+        generate: impl FnMut(ChunkCoord, u32) -> Chunk + 'static
+    ) -> Self {
+        Self {
+            generate: Box::new(generate),
+        }
+    }
+}
+
+impl ChunkSource for ProceduralChunkSource {
+    fn load_chunk(
+        &mut self,
+        coord: ChunkCoord,
+        size: u32,
+    ) -> Result<Option<Chunk>, String> {
+        let chunk = (self.generate)(coord, size);
+        if chunk.size() != size {
+            return Err(format!(
+                "Generated chunk {:?} has size {}, expected {}",
+                coord,
+                chunk.size(),
+                size
+            ));
+        }
+        Ok(Some(chunk))
+    }
+}
+
+/// Stores chunks as JSON files named `chunk_<x>_<y>.json` in a directory.
+/// Chunks without a file come from the fallback source, if any.
+pub struct FileChunkSource {
+    directory: PathBuf,
+    fallback: Option<Box<dyn ChunkSource>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ChunkFile {
+    size: u32,
+    tiles: Vec<Option<ChunkFileTile>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ChunkFileTile {
+    id: u32,
+    #[serde(default)]
+    flags: u8,
+}
+
+impl FileChunkSource {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+            fallback: None,
+        }
+    }
+
+    pub fn with_fallback(
+        mut self,
+        fallback: Box<dyn ChunkSource>,
+    ) -> Self {
+        self.fallback = Some(fallback);
+        self
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    pub fn chunk_path(
+        &self,
+        (x, y): ChunkCoord,
+    ) -> PathBuf {
+        self.directory.join(format!("chunk_{}_{}.json", x, y))
+    }
+}
+
+impl ChunkSource for FileChunkSource {
+    fn load_chunk(
+        &mut self,
+        coord: ChunkCoord,
+        size: u32,
+    ) -> Result<Option<Chunk>, String> {
+        let path = self.chunk_path(coord);
+        if !path.exists() {
+            return match &mut self.fallback {
+                Some(fallback) => fallback.load_chunk(coord, size),
+                None => Ok(None),
+            };
+        }
+
+        let json = fs::read_to_string(&path).map_err(|e| {
+            format!("Failed to read chunk {}: {}", path.display(), e)
+        })?;
+        let file: ChunkFile = serde_json::from_str(&json).map_err(|e| {
+            format!("Failed to parse chunk {}: {}", path.display(), e)
+        })?;
+        if file.size != size {
+            return Err(format!(
+                "Chunk {} has size {}, expected {}",
+                path.display(),
+                file.size,
+                size
+            ));
+        }
+        let tiles = file
+            .tiles
+            .into_iter()
+            .map(|tile| {
+                tile.map(|tile| {
+                    TileInstance::new(tile.id)
+                        .with_flags(TileFlags::from_bits(tile.flags))
+                })
+            })
+            .collect();
+        Chunk::from_tiles(size, tiles).map(Some)
+    }
+
+    fn save_chunk(
+        &mut self,
+        coord: ChunkCoord,
+        chunk: &Chunk,
+    ) -> Result<(), String> {
+        fs::create_dir_all(&self.directory).map_err(|e| {
+            format!(
+                "Failed to create chunk directory {}: {}",
+                self.directory.display(),
+                e
+            )
+        })?;
+        let file = ChunkFile {
+            size: chunk.size(),
+            tiles: chunk
+                .tiles()
+                .iter()
+                .map(|tile| {
+                    tile.map(|tile| ChunkFileTile {
+                        id: tile.id,
+                        flags: tile.flags.bits(),
+                    })
+                })
+                .collect(),
+        };
+        let json = serde_json::to_string(&file)
+            .map_err(|e| format!("Failed to serialize chunk: {}", e))?;
+        let path = self.chunk_path(coord);
+        fs::write(&path, json).map_err(|e| {
+            format!("Failed to write chunk {}: {}", path.display(), e)
+        })
+    }
+}
@@ -0,0 +1,205 @@
+use glam::Vec2;
+
+use super::{TileLayer, TileMap};
+
+/// Health of a damaged tile. Untouched tiles have none and are at the
+/// `health` of their properties.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct TileDamage {
+    /// Tile placed before any damage, whose destruction stages apply.
+    source: u32,
+    health: f32,
+    max_health: f32,
+}
+
+/// Something that happened to a tile while applying damage.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TileDamageEvent {
+    pub layer: String,
+    pub x: u32,
+    pub y: u32,
+    pub kind: TileDamageKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TileDamageKind {
+    /// Health dropped but the tile survived.
+    Damaged { health: f32, max_health: f32 },
+    /// The tile was swapped for the one of a damage stage.
+    Cracked { from: u32, to: u32 },
+    /// Health reached zero. `replacement` is the tile left behind, if any.
+    Destroyed { tile: u32, replacement: Option<u32> },
+}
+
+impl TileLayer {
+    pub fn is_damaged(
+        &self,
+        x: u32,
+        y: u32,
+    ) -> bool {
+        self.damage.contains_key(&(x, y))
+    }
+}
+
+impl TileMap {
+    /// Remaining health of the tile at (`x`, `y`) of `layer`, `None` if
+    /// the cell is empty or the tile has no `health` and cannot be
+    /// destroyed.
+    pub fn tile_health(
+        &self,
+        layer: &str,
+        x: u32,
+        y: u32,
+    ) -> Option<f32> {
+        let layer = self.layers.get(layer)?;
+        if let Some(damage) = layer.damage.get(&(x, y)) {
+            return Some(damage.health);
+        }
+        self.properties_in(layer, x, y)?.gameplay.health
+    }
+
+    /// Take `amount` health off the tile at (`x`, `y`) of `layer`.
+    ///
+    /// Only tiles whose properties have a `health` take damage. As health
+    /// drops the tile is swapped for the stages of its tileset
+    /// `destruction` data, and at zero it is replaced by the destroyed tile
+    /// or removed. Returns what happened, in order.
+    ///
+    /// ```no_run
+    /// use teengine::tile::TileDamageKind;
+    /// # fn example(map: &mut teengine::tile::TileMap) {
+    /// for event in map.damage_tile("walls", 4, 7, 25.0) {
+    ///     if let TileDamageKind::Destroyed { .. } = event.kind {
+    ///         println!("Wall at ({}, {}) collapsed", event.x, event.y);
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn damage_tile(
+        &mut self,
+        layer: &str,
+        x: u32,
+        y: u32,
+        amount: f32,
+    ) -> Vec<TileDamageEvent> {
+        let mut events = Vec::new();
+        self.apply_damage(layer, x, y, amount, &mut events);
+        events
+    }
+
+    /// Damage every tile of `layer` overlapping a world circle, e.g. an
+    /// explosion. Tiles are hit row by row from the top left.
+    pub fn damage_area(
+        &mut self,
+        layer: &str,
+        center: Vec2,
+        radius: f32,
+        amount: f32,
+    ) -> Vec<TileDamageEvent> {
+        let tiles: Vec<_> = self.tiles_in_circle(center, radius).collect();
+        let mut events = Vec::new();
+        for (x, y) in tiles {
+            self.apply_damage(layer, x, y, amount, &mut events);
+        }
+        events
+    }
+
+    fn apply_damage(
+        &mut self,
+        layer_name: &str,
+        x: u32,
+        y: u32,
+        amount: f32,
+        events: &mut Vec<TileDamageEvent>,
+    ) {
+        if amount.is_nan() || amount <= 0.0 {
+            return;
+        }
+        let Some(layer) = self.layers.get(layer_name) else {
+            return;
+        };
+        let Some(tile) = self.tile_ref(layer_name, layer, x, y) else {
+            return;
+        };
+        let current = tile.tile.id;
+        let mut damage = match layer.damage.get(&(x, y)) {
+            Some(damage) => *damage,
+            None => {
+                let Some(max_health) = tile.properties.gameplay.health else {
+                    return;
+                };
+                TileDamage {
+                    source: current,
+                    health: max_health,
+                    max_health,
+                }
+            }
+        };
+        damage.health = (damage.health - amount).max(0.0);
+        let event = |kind| TileDamageEvent {
+            layer: layer_name.to_string(),
+            x,
+            y,
+            kind,
+        };
+
+        if damage.health <= 0.0 {
+            let replacement = self.destroyed_tile(damage.source);
+            let instance = replacement.map(|gid| self.tile_instance(gid));
+            if let Some(layer) = self.layers.get_mut(layer_name) {
+                layer.replace_tile(x, y, instance);
+            }
+            events.push(event(TileDamageKind::Destroyed {
+                tile: current,
+                replacement,
+            }));
+            return;
+        }
+
+        events.push(event(TileDamageKind::Damaged {
+            health: damage.health,
+            max_health: damage.max_health,
+        }));
+        let fraction = damage.health / damage.max_health;
+        let stage = self.damage_stage(damage.source, fraction);
+        let swap = stage
+            .filter(|&gid| gid != current)
+            .map(|gid| self.tile_instance(gid));
+        let Some(layer) = self.layers.get_mut(layer_name) else {
+            return;
+        };
+        if let Some(instance) = swap {
+            let to = instance.id;
+            layer.replace_tile(x, y, Some(instance));
+            events.push(event(TileDamageKind::Cracked { from: current, to }));
+        }
+        layer.damage.insert((x, y), damage);
+    }
+
+    /// Global ID of the deepest damage stage of `source` reached at
+    /// `fraction` of its health.
+    fn damage_stage(
+        &self,
+        source: u32,
+        fraction: f32,
+    ) -> Option<u32> {
+        let (tileset, local_id) = self.resolve_gid(source)?;
+        let first_gid = source - local_id;
+        tileset
+            .destruction(local_id)?
+            .stages
+            .iter()
+            .filter(|stage| fraction <= stage.below)
+            .min_by(|a, b| a.below.total_cmp(&b.below))
+            .map(|stage| first_gid + stage.tile_id)
+    }
+
+    fn destroyed_tile(
+        &self,
+        source: u32,
+    ) -> Option<u32> {
+        let (tileset, local_id) = self.resolve_gid(source)?;
+        let destroyed = tileset.destruction(local_id)?.destroyed_tile?;
+        Some(source - local_id + destroyed)
+    }
+}
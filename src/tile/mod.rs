@@ -1,9 +1,22 @@
+pub mod autotile;
+pub mod chunked;
+pub mod damage;
+pub mod orientation;
+pub mod pathfinding;
+pub mod procgen;
 pub mod properties;
+pub mod query;
 pub mod renderer;
+pub mod tile_shaders;
 pub mod tilemap;
 pub mod tileset;
+pub mod visibility;
 
+pub use autotile::{TerrainRules, TerrainSetData, TerrainType};
+pub use damage::{TileDamageEvent, TileDamageKind};
+pub use orientation::{HexTop, Orientation, StaggerIndex};
 pub use properties::*;
+pub use query::{RaycastHit, TileRef};
 pub use renderer::*;
 pub use tilemap::*;
 pub use tileset::*;
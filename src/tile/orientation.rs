@@ -0,0 +1,285 @@
+use glam::{Mat2, Vec2};
+
+/// How a map's tile grid is laid out on screen, as in Tiled.
+///
+/// Positions returned for a tile are the top left corner of its bounding
+/// box, `size` wide and high. Isometric and staggered tiles are diamonds
+/// inside that box; hexagonal tiles are hexagons whose sides facing the
+/// neighbouring rows (pointy) or columns (flat) are half the box.
+///
+/// ```
+/// use glam::Vec2;
+/// use teengine::tile::{HexTop, Orientation, StaggerIndex};
+///
+/// let size = Vec2::new(64.0, 32.0);
+/// let iso = Orientation::Isometric;
+/// assert_eq!(iso.tile_to_world(1, 0, size), Vec2::new(32.0, 16.0));
+/// assert_eq!(iso.world_to_tile(Vec2::new(64.0, 34.0), size), (1, 0));
+///
+/// let hex = Orientation::Hexagonal(HexTop::Pointy, StaggerIndex::Odd);
+/// let center = hex.tile_center(3, 5, size);
+/// assert_eq!(hex.world_to_tile(center, size), (3, 5));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Orientation {
+    /// Rectangular tiles in rows and columns.
+    #[default]
+    Orthogonal,
+    /// Diamonds: x runs down to the right, y down to the left.
+    Isometric,
+    /// Diamonds in rows offset by half a tile, so the map stays
+    /// rectangular.
+    Staggered(StaggerIndex),
+    /// Hexagons in offset rows (pointy top) or columns (flat top).
+    Hexagonal(HexTop, StaggerIndex),
+}
+
+/// Which rows or columns of a staggered or hexagonal map are shifted by
+/// half a tile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StaggerIndex {
+    Odd,
+    Even,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HexTop {
+    /// A corner on top; rows are staggered horizontally.
+    Pointy,
+    /// An edge on top; columns are staggered vertically.
+    Flat,
+}
+
+impl StaggerIndex {
+    fn is_shifted(
+        self,
+        index: i32,
+    ) -> bool {
+        let odd = index.rem_euclid(2) == 1;
+        match self {
+            StaggerIndex::Odd => odd,
+            StaggerIndex::Even => !odd,
+        }
+    }
+}
+
+impl Orientation {
+    /// Top left of the bounding box of tile (`x`, `y`).
+    pub fn tile_to_world(
+        self,
+        x: i32,
+        y: i32,
+        size: Vec2,
+    ) -> Vec2 {
+        let (xf, yf) = (x as f32, y as f32);
+        let shift = |stagger: StaggerIndex, index: i32| {
+            if stagger.is_shifted(index) {
+                0.5
+            } else {
+                0.0
+            }
+        };
+        match self {
+            Orientation::Orthogonal => Vec2::new(xf, yf) * size,
+            Orientation::Isometric => Vec2::new(xf - yf, xf + yf) * size * 0.5,
+            Orientation::Staggered(stagger) => {
+                Vec2::new((xf + shift(stagger, y)) * size.x, yf * size.y * 0.5)
+            }
+            Orientation::Hexagonal(HexTop::Pointy, stagger) => {
+                Vec2::new((xf + shift(stagger, y)) * size.x, yf * size.y * 0.75)
+            }
+            Orientation::Hexagonal(HexTop::Flat, stagger) => {
+                Vec2::new(xf * size.x * 0.75, (yf + shift(stagger, x)) * size.y)
+            }
+        }
+    }
+
+    /// Center of tile (`x`, `y`).
+    pub fn tile_center(
+        self,
+        x: i32,
+        y: i32,
+        size: Vec2,
+    ) -> Vec2 {
+        self.tile_to_world(x, y, size) + size * 0.5
+    }
+
+    /// Corners of the shape of tile (`x`, `y`), clockwise on screen.
+    pub fn tile_outline(
+        self,
+        x: i32,
+        y: i32,
+        size: Vec2,
+    ) -> Vec<Vec2> {
+        let origin = self.tile_to_world(x, y, size);
+        let corners: &[(f32, f32)] = match self {
+            Orientation::Orthogonal => {
+                &[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]
+            }
+            Orientation::Isometric | Orientation::Staggered(_) => {
+                &[(0.5, 0.0), (1.0, 0.5), (0.5, 1.0), (0.0, 0.5)]
+            }
+            Orientation::Hexagonal(HexTop::Pointy, _) => &[
+                (0.5, 0.0),
+                (1.0, 0.25),
+                (1.0, 0.75),
+                (0.5, 1.0),
+                (0.0, 0.75),
+                (0.0, 0.25),
+            ],
+            Orientation::Hexagonal(HexTop::Flat, _) => &[
+                (0.25, 0.0),
+                (0.75, 0.0),
+                (1.0, 0.5),
+                (0.75, 1.0),
+                (0.25, 1.0),
+                (0.0, 0.5),
+            ],
+        };
+        corners
+            .iter()
+            .map(|&(cx, cy)| origin + Vec2::new(cx, cy) * size)
+            .collect()
+    }
+
+    /// Affine map from world positions to continuous tile coordinates,
+    /// `grid = matrix * world + offset`, in which tile (x, y) covers x to
+    /// x + 1 and y to y + 1. Only orthogonal and isometric grids are such
+    /// an image of world space; staggered and hexagonal rows are not.
+    pub(crate) fn grid_transform(
+        self,
+        size: Vec2,
+    ) -> Option<(Mat2, Vec2)> {
+        let inverse = size.recip();
+        match self {
+            Orientation::Orthogonal => {
+                Some((Mat2::from_diagonal(inverse), Vec2::ZERO))
+            }
+            Orientation::Isometric => Some((
+                Mat2::from_cols(
+                    Vec2::new(inverse.x, -inverse.x),
+                    Vec2::new(inverse.y, inverse.y),
+                ),
+                Vec2::new(-0.5, 0.5),
+            )),
+            _ => None,
+        }
+    }
+
+    /// Tile whose shape contains `position`.
+    pub fn world_to_tile(
+        self,
+        position: Vec2,
+        size: Vec2,
+    ) -> (i32, i32) {
+        let cell = |value: f32| value.floor() as i32;
+        let (estimate_x, estimate_y) = match self {
+            Orientation::Orthogonal => {
+                let tile = position / size;
+                return (cell(tile.x), cell(tile.y));
+            }
+            Orientation::Isometric => {
+                // Offset from the top corner of tile (0, 0), in half tiles
+                let half =
+                    (position - Vec2::new(size.x * 0.5, 0.0)) / (size * 0.5);
+                return (
+                    cell((half.x + half.y) * 0.5),
+                    cell((half.y - half.x) * 0.5),
+                );
+            }
+            Orientation::Staggered(_) => {
+                (cell(position.x / size.x), cell(position.y / (size.y * 0.5)))
+            }
+            Orientation::Hexagonal(HexTop::Pointy, _) => (
+                cell(position.x / size.x),
+                cell(position.y / (size.y * 0.75)),
+            ),
+            Orientation::Hexagonal(HexTop::Flat, _) => (
+                cell(position.x / (size.x * 0.75)),
+                cell(position.y / size.y),
+            ),
+        };
+
+        // The tile is the estimate or one of its neighbours: the one
+        // whose shape, scaled around its center, reaches `position` first
+        let mut best = (estimate_x, estimate_y);
+        let mut best_distance = f32::INFINITY;
+        for y in estimate_y - 1..=estimate_y + 1 {
+            for x in estimate_x - 1..=estimate_x + 1 {
+                let offset = position - self.tile_center(x, y, size);
+                let distance = self.shape_distance(offset, size);
+                if distance < best_distance {
+                    best = (x, y);
+                    best_distance = distance;
+                }
+            }
+        }
+        best
+    }
+
+    /// How far `offset` from a tile center is in units of the tile shape:
+    /// 1 on its outline.
+    fn shape_distance(
+        self,
+        offset: Vec2,
+        size: Vec2,
+    ) -> f32 {
+        let d = offset.abs() / size;
+        match self {
+            Orientation::Orthogonal => d.x.max(d.y) * 2.0,
+            Orientation::Isometric | Orientation::Staggered(_) => {
+                (d.x + d.y) * 2.0
+            }
+            Orientation::Hexagonal(HexTop::Pointy, _) => {
+                (d.x * 2.0).max(d.x + d.y * 2.0)
+            }
+            Orientation::Hexagonal(HexTop::Flat, _) => {
+                (d.y * 2.0).max(d.y + d.x * 2.0)
+            }
+        }
+    }
+
+    /// Tiles of a `width` by `height` map ordered back to front, so tiles
+    /// taller than the grid overlap the ones behind them correctly.
+    pub fn draw_order(
+        self,
+        width: u32,
+        height: u32,
+    ) -> Vec<(u32, u32)> {
+        let mut order = Vec::with_capacity(width as usize * height as usize);
+        if width == 0 || height == 0 {
+            return order;
+        }
+        match self {
+            Orientation::Isometric => {
+                // Diagonal by diagonal from the top corner
+                for depth in 0..width + height - 1 {
+                    let first_x = depth.saturating_sub(height - 1);
+                    for x in first_x..=depth.min(width - 1) {
+                        order.push((x, depth - x));
+                    }
+                }
+            }
+            Orientation::Hexagonal(HexTop::Flat, stagger) => {
+                // Raised columns of a row come before the shifted ones
+                for y in 0..height {
+                    for shifted in [false, true] {
+                        for x in 0..width {
+                            if stagger.is_shifted(x as i32) == shifted {
+                                order.push((x, y));
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {
+                for y in 0..height {
+                    for x in 0..width {
+                        order.push((x, y));
+                    }
+                }
+            }
+        }
+        order
+    }
+}
@@ -0,0 +1,171 @@
+use glam::Vec2;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use super::{Movement, PathGrid};
+use crate::tile::TileMap;
+
+/// Tiles from start to goal, both included.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path {
+    pub tiles: Vec<(u32, u32)>,
+    /// Sum of the step costs.
+    pub cost: f32,
+}
+
+impl Path {
+    /// Centers of the path's tiles in world units.
+    pub fn world_points(
+        &self,
+        tilemap: &TileMap,
+    ) -> Vec<Vec2> {
+        self.tiles
+            .iter()
+            .map(|&(x, y)| tilemap.tile_center(x as i32, y as i32))
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+}
+
+/// Open list entry, ordered so the binary heap pops the lowest estimate.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct OpenNode {
+    pub estimate: f32,
+    pub cost: f32,
+    pub index: usize,
+}
+
+impl PartialEq for OpenNode {
+    fn eq(
+        &self,
+        other: &Self,
+    ) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for OpenNode {}
+
+impl PartialOrd for OpenNode {
+    fn partial_cmp(
+        &self,
+        other: &Self,
+    ) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OpenNode {
+    fn cmp(
+        &self,
+        other: &Self,
+    ) -> Ordering {
+        // Reverse for a min-heap; prefer deeper nodes on ties
+        other
+            .estimate
+            .total_cmp(&self.estimate)
+            .then(self.cost.total_cmp(&other.cost))
+            .then(other.index.cmp(&self.index))
+    }
+}
+
+/// Best first search shared by A* and jump point search. `successors`
+/// yields the nodes reachable from a node with the cost to get there.
+pub(crate) fn search<I>(
+    grid: &PathGrid,
+    start: (u32, u32),
+    goal: (u32, u32),
+    movement: Movement,
+    mut successors: impl FnMut((i64, i64), Option<(i64, i64)>) -> I,
+) -> Option<Vec<((i64, i64), f32)>>
+where
+    I: Iterator<Item = ((i64, i64), f32)>,
+{
+    let width = grid.width() as usize;
+    let start = (start.0 as i64, start.1 as i64);
+    let goal = (goal.0 as i64, goal.1 as i64);
+    if !grid.is_walkable(start.0, start.1) || !grid.is_walkable(goal.0, goal.1)
+    {
+        return None;
+    }
+
+    let index = |(x, y): (i64, i64)| y as usize * width + x as usize;
+    let position =
+        |index: usize| ((index % width) as i64, (index / width) as i64);
+    let cells = width * grid.height() as usize;
+    let mut costs = vec![f32::INFINITY; cells];
+    let mut parents = vec![usize::MAX; cells];
+    let mut closed = vec![false; cells];
+    let mut open = BinaryHeap::new();
+
+    costs[index(start)] = 0.0;
+    open.push(OpenNode {
+        estimate: grid.heuristic(start, goal, movement),
+        cost: 0.0,
+        index: index(start),
+    });
+
+    while let Some(node) = open.pop() {
+        if closed[node.index] {
+            continue;
+        }
+        closed[node.index] = true;
+        let current = position(node.index);
+        if current == goal {
+            let mut nodes = vec![(current, costs[node.index])];
+            let mut at = node.index;
+            while parents[at] != usize::MAX {
+                at = parents[at];
+                nodes.push((position(at), costs[at]));
+            }
+            nodes.reverse();
+            return Some(nodes);
+        }
+
+        let parent = (parents[node.index] != usize::MAX)
+            .then(|| position(parents[node.index]));
+        for (next, step) in successors(current, parent) {
+            let next_index = index(next);
+            let cost = costs[node.index] + step;
+            if closed[next_index] || cost >= costs[next_index] {
+                continue;
+            }
+            costs[next_index] = cost;
+            parents[next_index] = node.index;
+            open.push(OpenNode {
+                estimate: cost + grid.heuristic(next, goal, movement),
+                cost,
+                index: next_index,
+            });
+        }
+    }
+    None
+}
+
+impl PathGrid {
+    /// Cheapest path with A*, or `None` if the goal is unreachable.
+    pub fn find_path(
+        &self,
+        start: (u32, u32),
+        goal: (u32, u32),
+        movement: Movement,
+    ) -> Option<Path> {
+        let nodes = search(self, start, goal, movement, |(x, y), _| {
+            self.neighbours(x, y, movement)
+        })?;
+        Some(Path {
+            cost: nodes.last().map_or(0.0, |&(_, cost)| cost),
+            tiles: nodes
+                .into_iter()
+                .map(|((x, y), _)| (x as u32, y as u32))
+                .collect(),
+        })
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+
+use super::{CornerCutting, FlowField, Movement, Path, PathGrid};
+use crate::tile::TileMap;
+
+const DEFAULT_CACHE_SIZE: usize = 256;
+
+// Start and goal tile
+type PathKey = ((u32, u32), (u32, u32));
+
+/// Path queries against a `TileMap` with cached results.
+///
+/// The cost grid is rebuilt and every cached path and flow field dropped
+/// as soon as [`TileMap::revision`] changes, i.e. when tiles are set or
+/// cleared through the layer methods.
+///
+/// ```no_run
+/// use teengine::tile::pathfinding::{CornerCutting, Movement, PathFinder};
+/// # fn example(map: &teengine::tile::TileMap) {
+/// let mut finder =
+///     PathFinder::new(Movement::EightWay(CornerCutting::Never))
+///         .with_jump_point_search(true);
+/// if let Some(path) = finder.find_path(map, (1, 1), (18, 12)) {
+///     let waypoints = path.world_points(map);
+/// }
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct PathFinder {
+    movement: Movement,
+    jump_point_search: bool,
+    cache_size: usize,
+    grid: Option<PathGrid>,
+    revision: u64,
+    paths: HashMap<PathKey, Option<Path>>,
+    flow_fields: HashMap<Vec<(u32, u32)>, FlowField>,
+}
+
+impl PathFinder {
+    pub fn new(movement: Movement) -> Self {
+        Self {
+            movement,
+            jump_point_search: false,
+            cache_size: DEFAULT_CACHE_SIZE,
+            grid: None,
+            revision: 0,
+            paths: HashMap::new(),
+            flow_fields: HashMap::new(),
+        }
+    }
+
+    /// Use jump point search where it applies: eight way movement without
+    /// corner cutting on maps whose open tiles all cost the same.
+    pub fn with_jump_point_search(
+        mut self,
+        enabled: bool,
+    ) -> Self {
+        self.jump_point_search = enabled;
+        self
+    }
+
+    /// Most paths kept before the cache is emptied. Zero disables caching.
+    pub fn with_cache_size(
+        mut self,
+        cache_size: usize,
+    ) -> Self {
+        self.cache_size = cache_size;
+        self
+    }
+
+    pub fn movement(&self) -> Movement {
+        self.movement
+    }
+
+    /// Rebuild the cost grid if the map changed since the last query.
+    /// Returns true if it was rebuilt.
+    pub fn update(
+        &mut self,
+        tilemap: &TileMap,
+    ) -> bool {
+        if self.grid.is_some() && self.revision == tilemap.revision() {
+            return false;
+        }
+        self.grid = Some(PathGrid::from_tilemap(tilemap));
+        self.revision = tilemap.revision();
+        self.invalidate();
+        true
+    }
+
+    /// Drop all cached paths and flow fields.
+    pub fn invalidate(&mut self) {
+        self.paths.clear();
+        self.flow_fields.clear();
+    }
+
+    /// Cost grid of the last map seen by [`PathFinder::update`].
+    pub fn grid(&self) -> Option<&PathGrid> {
+        self.grid.as_ref()
+    }
+
+    pub fn find_path(
+        &mut self,
+        tilemap: &TileMap,
+        start: (u32, u32),
+        goal: (u32, u32),
+    ) -> Option<Path> {
+        self.update(tilemap);
+        if let Some(cached) = self.paths.get(&(start, goal)) {
+            return cached.clone();
+        }
+
+        let grid = self.grid.as_ref()?;
+        let use_jps = self.jump_point_search
+            && self.movement == Movement::EightWay(CornerCutting::Never);
+        let path = if use_jps {
+            grid.find_path_jps(start, goal)
+        } else {
+            grid.find_path(start, goal, self.movement)
+        };
+
+        if self.cache_size > 0 {
+            if self.paths.len() >= self.cache_size {
+                self.paths.clear();
+            }
+            self.paths.insert((start, goal), path.clone());
+        }
+        path
+    }
+
+    /// Flow field towards `goals`, cached until the map changes. With a
+    /// cache size of zero it is recomputed on every call and only the
+    /// returned field is kept.
+    pub fn flow_field(
+        &mut self,
+        tilemap: &TileMap,
+        goals: &[(u32, u32)],
+    ) -> Option<&FlowField> {
+        self.update(tilemap);
+        let grid = self.grid.as_ref()?;
+        if self.cache_size == 0
+            || (!self.flow_fields.contains_key(goals)
+                && self.flow_fields.len() >= self.cache_size)
+        {
+            self.flow_fields.clear();
+        }
+        Some(
+            self.flow_fields
+                .entry(goals.to_vec())
+                .or_insert_with(|| grid.flow_field(goals, self.movement)),
+        )
+    }
+}
@@ -0,0 +1,159 @@
+use std::collections::BinaryHeap;
+
+use super::astar::OpenNode;
+use super::{Movement, Path, PathGrid};
+
+/// Cost to reach the nearest goal from every tile, built with Dijkstra's
+/// algorithm. One field steers any number of units towards the same
+/// goals, which is cheaper than a path per unit.
+#[derive(Debug, Clone)]
+pub struct FlowField {
+    width: u32,
+    height: u32,
+    movement: Movement,
+    costs: Vec<f32>,
+    next: Vec<Option<(u32, u32)>>,
+}
+
+impl FlowField {
+    pub fn new(
+        grid: &PathGrid,
+        goals: &[(u32, u32)],
+        movement: Movement,
+    ) -> Self {
+        let width = grid.width() as usize;
+        let cells = width * grid.height() as usize;
+        let index = |(x, y): (i64, i64)| y as usize * width + x as usize;
+        let mut costs = vec![f32::INFINITY; cells];
+        let mut next = vec![None; cells];
+        let mut open = BinaryHeap::new();
+
+        for &(x, y) in goals {
+            let goal = (x as i64, y as i64);
+            if grid.is_walkable(goal.0, goal.1) {
+                costs[index(goal)] = 0.0;
+                open.push(OpenNode {
+                    estimate: 0.0,
+                    cost: 0.0,
+                    index: index(goal),
+                });
+            }
+        }
+
+        while let Some(node) = open.pop() {
+            if node.cost > costs[node.index] {
+                continue;
+            }
+            let current =
+                ((node.index % width) as i64, (node.index / width) as i64);
+            let Some(enter_cost) = grid.cost(current.0, current.1) else {
+                continue;
+            };
+            // Relax the neighbours that can step into `current`. Steps are
+            // symmetric, so the neighbours of `current` are exactly those.
+            for ((nx, ny), step) in
+                grid.neighbours(current.0, current.1, movement)
+            {
+                // `step` is the cost of entering the neighbour; scale it to
+                // entering `current` instead
+                let length = step / grid.cost(nx, ny).unwrap_or(1.0);
+                let cost = node.cost + enter_cost * length;
+                let neighbour = index((nx, ny));
+                if cost < costs[neighbour] {
+                    costs[neighbour] = cost;
+                    next[neighbour] =
+                        Some((current.0 as u32, current.1 as u32));
+                    open.push(OpenNode {
+                        estimate: cost,
+                        cost,
+                        index: neighbour,
+                    });
+                }
+            }
+        }
+
+        Self {
+            width: grid.width(),
+            height: grid.height(),
+            movement,
+            costs,
+            next,
+        }
+    }
+
+    pub fn movement(&self) -> Movement {
+        self.movement
+    }
+
+    /// Cost from (`x`, `y`) to the nearest goal, `None` if unreachable.
+    pub fn cost(
+        &self,
+        x: u32,
+        y: u32,
+    ) -> Option<f32> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let cost = self.costs[self.index(x, y)];
+        cost.is_finite().then_some(cost)
+    }
+
+    /// Tile to step to from (`x`, `y`), `None` at a goal or when no goal
+    /// is reachable.
+    pub fn next_step(
+        &self,
+        x: u32,
+        y: u32,
+    ) -> Option<(u32, u32)> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        self.next[self.index(x, y)]
+    }
+
+    /// Unit direction to move in from (`x`, `y`), in tile axes.
+    pub fn direction(
+        &self,
+        x: u32,
+        y: u32,
+    ) -> Option<glam::Vec2> {
+        let (nx, ny) = self.next_step(x, y)?;
+        Some(
+            glam::Vec2::new(nx as f32 - x as f32, ny as f32 - y as f32)
+                .normalize(),
+        )
+    }
+
+    /// Follow the field from `start` to the nearest goal.
+    pub fn path_from(
+        &self,
+        start: (u32, u32),
+    ) -> Option<Path> {
+        let cost = self.cost(start.0, start.1)?;
+        let mut tiles = vec![start];
+        let mut at = start;
+        while let Some(step) = self.next_step(at.0, at.1) {
+            tiles.push(step);
+            at = step;
+        }
+        Some(Path { tiles, cost })
+    }
+
+    fn index(
+        &self,
+        x: u32,
+        y: u32,
+    ) -> usize {
+        y as usize * self.width as usize + x as usize
+    }
+}
+
+impl PathGrid {
+    pub fn flow_field(
+        &self,
+        goals: &[(u32, u32)],
+        movement: Movement,
+    ) -> FlowField {
+        FlowField::new(self, goals, movement)
+    }
+}
@@ -0,0 +1,211 @@
+use crate::tile::procgen::Grid;
+use crate::tile::TileMap;
+
+/// Which neighbours a path may step to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Movement {
+    /// Up, down, left and right.
+    FourWay,
+    /// Also diagonally, following the corner rule.
+    EightWay(CornerCutting),
+}
+
+/// When a diagonal step may pass a blocked orthogonal neighbour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CornerCutting {
+    /// Diagonal steps ignore the orthogonal neighbours.
+    Allow,
+    /// At least one of the two orthogonal neighbours must be open.
+    IfOneOpen,
+    /// Both orthogonal neighbours must be open, so units never clip
+    /// corners.
+    Never,
+}
+
+// Orthogonal steps first, then diagonals
+const STEPS: [(i64, i64); 8] = [
+    (0, -1),
+    (1, 0),
+    (0, 1),
+    (-1, 0),
+    (1, -1),
+    (1, 1),
+    (-1, 1),
+    (-1, -1),
+];
+
+/// Traversal cost of every tile of a map; `None` is blocked.
+///
+/// A cell is blocked if any layer has a collidable tile there. Otherwise
+/// entering it costs `1 / movement_modifier` of the slowest tile on it, so
+/// a modifier of 0.5 makes a tile twice as expensive and 0 blocks it.
+/// Diagonal steps cost √2 times as much.
+///
+/// ```
+/// use teengine::tile::pathfinding::{CornerCutting, Movement, PathGrid};
+/// use teengine::tile::procgen::Grid;
+///
+/// // A wall with a gap at the bottom
+/// let mut costs = Grid::new(5, 5, Some(1.0));
+/// for y in 0..4 {
+///     costs.set(2, y, None);
+/// }
+/// let grid = PathGrid::new(costs);
+///
+/// let path = grid.find_path((0, 0), (4, 0), Movement::FourWay).unwrap();
+/// assert_eq!(path.cost, 12.0);
+///
+/// let eight_way = Movement::EightWay(CornerCutting::Never);
+/// let astar = grid.find_path((0, 0), (4, 0), eight_way).unwrap();
+/// let jps = grid.find_path_jps((0, 0), (4, 0)).unwrap();
+/// assert_eq!(astar.cost, jps.cost);
+/// assert_eq!(jps.tiles.len(), astar.tiles.len());
+/// ```
+#[derive(Debug, Clone)]
+pub struct PathGrid {
+    costs: Grid<Option<f32>>,
+    min_cost: f32,
+    uniform: bool,
+}
+
+impl PathGrid {
+    pub fn new(costs: Grid<Option<f32>>) -> Self {
+        let mut open = costs.cells().iter().flatten();
+        let first = open.next().copied();
+        let (mut min_cost, mut uniform) = (first.unwrap_or(1.0), true);
+        for &cost in open {
+            uniform &= Some(cost) == first;
+            min_cost = min_cost.min(cost);
+        }
+        Self {
+            costs,
+            min_cost,
+            uniform,
+        }
+    }
+
+    pub fn from_tilemap(tilemap: &TileMap) -> Self {
+        let mut costs = Grid::new(tilemap.width, tilemap.height, Some(1.0));
+        for y in 0..tilemap.height {
+            for x in 0..tilemap.width {
+                let mut cost = Some(1.0_f32);
+                for layer in tilemap.layers.values() {
+                    let Some(properties) = tilemap.properties_in(layer, x, y)
+                    else {
+                        continue;
+                    };
+                    let modifier = properties.gameplay.movement_modifier;
+                    if properties.is_collidable() || modifier <= 0.0 {
+                        cost = None;
+                        break;
+                    }
+                    cost = cost.map(|cost| cost.max(1.0 / modifier));
+                }
+                costs.set(x, y, cost);
+            }
+        }
+        Self::new(costs)
+    }
+
+    pub fn width(&self) -> u32 {
+        self.costs.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.costs.height()
+    }
+
+    /// Cost of entering (`x`, `y`), `None` if it is blocked or outside.
+    pub fn cost(
+        &self,
+        x: i64,
+        y: i64,
+    ) -> Option<f32> {
+        if !self.costs.in_bounds(x, y) {
+            return None;
+        }
+        *self.costs.get(x as u32, y as u32)?
+    }
+
+    pub fn is_walkable(
+        &self,
+        x: i64,
+        y: i64,
+    ) -> bool {
+        self.cost(x, y).is_some()
+    }
+
+    /// Cheapest cost of any open cell, used to keep heuristics admissible.
+    pub fn min_cost(&self) -> f32 {
+        self.min_cost
+    }
+
+    /// Whether every open cell costs the same, which jump point search
+    /// needs.
+    pub fn is_uniform(&self) -> bool {
+        self.uniform
+    }
+
+    /// Steps allowed from (`x`, `y`) with the cost of each.
+    pub(crate) fn neighbours(
+        &self,
+        x: i64,
+        y: i64,
+        movement: Movement,
+    ) -> impl Iterator<Item = ((i64, i64), f32)> + '_ {
+        let count = match movement {
+            Movement::FourWay => 4,
+            Movement::EightWay(_) => 8,
+        };
+        STEPS[..count].iter().filter_map(move |&(dx, dy)| {
+            let (nx, ny) = (x + dx, y + dy);
+            let cost = self.cost(nx, ny)?;
+            if dx != 0 && dy != 0 {
+                let rule = match movement {
+                    Movement::EightWay(rule) => rule,
+                    Movement::FourWay => return None,
+                };
+                if !self.can_cut(x, y, dx, dy, rule) {
+                    return None;
+                }
+                return Some(((nx, ny), cost * std::f32::consts::SQRT_2));
+            }
+            Some(((nx, ny), cost))
+        })
+    }
+
+    fn can_cut(
+        &self,
+        x: i64,
+        y: i64,
+        dx: i64,
+        dy: i64,
+        rule: CornerCutting,
+    ) -> bool {
+        let horizontal = self.is_walkable(x + dx, y);
+        let vertical = self.is_walkable(x, y + dy);
+        match rule {
+            CornerCutting::Allow => true,
+            CornerCutting::IfOneOpen => horizontal || vertical,
+            CornerCutting::Never => horizontal && vertical,
+        }
+    }
+
+    /// Admissible estimate of the cost from `a` to `b`.
+    pub(crate) fn heuristic(
+        &self,
+        a: (i64, i64),
+        b: (i64, i64),
+        movement: Movement,
+    ) -> f32 {
+        let dx = (a.0 - b.0).abs() as f32;
+        let dy = (a.1 - b.1).abs() as f32;
+        let distance = match movement {
+            Movement::FourWay => dx + dy,
+            Movement::EightWay(_) => {
+                dx.max(dy) + (std::f32::consts::SQRT_2 - 1.0) * dx.min(dy)
+            }
+        };
+        distance * self.min_cost
+    }
+}
@@ -0,0 +1,163 @@
+use super::astar::search;
+use super::{CornerCutting, Movement, Path, PathGrid};
+
+const MOVEMENT: Movement = Movement::EightWay(CornerCutting::Never);
+
+impl PathGrid {
+    /// Path with jump point search, which skips over the open stretches
+    /// A* would expand tile by tile. It finds the same cost as
+    /// [`PathGrid::find_path`] but only applies to eight way movement
+    /// without corner cutting on a grid of uniform cost; other grids fall
+    /// back to A*.
+    pub fn find_path_jps(
+        &self,
+        start: (u32, u32),
+        goal: (u32, u32),
+    ) -> Option<Path> {
+        if !self.is_uniform() {
+            return self.find_path(start, goal, MOVEMENT);
+        }
+
+        let target = (goal.0 as i64, goal.1 as i64);
+        let jump_points =
+            search(self, start, goal, MOVEMENT, |current, parent| {
+                self.pruned_directions(current, parent)
+                    .into_iter()
+                    .filter_map(move |(dx, dy)| {
+                        let point = self.jump(current, dx, dy, target)?;
+                        // Runs are straight or diagonal, so the octile
+                        // distance is exact on a uniform grid
+                        let cost = self.heuristic(current, point, MOVEMENT);
+                        Some((point, cost))
+                    })
+            })?;
+
+        // Fill in the straight and diagonal runs between jump points
+        let mut tiles = vec![start];
+        for pair in jump_points.windows(2) {
+            let ((mut x, mut y), _) = pair[0];
+            let ((to_x, to_y), _) = pair[1];
+            while (x, y) != (to_x, to_y) {
+                x += (to_x - x).signum();
+                y += (to_y - y).signum();
+                tiles.push((x as u32, y as u32));
+            }
+        }
+        Some(Path {
+            tiles,
+            cost: jump_points.last().map_or(0.0, |&(_, cost)| cost),
+        })
+    }
+
+    /// Directions worth exploring from `current` given the direction it
+    /// was entered from.
+    fn pruned_directions(
+        &self,
+        (x, y): (i64, i64),
+        parent: Option<(i64, i64)>,
+    ) -> Vec<(i64, i64)> {
+        let Some((px, py)) = parent else {
+            return self
+                .neighbours(x, y, MOVEMENT)
+                .map(|((nx, ny), _)| (nx - x, ny - y))
+                .collect();
+        };
+
+        let dx = (x - px).signum();
+        let dy = (y - py).signum();
+        let open = |ox: i64, oy: i64| self.is_walkable(x + ox, y + oy);
+        let mut directions = Vec::with_capacity(4);
+        if dx != 0 && dy != 0 {
+            if open(0, dy) {
+                directions.push((0, dy));
+            }
+            if open(dx, 0) {
+                directions.push((dx, 0));
+            }
+            if open(0, dy) && open(dx, 0) && open(dx, dy) {
+                directions.push((dx, dy));
+            }
+        } else if dx != 0 {
+            let (next, down, up) = (open(dx, 0), open(0, 1), open(0, -1));
+            if next {
+                directions.push((dx, 0));
+                if down && open(dx, 1) {
+                    directions.push((dx, 1));
+                }
+                if up && open(dx, -1) {
+                    directions.push((dx, -1));
+                }
+            }
+            if down {
+                directions.push((0, 1));
+            }
+            if up {
+                directions.push((0, -1));
+            }
+        } else {
+            let (next, right, left) = (open(0, dy), open(1, 0), open(-1, 0));
+            if next {
+                directions.push((0, dy));
+                if right && open(1, dy) {
+                    directions.push((1, dy));
+                }
+                if left && open(-1, dy) {
+                    directions.push((-1, dy));
+                }
+            }
+            if right {
+                directions.push((1, 0));
+            }
+            if left {
+                directions.push((-1, 0));
+            }
+        }
+        directions
+    }
+
+    /// Walk from `from` in direction (`dx`, `dy`) until reaching the goal,
+    /// a tile with a forced neighbour, or a wall.
+    fn jump(
+        &self,
+        from: (i64, i64),
+        dx: i64,
+        dy: i64,
+        goal: (i64, i64),
+    ) -> Option<(i64, i64)> {
+        let (mut x, mut y) = (from.0 + dx, from.1 + dy);
+        loop {
+            if !self.is_walkable(x, y) {
+                return None;
+            }
+            if (x, y) == goal {
+                return Some((x, y));
+            }
+
+            let open = |ox: i64, oy: i64| self.is_walkable(x + ox, y + oy);
+            if dx != 0 && dy != 0 {
+                if self.jump((x, y), dx, 0, goal).is_some()
+                    || self.jump((x, y), 0, dy, goal).is_some()
+                {
+                    return Some((x, y));
+                }
+            } else if dx != 0 {
+                if (open(0, -1) && !open(-dx, -1))
+                    || (open(0, 1) && !open(-dx, 1))
+                {
+                    return Some((x, y));
+                }
+            } else if (open(-1, 0) && !open(-1, -dy))
+                || (open(1, 0) && !open(1, -dy))
+            {
+                return Some((x, y));
+            }
+
+            // Diagonal runs may not squeeze past corners
+            if !(open(dx, 0) && open(0, dy)) {
+                return None;
+            }
+            x += dx;
+            y += dy;
+        }
+    }
+}
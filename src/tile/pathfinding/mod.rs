@@ -0,0 +1,16 @@
+//! Grid path finding over tile maps.
+//!
+//! [`PathGrid`] turns a map into traversal costs. Query it directly with
+//! A*, jump point search or flow fields, or go through [`PathFinder`],
+//! which caches results until the map's tiles change.
+
+pub mod astar;
+pub mod finder;
+pub mod flow_field;
+pub mod grid;
+mod jps;
+
+pub use astar::Path;
+pub use finder::PathFinder;
+pub use flow_field::FlowField;
+pub use grid::{CornerCutting, Movement, PathGrid};
@@ -0,0 +1,246 @@
+use super::Grid;
+use crate::random::Random;
+
+/// Rectangular room carved by [`BspGenerator`], in tiles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Room {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Room {
+    pub fn center(&self) -> (u32, u32) {
+        (self.x + self.width / 2, self.y + self.height / 2)
+    }
+
+    pub fn contains(
+        &self,
+        x: u32,
+        y: u32,
+    ) -> bool {
+        x >= self.x
+            && y >= self.y
+            && x < self.x + self.width
+            && y < self.y + self.height
+    }
+}
+
+/// Output of [`BspGenerator::generate`].
+#[derive(Debug, Clone)]
+pub struct Dungeon {
+    /// `true` cells are floor.
+    pub grid: Grid<bool>,
+    /// Rooms in the order they were carved.
+    pub rooms: Vec<Room>,
+}
+
+/// Rooms and corridors from binary space partitioning.
+///
+/// The map is split recursively into leaves no smaller than
+/// `min_leaf_size`. Each leaf gets a room with at least one tile of wall
+/// around it, and sibling subtrees are joined by L-shaped corridors, so
+/// every room is reachable.
+#[derive(Debug, Clone)]
+pub struct BspGenerator {
+    min_leaf_size: u32,
+    min_room_size: u32,
+    max_depth: u32,
+}
+
+impl Default for BspGenerator {
+    fn default() -> Self {
+        Self {
+            min_leaf_size: 8,
+            min_room_size: 4,
+            max_depth: 6,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Leaf {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+impl BspGenerator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_min_leaf_size(
+        mut self,
+        size: u32,
+    ) -> Self {
+        self.min_leaf_size = size.max(3);
+        self
+    }
+
+    pub fn with_min_room_size(
+        mut self,
+        size: u32,
+    ) -> Self {
+        self.min_room_size = size.max(1);
+        self
+    }
+
+    pub fn with_max_depth(
+        mut self,
+        depth: u32,
+    ) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    pub fn generate(
+        &self,
+        width: u32,
+        height: u32,
+        seed: u64,
+    ) -> Dungeon {
+        let mut random = Random::new(seed);
+        let mut dungeon = Dungeon {
+            grid: Grid::new(width, height, false),
+            rooms: Vec::new(),
+        };
+        let root = Leaf {
+            x: 0,
+            y: 0,
+            width,
+            height,
+        };
+        self.split(root, 0, &mut random, &mut dungeon);
+        dungeon
+    }
+
+    /// Fill `leaf` with rooms and return the one to connect it through.
+    fn split(
+        &self,
+        leaf: Leaf,
+        depth: u32,
+        random: &mut Random,
+        dungeon: &mut Dungeon,
+    ) -> Option<Room> {
+        let can_split_x = leaf.width >= self.min_leaf_size * 2;
+        let can_split_y = leaf.height >= self.min_leaf_size * 2;
+        if depth >= self.max_depth || (!can_split_x && !can_split_y) {
+            return self.carve_room(leaf, random, dungeon);
+        }
+
+        let split_x = if can_split_x && can_split_y {
+            // Prefer cutting across the longer side
+            if leaf.width * 4 > leaf.height * 5 {
+                true
+            } else if leaf.height * 4 > leaf.width * 5 {
+                false
+            } else {
+                random.chance(0.5)
+            }
+        } else {
+            can_split_x
+        };
+
+        let (first, second) = if split_x {
+            let at = random.range_i32(
+                self.min_leaf_size as i32,
+                (leaf.width - self.min_leaf_size) as i32,
+            ) as u32;
+            (
+                Leaf { width: at, ..leaf },
+                Leaf {
+                    x: leaf.x + at,
+                    width: leaf.width - at,
+                    ..leaf
+                },
+            )
+        } else {
+            let at = random.range_i32(
+                self.min_leaf_size as i32,
+                (leaf.height - self.min_leaf_size) as i32,
+            ) as u32;
+            (
+                Leaf { height: at, ..leaf },
+                Leaf {
+                    y: leaf.y + at,
+                    height: leaf.height - at,
+                    ..leaf
+                },
+            )
+        };
+
+        let first = self.split(first, depth + 1, random, dungeon);
+        let second = self.split(second, depth + 1, random, dungeon);
+        match (first, second) {
+            (Some(a), Some(b)) => {
+                carve_corridor(a.center(), b.center(), random, dungeon);
+                Some(if random.chance(0.5) { a } else { b })
+            }
+            (room, None) | (None, room) => room,
+        }
+    }
+
+    fn carve_room(
+        &self,
+        leaf: Leaf,
+        random: &mut Random,
+        dungeon: &mut Dungeon,
+    ) -> Option<Room> {
+        // Leave a wall tile on every side of the leaf
+        let max_width = leaf.width.checked_sub(2)?;
+        let max_height = leaf.height.checked_sub(2)?;
+        if max_width < self.min_room_size || max_height < self.min_room_size {
+            return None;
+        }
+
+        let width = random
+            .range_i32(self.min_room_size as i32, max_width as i32)
+            as u32;
+        let height = random
+            .range_i32(self.min_room_size as i32, max_height as i32)
+            as u32;
+        let x =
+            leaf.x + 1 + random.range_i32(0, (max_width - width) as i32) as u32;
+        let y = leaf.y
+            + 1
+            + random.range_i32(0, (max_height - height) as i32) as u32;
+
+        let room = Room {
+            x,
+            y,
+            width,
+            height,
+        };
+        for ty in y..y + height {
+            for tx in x..x + width {
+                dungeon.grid.set(tx, ty, true);
+            }
+        }
+        dungeon.rooms.push(room);
+        Some(room)
+    }
+}
+
+fn carve_corridor(
+    from: (u32, u32),
+    to: (u32, u32),
+    random: &mut Random,
+    dungeon: &mut Dungeon,
+) {
+    // Bend either at the start's row or at its column
+    let corner = if random.chance(0.5) {
+        (to.0, from.1)
+    } else {
+        (from.0, to.1)
+    };
+    for (a, b) in [(from, corner), (corner, to)] {
+        for y in a.1.min(b.1)..=a.1.max(b.1) {
+            for x in a.0.min(b.0)..=a.0.max(b.0) {
+                dungeon.grid.set(x, y, true);
+            }
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use super::Grid;
+use crate::random::Random;
+
+/// Cellular automaton cave generator.
+///
+/// The grid starts as random noise and is smoothed for a number of
+/// iterations: a cell becomes wall when at least `wall_threshold` of its
+/// eight neighbours are walls, floor when fewer than `wall_threshold - 1`
+/// are, and otherwise stays as it is. Cells outside the grid count as
+/// walls, so caves are always closed.
+#[derive(Debug, Clone)]
+pub struct CaveGenerator {
+    fill_chance: f32,
+    iterations: u32,
+    wall_threshold: u8,
+    keep_largest_region: bool,
+}
+
+impl Default for CaveGenerator {
+    fn default() -> Self {
+        Self {
+            fill_chance: 0.45,
+            iterations: 5,
+            wall_threshold: 5,
+            keep_largest_region: true,
+        }
+    }
+}
+
+impl CaveGenerator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Chance of a cell starting as wall.
+    pub fn with_fill_chance(
+        mut self,
+        fill_chance: f32,
+    ) -> Self {
+        self.fill_chance = fill_chance;
+        self
+    }
+
+    pub fn with_iterations(
+        mut self,
+        iterations: u32,
+    ) -> Self {
+        self.iterations = iterations;
+        self
+    }
+
+    pub fn with_wall_threshold(
+        mut self,
+        wall_threshold: u8,
+    ) -> Self {
+        self.wall_threshold = wall_threshold;
+        self
+    }
+
+    /// Fill every cave but the largest so all floor is reachable. On by
+    /// default.
+    pub fn with_keep_largest_region(
+        mut self,
+        keep: bool,
+    ) -> Self {
+        self.keep_largest_region = keep;
+        self
+    }
+
+    /// Generate a cave; `true` cells are floor.
+    pub fn generate(
+        &self,
+        width: u32,
+        height: u32,
+        seed: u64,
+    ) -> Grid<bool> {
+        let mut random = Random::new(seed);
+        let mut grid = Grid::new(width, height, false);
+        for y in 1..height.saturating_sub(1) {
+            for x in 1..width.saturating_sub(1) {
+                grid.set(x, y, !random.chance(self.fill_chance));
+            }
+        }
+
+        for _ in 0..self.iterations {
+            grid = self.smooth(&grid);
+        }
+
+        if self.keep_largest_region {
+            grid.keep_largest_region();
+        }
+        grid
+    }
+
+    fn smooth(
+        &self,
+        grid: &Grid<bool>,
+    ) -> Grid<bool> {
+        let mut next = grid.clone();
+        for y in 0..grid.height() {
+            for x in 0..grid.width() {
+                let walls = neighbour_walls(grid, x as i64, y as i64);
+                if walls >= self.wall_threshold {
+                    next.set(x, y, false);
+                } else if walls + 1 < self.wall_threshold {
+                    next.set(x, y, true);
+                }
+            }
+        }
+        next
+    }
+}
+
+fn neighbour_walls(
+    grid: &Grid<bool>,
+    x: i64,
+    y: i64,
+) -> u8 {
+    let mut walls = 0;
+    for dy in -1..=1 {
+        for dx in -1..=1 {
+            if (dx, dy) != (0, 0) && !grid.is_floor(x + dx, y + dy) {
+                walls += 1;
+            }
+        }
+    }
+    walls
+}
@@ -0,0 +1,98 @@
+use super::Grid;
+use crate::random::Random;
+
+/// Drunkard's walk: a random walker carves floor until a share of the map
+/// is open. Produces winding, always connected tunnels.
+#[derive(Debug, Clone)]
+pub struct DrunkardWalk {
+    floor_ratio: f32,
+    start: Option<(u32, u32)>,
+}
+
+impl Default for DrunkardWalk {
+    fn default() -> Self {
+        Self {
+            floor_ratio: 0.4,
+            start: None,
+        }
+    }
+}
+
+impl DrunkardWalk {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Share of the map to carve, from 0.0 to 1.0.
+    pub fn with_floor_ratio(
+        mut self,
+        floor_ratio: f32,
+    ) -> Self {
+        self.floor_ratio = floor_ratio.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Tile the walk starts on, which is always floor. Defaults to the
+    /// center of the map.
+    pub fn with_start(
+        mut self,
+        x: u32,
+        y: u32,
+    ) -> Self {
+        self.start = Some((x, y));
+        self
+    }
+
+    /// Generate tunnels; `true` cells are floor. The outer ring of the
+    /// map stays wall unless the start is on it.
+    pub fn generate(
+        &self,
+        width: u32,
+        height: u32,
+        seed: u64,
+    ) -> Grid<bool> {
+        let mut random = Random::new(seed);
+        let mut grid = Grid::new(width, height, false);
+        if width == 0 || height == 0 {
+            return grid;
+        }
+
+        let (mut x, mut y) = self.start.unwrap_or((width / 2, height / 2));
+        x = x.min(width - 1);
+        y = y.min(height - 1);
+        // Keep the border as wall when the map is big enough to have one
+        let min_x = if width > 2 { 1.min(x) } else { 0 };
+        let min_y = if height > 2 { 1.min(y) } else { 0 };
+        let max_x = if width > 2 {
+            (width - 2).max(x)
+        } else {
+            width - 1
+        };
+        let max_y = if height > 2 {
+            (height - 2).max(y)
+        } else {
+            height - 1
+        };
+
+        let target = ((width * height) as f32 * self.floor_ratio) as usize;
+        let max_steps = width as usize * height as usize * 20;
+        let mut floor = 0;
+        for _ in 0..max_steps {
+            if !grid.is_floor(x as i64, y as i64) {
+                grid.set(x, y, true);
+                floor += 1;
+            }
+            if floor >= target.max(1) {
+                break;
+            }
+            match random.range_i32(0, 3) {
+                0 if y > min_y => y -= 1,
+                1 if x < max_x => x += 1,
+                2 if y < max_y => y += 1,
+                3 if x > min_x => x -= 1,
+                _ => {}
+            }
+        }
+        grid
+    }
+}
@@ -0,0 +1,168 @@
+use crate::tile::{TileInstance, TileLayer};
+
+/// Rectangular grid of generator output, row by row from the top left.
+///
+/// Floor/wall generators produce a `Grid<bool>` where `true` is floor.
+/// Write any grid into a layer with [`Grid::write_to`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid<T> {
+    width: u32,
+    height: u32,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn new(
+        width: u32,
+        height: u32,
+        fill: T,
+    ) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![fill; width as usize * height as usize],
+        }
+    }
+}
+
+impl<T> Grid<T> {
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn cells(&self) -> &[T] {
+        &self.cells
+    }
+
+    pub fn in_bounds(
+        &self,
+        x: i64,
+        y: i64,
+    ) -> bool {
+        x >= 0 && y >= 0 && x < self.width as i64 && y < self.height as i64
+    }
+
+    pub fn get(
+        &self,
+        x: u32,
+        y: u32,
+    ) -> Option<&T> {
+        if !self.in_bounds(x as i64, y as i64) {
+            return None;
+        }
+        self.cells.get(self.index(x, y))
+    }
+
+    pub fn set(
+        &mut self,
+        x: u32,
+        y: u32,
+        value: T,
+    ) {
+        if self.in_bounds(x as i64, y as i64) {
+            let index = self.index(x, y);
+            self.cells[index] = value;
+        }
+    }
+
+    pub fn map<U>(
+        &self,
+        f: impl FnMut(&T) -> U,
+    ) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+
+    /// Place `tile(cell)` in `layer` for every cell, clearing the layer
+    /// where it returns `None`. Tiles go through `TileLayer::set_tile`, so
+    /// the layer's terrain rules autotile the result.
+    pub fn write_to(
+        &self,
+        layer: &mut TileLayer,
+        mut tile: impl FnMut(&T) -> Option<TileInstance>,
+    ) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                match tile(&self.cells[self.index(x, y)]) {
+                    Some(instance) => layer.set_tile(x, y, instance),
+                    None => layer.clear_tile(x, y),
+                }
+            }
+        }
+    }
+
+    fn index(
+        &self,
+        x: u32,
+        y: u32,
+    ) -> usize {
+        y as usize * self.width as usize + x as usize
+    }
+}
+
+impl Grid<bool> {
+    /// Whether (`x`, `y`) is floor. Cells outside the grid are walls.
+    pub fn is_floor(
+        &self,
+        x: i64,
+        y: i64,
+    ) -> bool {
+        self.in_bounds(x, y) && self.cells[self.index(x as u32, y as u32)]
+    }
+
+    pub fn floor_count(&self) -> usize {
+        self.cells.iter().filter(|&&floor| floor).count()
+    }
+
+    /// Connected floor areas, largest first. Cells connect through their
+    /// four edges.
+    pub fn regions(&self) -> Vec<Vec<(u32, u32)>> {
+        let mut visited = vec![false; self.cells.len()];
+        let mut regions = Vec::new();
+
+        for start in 0..self.cells.len() {
+            if visited[start] || !self.cells[start] {
+                continue;
+            }
+            visited[start] = true;
+            let mut region = Vec::new();
+            let mut stack = vec![start];
+            while let Some(index) = stack.pop() {
+                let x = (index % self.width as usize) as i64;
+                let y = (index / self.width as usize) as i64;
+                region.push((x as u32, y as u32));
+                for (dx, dy) in [(0, -1), (1, 0), (0, 1), (-1, 0)] {
+                    if !self.is_floor(x + dx, y + dy) {
+                        continue;
+                    }
+                    let next = self.index((x + dx) as u32, (y + dy) as u32);
+                    if !visited[next] {
+                        visited[next] = true;
+                        stack.push(next);
+                    }
+                }
+            }
+            regions.push(region);
+        }
+
+        // Stable sort keeps equally sized regions in scan order
+        regions.sort_by_key(|region| std::cmp::Reverse(region.len()));
+        regions
+    }
+
+    /// Turn every floor area but the largest into wall.
+    pub fn keep_largest_region(&mut self) {
+        for region in self.regions().iter().skip(1) {
+            for &(x, y) in region {
+                self.set(x, y, false);
+            }
+        }
+    }
+}
@@ -0,0 +1,31 @@
+//! Seeded map generators that write into tile layers.
+//!
+//! Every generator takes a seed and produces the same [`Grid`] for the
+//! same seed and settings on every platform.
+//!
+//! ```no_run
+//! use teengine::tile::procgen::CaveGenerator;
+//! # use teengine::tile::{TileLayer, TileMap};
+//! # fn example(map: &TileMap) {
+//! let cave = CaveGenerator::new().generate(64, 48, 1234);
+//! let (floor, wall) = (map.tile_instance(0), map.tile_instance(2));
+//! let mut layer = TileLayer::new(64, 48);
+//! cave.write_to(&mut layer, |&is_floor| {
+//!     Some(if is_floor { floor } else { wall })
+//! });
+//! # }
+//! ```
+
+pub mod bsp;
+pub mod cave;
+pub mod drunkard;
+pub mod grid;
+pub mod noise;
+pub mod wfc;
+
+pub use bsp::{BspGenerator, Dungeon, Room};
+pub use cave::CaveGenerator;
+pub use drunkard::DrunkardWalk;
+pub use grid::Grid;
+pub use noise::{Noise, NoiseTerrain};
+pub use wfc::{AdjacencyRules, WaveFunctionCollapse};
@@ -0,0 +1,199 @@
+use super::Grid;
+use crate::tile::TileType;
+
+/// Seeded 2D value noise.
+#[derive(Debug, Clone, Copy)]
+pub struct Noise {
+    seed: u64,
+}
+
+impl Noise {
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+
+    /// Smoothly interpolated value in `0.0..=1.0`. Integer coordinates land
+    /// on lattice points.
+    pub fn sample(
+        &self,
+        x: f32,
+        y: f32,
+    ) -> f32 {
+        let x0 = x.floor();
+        let y0 = y.floor();
+        let tx = smoothstep(x - x0);
+        let ty = smoothstep(y - y0);
+        let (xi, yi) = (x0 as i64, y0 as i64);
+
+        let top = lerp(self.lattice(xi, yi), self.lattice(xi + 1, yi), tx);
+        let bottom =
+            lerp(self.lattice(xi, yi + 1), self.lattice(xi + 1, yi + 1), tx);
+        lerp(top, bottom, ty)
+    }
+
+    /// Sum of `octaves` layers of noise, each at twice the frequency and
+    /// `persistence` times the amplitude of the previous one, normalized
+    /// to `0.0..=1.0`.
+    pub fn fractal(
+        &self,
+        x: f32,
+        y: f32,
+        octaves: u32,
+        persistence: f32,
+    ) -> f32 {
+        let mut total = 0.0;
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+        let mut max = 0.0;
+        for octave in 0..octaves.max(1) {
+            // Offset octaves so their lattices do not line up
+            let shifted = Noise::new(self.seed.wrapping_add(octave as u64));
+            total += shifted.sample(x * frequency, y * frequency) * amplitude;
+            max += amplitude;
+            amplitude *= persistence;
+            frequency *= 2.0;
+        }
+        total / max
+    }
+
+    fn lattice(
+        &self,
+        x: i64,
+        y: i64,
+    ) -> f32 {
+        // splitmix64 finalizer over the seed and coordinates
+        let mut h = self.seed
+            ^ (x as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+            ^ (y as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+        h = (h ^ (h >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        h = (h ^ (h >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        h ^= h >> 31;
+        (h >> 40) as f32 / ((1u64 << 24) - 1) as f32
+    }
+}
+
+fn smoothstep(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn lerp(
+    a: f32,
+    b: f32,
+    t: f32,
+) -> f32 {
+    a + (b - a) * t
+}
+
+/// Terrain from fractal noise, with height bands mapped to tile types.
+///
+/// ```
+/// use teengine::tile::procgen::NoiseTerrain;
+/// use teengine::tile::TileType;
+///
+/// let terrain = NoiseTerrain::new()
+///     .with_band(0.35, TileType::Water)
+///     .with_band(0.45, TileType::Sand)
+///     .with_band(1.0, TileType::Ground);
+/// let map = terrain.generate(32, 32, 7).unwrap();
+/// assert_eq!(map, terrain.generate(32, 32, 7).unwrap());
+/// ```
+#[derive(Debug, Clone)]
+pub struct NoiseTerrain {
+    scale: f32,
+    octaves: u32,
+    persistence: f32,
+    bands: Vec<(f32, TileType)>,
+}
+
+impl Default for NoiseTerrain {
+    fn default() -> Self {
+        Self {
+            scale: 0.1,
+            octaves: 4,
+            persistence: 0.5,
+            bands: Vec::new(),
+        }
+    }
+}
+
+impl NoiseTerrain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Noise frequency per tile; smaller values give larger features.
+    pub fn with_scale(
+        mut self,
+        scale: f32,
+    ) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn with_octaves(
+        mut self,
+        octaves: u32,
+        persistence: f32,
+    ) -> Self {
+        self.octaves = octaves;
+        self.persistence = persistence;
+        self
+    }
+
+    /// Heights up to `max_height` above the previous band become
+    /// `tile_type`. The highest band also covers anything above it.
+    pub fn with_band(
+        mut self,
+        max_height: f32,
+        tile_type: TileType,
+    ) -> Self {
+        let index = self
+            .bands
+            .partition_point(|(height, _)| *height <= max_height);
+        self.bands.insert(index, (max_height, tile_type));
+        self
+    }
+
+    /// Raw heights in `0.0..=1.0`.
+    pub fn heights(
+        &self,
+        width: u32,
+        height: u32,
+        seed: u64,
+    ) -> Grid<f32> {
+        let noise = Noise::new(seed);
+        let mut grid = Grid::new(width, height, 0.0);
+        for y in 0..height {
+            for x in 0..width {
+                let value = noise.fractal(
+                    x as f32 * self.scale,
+                    y as f32 * self.scale,
+                    self.octaves,
+                    self.persistence,
+                );
+                grid.set(x, y, value);
+            }
+        }
+        grid
+    }
+
+    /// Tile type of every cell. Fails if no bands were added.
+    pub fn generate(
+        &self,
+        width: u32,
+        height: u32,
+        seed: u64,
+    ) -> Result<Grid<TileType>, String> {
+        if self.bands.is_empty() {
+            return Err("Noise terrain needs at least one band".to_string());
+        }
+        Ok(self.heights(width, height, seed).map(|&value| {
+            self.bands
+                .iter()
+                .find(|(max_height, _)| value <= *max_height)
+                .unwrap_or(&self.bands[self.bands.len() - 1])
+                .1
+                .clone()
+        }))
+    }
+}
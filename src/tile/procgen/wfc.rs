@@ -0,0 +1,305 @@
+use super::Grid;
+use crate::random::Random;
+
+// Direction order used for sockets and neighbour offsets
+const NORTH: usize = 0;
+const EAST: usize = 1;
+const SOUTH: usize = 2;
+const WEST: usize = 3;
+const OFFSETS: [(i64, i64); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+fn opposite(direction: usize) -> usize {
+    (direction + 2) % 4
+}
+
+/// Tile adjacency for [`WaveFunctionCollapse`].
+///
+/// Every tile has a socket name on its north, east, south and west edge.
+/// Two tiles may sit next to each other when the sockets on their shared
+/// edge are equal. Tilesets define these in their JSON with an
+/// `adjacency` entry per tile, collected with `TileMap::adjacency_rules`.
+#[derive(Debug, Clone, Default)]
+pub struct AdjacencyRules {
+    tiles: Vec<RuleTile>,
+}
+
+#[derive(Debug, Clone)]
+struct RuleTile {
+    id: u32,
+    weight: f32,
+    sockets: [String; 4],
+}
+
+impl AdjacencyRules {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add tile `id` with edge sockets in north, east, south, west order.
+    /// Higher `weight` makes the tile more common.
+    pub fn add_tile(
+        &mut self,
+        id: u32,
+        weight: f32,
+        sockets: [&str; 4],
+    ) {
+        self.tiles.push(RuleTile {
+            id,
+            weight: weight.max(0.0),
+            sockets: sockets.map(str::to_string),
+        });
+    }
+
+    pub fn with_tile(
+        mut self,
+        id: u32,
+        weight: f32,
+        sockets: [&str; 4],
+    ) -> Self {
+        self.add_tile(id, weight, sockets);
+        self
+    }
+
+    pub fn extend(
+        &mut self,
+        other: AdjacencyRules,
+    ) {
+        self.tiles.extend(other.tiles);
+    }
+
+    pub fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+}
+
+/// Wave function collapse over single tiles.
+///
+/// Cells start allowing every tile. The most constrained cell is
+/// collapsed to one tile picked by weight, and the choice is propagated
+/// to its neighbours until every cell holds one tile. A contradiction
+/// restarts the attempt with the generator's next random state.
+///
+/// ```
+/// use teengine::tile::procgen::{AdjacencyRules, WaveFunctionCollapse};
+///
+/// // Water (0) never touches grass (2) directly; sand (1) sits between
+/// let rules = AdjacencyRules::new()
+///     .with_tile(0, 1.0, ["water"; 4])
+///     .with_tile(1, 0.5, ["sand"; 4])
+///     .with_tile(2, 1.0, ["grass"; 4])
+///     .with_tile(3, 0.2, ["water", "sand", "sand", "water"])
+///     .with_tile(4, 0.2, ["sand", "grass", "grass", "sand"]);
+/// let wfc = WaveFunctionCollapse::new(rules);
+/// let map = wfc.generate(8, 8, 3).unwrap();
+/// assert_eq!(map, wfc.generate(8, 8, 3).unwrap());
+/// ```
+#[derive(Debug, Clone)]
+pub struct WaveFunctionCollapse {
+    rules: AdjacencyRules,
+    max_attempts: u32,
+}
+
+impl WaveFunctionCollapse {
+    pub fn new(rules: AdjacencyRules) -> Self {
+        Self {
+            rules,
+            max_attempts: 10,
+        }
+    }
+
+    pub fn with_max_attempts(
+        mut self,
+        max_attempts: u32,
+    ) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Grid of tile IDs from the rules.
+    pub fn generate(
+        &self,
+        width: u32,
+        height: u32,
+        seed: u64,
+    ) -> Result<Grid<u32>, String> {
+        if self.rules.is_empty() {
+            return Err(
+                "Wave function collapse needs at least one tile".to_string()
+            );
+        }
+
+        let compatible = self.compatibility();
+        let mut random = Random::new(seed);
+        for _ in 0..self.max_attempts {
+            if let Some(grid) =
+                self.attempt(width, height, &compatible, &mut random)
+            {
+                return Ok(grid);
+            }
+        }
+        Err(format!(
+            "Wave function collapse found no {}x{} layout in {} attempts",
+            width, height, self.max_attempts
+        ))
+    }
+
+    /// `compatible[direction][a][b]`: tile `b` may sit in `direction` of
+    /// tile `a`.
+    fn compatibility(&self) -> [Vec<Vec<bool>>; 4] {
+        let tiles = &self.rules.tiles;
+        std::array::from_fn(|direction| {
+            tiles
+                .iter()
+                .map(|a| {
+                    tiles
+                        .iter()
+                        .map(|b| {
+                            a.sockets[direction]
+                                == b.sockets[opposite(direction)]
+                        })
+                        .collect()
+                })
+                .collect()
+        })
+    }
+
+    fn attempt(
+        &self,
+        width: u32,
+        height: u32,
+        compatible: &[Vec<Vec<bool>>; 4],
+        random: &mut Random,
+    ) -> Option<Grid<u32>> {
+        let count = self.rules.tiles.len();
+        let mut wave = Grid::new(width, height, vec![true; count]);
+
+        loop {
+            // Most constrained open cell, ties broken randomly
+            let mut best: Option<(u32, u32)> = None;
+            let mut best_score = f32::MAX;
+            for y in 0..height {
+                for x in 0..width {
+                    let options =
+                        wave.get(x, y)?.iter().filter(|&&o| o).count();
+                    if options <= 1 {
+                        continue;
+                    }
+                    let score = options as f32 + random.next_f32() * 0.5;
+                    if score < best_score {
+                        best_score = score;
+                        best = Some((x, y));
+                    }
+                }
+            }
+            let Some((x, y)) = best else {
+                break;
+            };
+
+            let choice = self.pick(wave.get(x, y)?, random)?;
+            let mut collapsed = vec![false; count];
+            collapsed[choice] = true;
+            wave.set(x, y, collapsed);
+
+            if !propagate(&mut wave, compatible, x, y) {
+                return None;
+            }
+        }
+
+        let ids: Vec<u32> = wave
+            .cells()
+            .iter()
+            .map(|options| {
+                options
+                    .iter()
+                    .position(|&o| o)
+                    .map(|index| self.rules.tiles[index].id)
+            })
+            .collect::<Option<_>>()?;
+        let mut grid = Grid::new(width, height, 0);
+        for (index, id) in ids.into_iter().enumerate() {
+            grid.set(index as u32 % width, index as u32 / width, id);
+        }
+        Some(grid)
+    }
+
+    fn pick(
+        &self,
+        options: &[bool],
+        random: &mut Random,
+    ) -> Option<usize> {
+        let total: f32 = self
+            .rules
+            .tiles
+            .iter()
+            .zip(options)
+            .filter(|(_, &allowed)| allowed)
+            .map(|(tile, _)| tile.weight)
+            .sum();
+        let mut roll = random.next_f32() * total;
+        let mut last = None;
+        for (index, tile) in self.rules.tiles.iter().enumerate() {
+            if !options[index] {
+                continue;
+            }
+            last = Some(index);
+            if roll < tile.weight {
+                return Some(index);
+            }
+            roll -= tile.weight;
+        }
+        last
+    }
+}
+
+/// Remove options that no longer fit their neighbours, starting from
+/// (`x`, `y`). Returns false on a contradiction.
+fn propagate(
+    wave: &mut Grid<Vec<bool>>,
+    compatible: &[Vec<Vec<bool>>; 4],
+    x: u32,
+    y: u32,
+) -> bool {
+    let mut stack = vec![(x, y)];
+    while let Some((x, y)) = stack.pop() {
+        let Some(current) = wave.get(x, y).cloned() else {
+            continue;
+        };
+        for direction in [NORTH, EAST, SOUTH, WEST] {
+            let (dx, dy) = OFFSETS[direction];
+            let (nx, ny) = (x as i64 + dx, y as i64 + dy);
+            if !wave.in_bounds(nx, ny) {
+                continue;
+            }
+            let (nx, ny) = (nx as u32, ny as u32);
+            let Some(mut neighbour) = wave.get(nx, ny).cloned() else {
+                continue;
+            };
+
+            let mut changed = false;
+            for (b, allowed) in neighbour.iter_mut().enumerate() {
+                if *allowed
+                    && !current
+                        .iter()
+                        .enumerate()
+                        .any(|(a, &o)| o && compatible[direction][a][b])
+                {
+                    *allowed = false;
+                    changed = true;
+                }
+            }
+            if !changed {
+                continue;
+            }
+            if !neighbour.contains(&true) {
+                return false;
+            }
+            wave.set(nx, ny, neighbour);
+            stack.push((nx, ny));
+        }
+    }
+    true
+}
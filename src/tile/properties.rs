@@ -1,7 +1,12 @@
-use glam::Vec2;
+use glam::{Vec2, Vec4};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use super::TileFlags;
+
+/// Custom property overriding whether a tile blocks sight.
+pub const OPAQUE_PROPERTY: &str = "opaque";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TileProperties {
     pub physics: PhysicsProperties,
@@ -29,6 +34,87 @@ pub enum CollisionType {
     Custom(String),
 }
 
+impl CollisionType {
+    /// Corners of the solid part of a `Slope` tile drawn in a `size` cell
+    /// with `flags`, relative to the cell's top left corner. `None` for
+    /// other collision types.
+    ///
+    /// A slope rises to the right by its angle in degrees from the bottom
+    /// left corner, clipped to the tile; flipping or rotating the tile
+    /// turns the shape with it.
+    pub fn slope_outline(
+        &self,
+        size: Vec2,
+        flags: TileFlags,
+    ) -> Option<[Vec2; 4]> {
+        let CollisionType::Slope(angle) = self else {
+            return None;
+        };
+        let (left, right) = slope_surface(*angle, image_size(size, flags));
+        Some(
+            [
+                Vec2::new(0.0, left),
+                Vec2::new(1.0, right),
+                Vec2::ONE,
+                Vec2::new(0.0, 1.0),
+            ]
+            .map(|corner| flags.transform_point(corner) * size),
+        )
+    }
+
+    /// Whether `point`, relative to the top left corner of a `size` cell,
+    /// lies in the collision shape of a tile drawn there with `flags`.
+    /// Only `Full` and `Slope` tiles have a shape.
+    pub fn is_solid_at(
+        &self,
+        point: Vec2,
+        size: Vec2,
+        flags: TileFlags,
+    ) -> bool {
+        let local = point / size;
+        if local.cmplt(Vec2::ZERO).any() || local.cmpgt(Vec2::ONE).any() {
+            return false;
+        }
+        match self {
+            CollisionType::Full => true,
+            CollisionType::Slope(angle) => {
+                let image = flags.inverse_transform_point(local);
+                let (left, right) =
+                    slope_surface(*angle, image_size(size, flags));
+                image.y >= left + (right - left) * image.x
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Height of a slope's surface at the left and right edge of the tile
+/// image, in tile units from the top.
+fn slope_surface(
+    angle: f32,
+    image_size: Vec2,
+) -> (f32, f32) {
+    let rise = (angle.to_radians().tan() * image_size.x / image_size.y)
+        .clamp(-1.0, 1.0);
+    if rise >= 0.0 {
+        (1.0, 1.0 - rise)
+    } else {
+        (1.0 + rise, 1.0)
+    }
+}
+
+// A diagonal flip lays the tile image on its side in the cell
+fn image_size(
+    size: Vec2,
+    flags: TileFlags,
+) -> Vec2 {
+    if flags.contains(TileFlags::FLIP_DIAGONAL) {
+        Vec2::new(size.y, size.x)
+    } else {
+        size
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameplayProperties {
     pub tile_type: TileType,
@@ -62,7 +148,7 @@ pub struct VisualProperties {
     pub tint: Option<Color>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct Color {
     pub r: f32,
     pub g: f32,
@@ -70,6 +156,24 @@ pub struct Color {
     pub a: f32,
 }
 
+impl Color {
+    pub const WHITE: Color = Color::new(1.0, 1.0, 1.0, 1.0);
+    pub const BLACK: Color = Color::new(0.0, 0.0, 0.0, 1.0);
+
+    pub const fn new(
+        r: f32,
+        g: f32,
+        b: f32,
+        a: f32,
+    ) -> Self {
+        Self { r, g, b, a }
+    }
+
+    pub fn to_vec4(self) -> Vec4 {
+        Vec4::new(self.r, self.g, self.b, self.a)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum PropertyValue {
@@ -126,4 +230,15 @@ impl TileProperties {
     pub fn is_collidable(&self) -> bool {
         self.physics.collision
     }
+
+    /// Whether the tile blocks sight. Collidable tiles do unless the
+    /// `opaque` custom property says otherwise, e.g. `false` for glass or
+    /// `true` for tall grass.
+    pub fn is_opaque(&self) -> bool {
+        match self.custom_properties.get(OPAQUE_PROPERTY) {
+            Some(PropertyValue::Bool(opaque)) => *opaque,
+            Some(PropertyValue::Int(opaque)) => *opaque != 0,
+            _ => self.physics.collision,
+        }
+    }
 }
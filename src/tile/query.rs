@@ -0,0 +1,466 @@
+use glam::Vec2;
+
+use super::{
+    Orientation, TileInstance, TileLayer, TileMap, TileProperties, TileType,
+};
+
+/// Tile of a layer returned by the map queries, with its properties.
+#[derive(Debug, Clone, Copy)]
+pub struct TileRef<'a> {
+    pub layer: &'a str,
+    pub x: u32,
+    pub y: u32,
+    pub tile: &'a TileInstance,
+    pub properties: &'a TileProperties,
+}
+
+/// First tile a ray hit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RaycastHit {
+    pub tile: (u32, u32),
+    /// World position where the ray entered the tile.
+    pub point: Vec2,
+    /// Unit normal of the tile side that was hit, pointing back towards
+    /// the ray. Zero if the ray started inside the tile.
+    pub normal: Vec2,
+    /// World distance from the ray origin to `point`.
+    pub distance: f32,
+}
+
+/// One tile crossed by a [`GridRay`], in tile units.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct RayStep {
+    pub tile: (i64, i64),
+    /// Distance along the ray where it enters the tile.
+    pub distance: f32,
+    /// Side it entered through, zero for the starting tile.
+    pub normal: Vec2,
+}
+
+/// DDA traversal of the tiles a ray passes through, in tile units.
+#[derive(Debug, Clone)]
+pub(crate) struct GridRay {
+    tile: (i64, i64),
+    step: (i64, i64),
+    t_max: Vec2,
+    t_delta: Vec2,
+    max_distance: f32,
+    started: bool,
+}
+
+impl GridRay {
+    /// Ray from `origin` along the unit vector `direction`, ending after
+    /// `max_distance`. Positions are in tile units, so (1.5, 0.5) is the
+    /// center of tile (1, 0).
+    pub fn new(
+        origin: Vec2,
+        direction: Vec2,
+        max_distance: f32,
+    ) -> Self {
+        let axis = |delta: f32| {
+            if delta > 0.0 {
+                1
+            } else if delta < 0.0 {
+                -1
+            } else {
+                0
+            }
+        };
+        let step = (axis(direction.x), axis(direction.y));
+        // Ray length to cross one tile on each axis, and to reach the
+        // first border
+        let t_delta = Vec2::new(
+            if step.0 != 0 {
+                1.0 / direction.x.abs()
+            } else {
+                f32::INFINITY
+            },
+            if step.1 != 0 {
+                1.0 / direction.y.abs()
+            } else {
+                f32::INFINITY
+            },
+        );
+        let border = |position: f32, step: i64, t_delta: f32| match step {
+            1 => (position.floor() + 1.0 - position) * t_delta,
+            -1 => (position - position.floor()) * t_delta,
+            _ => f32::INFINITY,
+        };
+
+        Self {
+            tile: (origin.x.floor() as i64, origin.y.floor() as i64),
+            step,
+            t_max: Vec2::new(
+                border(origin.x, step.0, t_delta.x),
+                border(origin.y, step.1, t_delta.y),
+            ),
+            t_delta,
+            max_distance,
+            started: false,
+        }
+    }
+}
+
+impl Iterator for GridRay {
+    type Item = RayStep;
+
+    fn next(&mut self) -> Option<RayStep> {
+        if !self.started {
+            self.started = true;
+            return Some(RayStep {
+                tile: self.tile,
+                distance: 0.0,
+                normal: Vec2::ZERO,
+            });
+        }
+
+        let (distance, normal) = if self.t_max.x < self.t_max.y {
+            self.tile.0 += self.step.0;
+            let distance = self.t_max.x;
+            self.t_max.x += self.t_delta.x;
+            (distance, Vec2::new(-self.step.0 as f32, 0.0))
+        } else {
+            self.tile.1 += self.step.1;
+            let distance = self.t_max.y;
+            self.t_max.y += self.t_delta.y;
+            (distance, Vec2::new(0.0, -self.step.1 as f32))
+        };
+        if !distance.is_finite() || distance > self.max_distance {
+            return None;
+        }
+        Some(RayStep {
+            tile: self.tile,
+            distance,
+            normal,
+        })
+    }
+}
+
+impl TileMap {
+    /// Cast a ray from `origin` along `direction` for up to
+    /// `max_distance` world units and return the first collidable tile.
+    ///
+    /// ```no_run
+    /// # use glam::Vec2;
+    /// # fn example(map: &teengine::tile::TileMap, player: Vec2) {
+    /// let aim = Vec2::new(1.0, 0.5);
+    /// if let Some(hit) = map.raycast(player, aim, 800.0) {
+    ///     // Bounce a projectile off the wall
+    ///     let reflected = aim - 2.0 * aim.dot(hit.normal) * hit.normal;
+    /// }
+    /// # }
+    /// ```
+    pub fn raycast(
+        &self,
+        origin: Vec2,
+        direction: Vec2,
+        max_distance: f32,
+    ) -> Option<RaycastHit> {
+        self.raycast_by(origin, direction, max_distance, |tile| {
+            tile.properties.is_collidable()
+        })
+    }
+
+    /// Like [`TileMap::raycast`], stopping at the first tile of any layer
+    /// for which `hits` returns true. The ray stops at the map edge.
+    ///
+    /// Returns `None` on staggered and hexagonal maps: their offset rows
+    /// do not form a straight grid to walk the ray through.
+    pub fn raycast_by(
+        &self,
+        origin: Vec2,
+        direction: Vec2,
+        max_distance: f32,
+        mut hits: impl FnMut(TileRef<'_>) -> bool,
+    ) -> Option<RaycastHit> {
+        let direction = direction.normalize_or_zero();
+        if direction == Vec2::ZERO {
+            return None;
+        }
+        // Walk the grid in tile coordinates, where the direction is
+        // stretched or sheared; `tile_speed` tile units are covered per
+        // world unit
+        let (to_grid, offset) =
+            self.orientation.grid_transform(self.world_tile_size())?;
+        let tile_direction = to_grid * direction;
+        let tile_speed = tile_direction.length();
+        let ray = GridRay::new(
+            to_grid * origin + offset,
+            tile_direction / tile_speed,
+            max_distance * tile_speed,
+        );
+
+        for step in ray {
+            let (x, y) = (step.tile.0 as i32, step.tile.1 as i32);
+            if !self.contains_tile(x, y) {
+                // Keep going while the ray approaches the map from outside
+                if self.ray_leaves_map(step.tile, tile_direction) {
+                    return None;
+                }
+                continue;
+            }
+            let (x, y) = (x as u32, y as u32);
+            let hit = self.layers.iter().any(|(name, layer)| {
+                self.tile_ref(name, layer, x, y).is_some_and(&mut hits)
+            });
+            if hit {
+                let distance = step.distance / tile_speed;
+                return Some(RaycastHit {
+                    tile: (x, y),
+                    point: origin + direction * distance,
+                    // Normals transform with the transposed grid map
+                    normal: (to_grid.transpose() * step.normal)
+                        .normalize_or_zero(),
+                    distance,
+                });
+            }
+        }
+        None
+    }
+
+    fn ray_leaves_map(
+        &self,
+        (x, y): (i64, i64),
+        direction: Vec2,
+    ) -> bool {
+        (x < 0 && direction.x <= 0.0)
+            || (y < 0 && direction.y <= 0.0)
+            || (x >= self.width as i64 && direction.x >= 0.0)
+            || (y >= self.height as i64 && direction.y >= 0.0)
+    }
+
+    /// Whether `world_pos` lies in the collision shape of a tile of any
+    /// layer: the whole cell for `Full` tiles and the part under the
+    /// surface for `Slope` tiles, flipped and rotated with the tile.
+    pub fn is_solid_at(
+        &self,
+        world_pos: Vec2,
+    ) -> bool {
+        let Some((x, y)) = self.world_to_tile_checked(world_pos) else {
+            return false;
+        };
+        let size = self.world_tile_size();
+        let local = world_pos - self.tile_to_world(x, y);
+        self.layers.iter().any(|(name, layer)| {
+            self.tile_ref(name, layer, x, y).is_some_and(|tile| {
+                let physics = &tile.properties.physics;
+                physics.collision
+                    && physics.collision_type.is_solid_at(
+                        local,
+                        size,
+                        tile.tile.flags,
+                    )
+            })
+        })
+    }
+
+    /// Tiles of every layer at (`x`, `y`), with layers in name order.
+    pub fn tiles_at(
+        &self,
+        x: u32,
+        y: u32,
+    ) -> impl Iterator<Item = TileRef<'_>> {
+        self.sorted_layers()
+            .into_iter()
+            .filter_map(move |(name, layer)| self.tile_ref(name, layer, x, y))
+    }
+
+    /// Coordinates of the map tiles overlapping the world rectangle from
+    /// `min` to `max`. Tiles only touching its edge are left out.
+    pub fn tiles_in_aabb(
+        &self,
+        min: Vec2,
+        max: Vec2,
+    ) -> impl Iterator<Item = (u32, u32)> + '_ {
+        let (min, max) = (min.min(max), max.max(min));
+        let (x0, y0, x1, y1) = self.tile_range(min, max);
+        let orthogonal = self.orientation == Orientation::Orthogonal;
+        (y0..y1)
+            .flat_map(move |y| (x0..x1).map(move |x| (x, y)))
+            .filter(move |&(x, y)| {
+                orthogonal
+                    || polygon_overlaps_rect(&self.tile_outline(x, y), min, max)
+            })
+    }
+
+    /// Coordinates of the map tiles overlapping a world circle.
+    pub fn tiles_in_circle(
+        &self,
+        center: Vec2,
+        radius: f32,
+    ) -> impl Iterator<Item = (u32, u32)> + '_ {
+        let radius = radius.max(0.0);
+        self.tiles_in_aabb(center - radius, center + radius).filter(
+            move |&(x, y)| {
+                polygon_overlaps_circle(
+                    &self.tile_outline(x, y),
+                    center,
+                    radius,
+                )
+            },
+        )
+    }
+
+    /// Corners of the shape of map tile (`x`, `y`) in world units.
+    pub fn tile_outline(
+        &self,
+        x: u32,
+        y: u32,
+    ) -> Vec<Vec2> {
+        self.orientation.tile_outline(
+            x as i32,
+            y as i32,
+            self.world_tile_size(),
+        )
+    }
+
+    // Map tiles whose cells may overlap the rectangle, as half-open ranges
+    // `x0..x1`, `y0..y1`
+    fn tile_range(
+        &self,
+        min: Vec2,
+        max: Vec2,
+    ) -> (u32, u32, u32, u32) {
+        let clamp_x = |v: f32| v.clamp(0.0, self.width as f32) as u32;
+        let clamp_y = |v: f32| v.clamp(0.0, self.height as f32) as u32;
+        if self.orientation == Orientation::Orthogonal {
+            let tile_size = self.world_tile_size();
+            let first = (min / tile_size).floor();
+            let end = (max / tile_size).ceil();
+            return (
+                clamp_x(first.x),
+                clamp_y(first.y),
+                clamp_x(end.x),
+                clamp_y(end.y),
+            );
+        }
+
+        // The corners bound the tiles of rotated grids; one more tile on
+        // every side covers the offset rows of staggered and hex grids
+        let corners =
+            [min, Vec2::new(max.x, min.y), Vec2::new(min.x, max.y), max]
+                .map(|corner| self.world_to_tile_signed(corner));
+        let first_x = corners.iter().map(|tile| tile.0).min().unwrap_or(0);
+        let first_y = corners.iter().map(|tile| tile.1).min().unwrap_or(0);
+        let last_x = corners.iter().map(|tile| tile.0).max().unwrap_or(0);
+        let last_y = corners.iter().map(|tile| tile.1).max().unwrap_or(0);
+        (
+            clamp_x((first_x - 1) as f32),
+            clamp_y((first_y - 1) as f32),
+            clamp_x((last_x + 2) as f32),
+            clamp_y((last_y + 2) as f32),
+        )
+    }
+
+    /// Every placed tile of every layer, layers in name order.
+    pub fn tiles(&self) -> impl Iterator<Item = TileRef<'_>> {
+        self.sorted_layers()
+            .into_iter()
+            .flat_map(move |(name, layer)| {
+                layer.tiles.iter().enumerate().flat_map(move |(y, row)| {
+                    (0..row.len()).filter_map(move |x| {
+                        self.tile_ref(name, layer, x as u32, y as u32)
+                    })
+                })
+            })
+    }
+
+    /// Placed tiles whose gameplay type is `tile_type`.
+    pub fn tiles_of_type<'a>(
+        &'a self,
+        tile_type: &'a TileType,
+    ) -> impl Iterator<Item = TileRef<'a>> {
+        self.tiles().filter(move |tile| {
+            tile.properties.gameplay.tile_type == *tile_type
+        })
+    }
+
+    /// Placed tiles that define the custom property `name`.
+    pub fn tiles_with_property<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> impl Iterator<Item = TileRef<'a>> {
+        self.tiles().filter(move |tile| {
+            tile.properties.custom_properties.contains_key(name)
+        })
+    }
+
+    pub(crate) fn tile_ref<'a>(
+        &'a self,
+        layer_name: &'a str,
+        layer: &'a TileLayer,
+        x: u32,
+        y: u32,
+    ) -> Option<TileRef<'a>> {
+        Some(TileRef {
+            layer: layer_name,
+            x,
+            y,
+            tile: layer.tiles.get(y as usize)?.get(x as usize)?.as_ref()?,
+            properties: self.properties_in(layer, x, y)?,
+        })
+    }
+
+    fn sorted_layers(&self) -> Vec<(&str, &TileLayer)> {
+        let mut layers: Vec<_> = self
+            .layers
+            .iter()
+            .map(|(name, layer)| (name.as_str(), layer))
+            .collect();
+        layers.sort_by_key(|(name, _)| *name);
+        layers
+    }
+}
+
+/// Whether a convex `polygon` and the rectangle from `min` to `max`
+/// share more than an edge, by separating axes.
+fn polygon_overlaps_rect(
+    polygon: &[Vec2],
+    min: Vec2,
+    max: Vec2,
+) -> bool {
+    let rect = [min, Vec2::new(max.x, min.y), max, Vec2::new(min.x, max.y)];
+    let edge_normals = polygon
+        .iter()
+        .zip(polygon.iter().cycle().skip(1))
+        .map(|(&a, &b)| (b - a).perp());
+    [Vec2::X, Vec2::Y]
+        .into_iter()
+        .chain(edge_normals)
+        .all(|axis| {
+            let (poly_min, poly_max) = project(polygon, axis);
+            let (rect_min, rect_max) = project(&rect, axis);
+            poly_min < rect_max && rect_min < poly_max
+        })
+}
+
+/// Whether a convex `polygon` and a circle overlap: the center lies inside
+/// the polygon or closer than `radius` to one of its edges.
+fn polygon_overlaps_circle(
+    polygon: &[Vec2],
+    center: Vec2,
+    radius: f32,
+) -> bool {
+    let edges = || polygon.iter().zip(polygon.iter().cycle().skip(1));
+    // Outlines run clockwise on screen, so the inside is to the right
+    let inside = edges().all(|(&a, &b)| (b - a).perp_dot(center - a) >= 0.0);
+    inside
+        || edges().any(|(&a, &b)| {
+            let edge = b - a;
+            let t = ((center - a).dot(edge) / edge.length_squared())
+                .clamp(0.0, 1.0);
+            (a + edge * t).distance_squared(center) < radius * radius
+        })
+}
+
+fn project(
+    points: &[Vec2],
+    axis: Vec2,
+) -> (f32, f32) {
+    points
+        .iter()
+        .map(|point| point.dot(axis))
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), d| {
+            (min.min(d), max.max(d))
+        })
+}
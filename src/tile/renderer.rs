@@ -1,24 +1,42 @@
-use super::{TileLayer, TileMap};
+use super::chunked::ChunkedLayer;
+use super::tile_shaders::{TILEMAP_FRAGMENT_SHADER, TILEMAP_VERTEX_SHADER};
+use super::visibility::VisibilityMap;
+use super::{Orientation, TileInstance, TileLayer, TileMap};
+use crate::shader::{Material, ShaderProgram};
 use gl::types::*;
-use glam::Mat4;
+use glam::{Mat4, Vec2};
 
+// position & scale (4) + UV rectangle (4) + color (4) + flip flags (1)
+const FLOATS_PER_INSTANCE: usize = 13;
+
+/// Draws tile layers with instancing, one batch per tileset texture.
+///
+/// Tiles of non-orthogonal maps overlap their neighbours, so there a batch
+/// is drawn whenever the next tile in draw order uses another tileset.
 pub struct TileMapRenderer {
-    shader_program: GLuint,
+    shader_program: ShaderProgram,
     vao: GLuint,
     vbo: GLuint,
     instance_vbo: GLuint,
     max_instances: usize,
-    vertex_data: Vec<f32>,
-    instance_data: Vec<f32>,
+    batches: Vec<Vec<f32>>,
+    /// Tileset of the last tile pushed since the batches were drawn.
+    batch_tileset: Option<usize>,
+    /// Back to front tile order of the last layer shape drawn, with the
+    /// orientation, width and height it was built for.
+    draw_order: Vec<(u32, u32)>,
+    draw_order_key: Option<(Orientation, u32, u32)>,
+    /// Resident chunk tiles with their position, sorted back to front.
+    chunk_tiles: Vec<(Vec2, TileInstance)>,
 }
 
 impl TileMapRenderer {
     pub fn new(max_instances: usize) -> Result<Self, String> {
-        let vertex_shader =
-            compile_shader(gl::VERTEX_SHADER, TILEMAP_VERTEX_SHADER)?;
-        let fragment_shader =
-            compile_shader(gl::FRAGMENT_SHADER, TILEMAP_FRAGMENT_SHADER)?;
-        let shader_program = link_program(vertex_shader, fragment_shader)?;
+        let max_instances = max_instances.max(1);
+        let shader_program = ShaderProgram::from_source(
+            TILEMAP_VERTEX_SHADER,
+            TILEMAP_FRAGMENT_SHADER,
+        )?;
 
         let mut vao = 0;
         let mut vbo = 0;
@@ -76,7 +94,9 @@ impl TileMapRenderer {
             gl::BindBuffer(gl::ARRAY_BUFFER, instance_vbo);
             gl::BufferData(
                 gl::ARRAY_BUFFER,
-                (max_instances * 8 * std::mem::size_of::<f32>()) as GLsizeiptr,
+                (max_instances
+                    * FLOATS_PER_INSTANCE
+                    * std::mem::size_of::<f32>()) as GLsizeiptr,
                 std::ptr::null(),
                 gl::DYNAMIC_DRAW,
             );
@@ -89,7 +109,7 @@ impl TileMapRenderer {
                 4,
                 gl::FLOAT,
                 gl::FALSE,
-                8 * std::mem::size_of::<f32>() as GLsizei,
+                (FLOATS_PER_INSTANCE * std::mem::size_of::<f32>()) as GLsizei,
                 std::ptr::null(),
             );
             gl::VertexAttribDivisor(2, 1);
@@ -101,11 +121,35 @@ impl TileMapRenderer {
                 4,
                 gl::FLOAT,
                 gl::FALSE,
-                8 * std::mem::size_of::<f32>() as GLsizei,
+                (FLOATS_PER_INSTANCE * std::mem::size_of::<f32>()) as GLsizei,
                 (4 * std::mem::size_of::<f32>()) as *const () as *const _,
             );
             gl::VertexAttribDivisor(3, 1);
 
+            // Color
+            gl::EnableVertexAttribArray(4);
+            gl::VertexAttribPointer(
+                4,
+                4,
+                gl::FLOAT,
+                gl::FALSE,
+                (FLOATS_PER_INSTANCE * std::mem::size_of::<f32>()) as GLsizei,
+                (8 * std::mem::size_of::<f32>()) as *const () as *const _,
+            );
+            gl::VertexAttribDivisor(4, 1);
+
+            // Flip flags
+            gl::EnableVertexAttribArray(5);
+            gl::VertexAttribPointer(
+                5,
+                1,
+                gl::FLOAT,
+                gl::FALSE,
+                (FLOATS_PER_INSTANCE * std::mem::size_of::<f32>()) as GLsizei,
+                (12 * std::mem::size_of::<f32>()) as *const () as *const _,
+            );
+            gl::VertexAttribDivisor(5, 1);
+
             gl::BindBuffer(gl::ARRAY_BUFFER, 0);
             gl::BindVertexArray(0);
         }
@@ -116,41 +160,84 @@ impl TileMapRenderer {
             vbo,
             instance_vbo,
             max_instances,
-            vertex_data,
-            instance_data: Vec::with_capacity(max_instances * 8),
+            batches: Vec::new(),
+            batch_tileset: None,
+            draw_order: Vec::new(),
+            draw_order_key: None,
+            chunk_tiles: Vec::new(),
         })
     }
 
+    /// Replace the shader used to draw tiles. The program must accept the
+    /// same attributes and `projection` uniform as the built-in one. Layers
+    /// with a material use the material's shader instead.
+    pub fn set_shader(
+        &mut self,
+        shader: ShaderProgram,
+    ) {
+        self.shader_program = shader;
+    }
+
+    pub fn shader(&self) -> &ShaderProgram {
+        &self.shader_program
+    }
+
     pub fn render(
         &mut self,
         tilemap: &TileMap,
         projection: &Mat4,
     ) {
-        unsafe {
-            gl::UseProgram(self.shader_program);
-
-            // Set projection matrix
-            let projection_loc = gl::GetUniformLocation(
-                self.shader_program,
-                b"projection\0".as_ptr() as *const _,
-            );
-            gl::UniformMatrix4fv(
-                projection_loc,
-                1,
-                gl::FALSE,
-                projection.to_cols_array().as_ptr(),
-            );
+        self.render_layers(tilemap, None, projection);
+    }
 
-            tilemap.tileset.bind_texture();
+    /// Draw with fog of war: visible tiles at full brightness, explored
+    /// ones darkened and hidden ones skipped.
+    pub fn render_with_visibility(
+        &mut self,
+        tilemap: &TileMap,
+        visibility: &VisibilityMap,
+        projection: &Mat4,
+    ) {
+        self.render_layers(tilemap, Some(visibility), projection);
+    }
 
-            // Render each layer
-            for (_, layer) in &tilemap.layers {
-                if !layer.visible {
-                    continue;
-                }
+    fn render_layers(
+        &mut self,
+        tilemap: &TileMap,
+        visibility: Option<&VisibilityMap>,
+        projection: &Mat4,
+    ) {
+        // Render each layer
+        for layer in tilemap.layers.values() {
+            if !layer.visible {
+                continue;
+            }
+            self.use_shader(layer.material.as_ref(), projection);
+            self.render_layer(tilemap, layer, visibility);
+        }
 
-                self.render_layer(tilemap, layer);
+        // Then the resident chunks of the unbounded layers
+        for layer in tilemap.chunked_layers.values() {
+            if !layer.visible {
+                continue;
             }
+            self.use_shader(layer.material.as_ref(), projection);
+            self.render_chunked_layer(tilemap, layer);
+        }
+    }
+
+    fn use_shader(
+        &self,
+        material: Option<&Material>,
+        projection: &Mat4,
+    ) {
+        let shader =
+            material.map_or(&self.shader_program, |material| material.shader());
+        shader.use_program();
+        shader.set_mat4("projection", projection);
+        shader.set_int("image", 0);
+        if let Some(material) = material {
+            material.apply(1);
         }
     }
 
@@ -158,165 +245,182 @@ impl TileMapRenderer {
         &mut self,
         tilemap: &TileMap,
         layer: &TileLayer,
+        visibility: Option<&VisibilityMap>,
+    ) {
+        self.clear_batches(tilemap);
+        let height = layer.tiles.len() as u32;
+        let width = layer.tiles.first().map_or(0, |row| row.len() as u32);
+        let key = (tilemap.orientation, width, height);
+        if self.draw_order_key != Some(key) {
+            self.draw_order = tilemap.orientation.draw_order(width, height);
+            self.draw_order_key = Some(key);
+        }
+
+        let draw_order = std::mem::take(&mut self.draw_order);
+        for &(x, y) in &draw_order {
+            let Some(tile) = layer.get_tile(x, y) else {
+                continue;
+            };
+            let brightness = match visibility {
+                Some(visibility) => match visibility.brightness(x, y) {
+                    Some(brightness) => brightness,
+                    None => continue,
+                },
+                None => 1.0,
+            };
+            let world_pos = tilemap.tile_to_world(x, y);
+            self.push_tile(tilemap, &tile, world_pos, brightness);
+        }
+        self.draw_order = draw_order;
+        self.draw_batches(tilemap);
+    }
+
+    fn render_chunked_layer(
+        &mut self,
+        tilemap: &TileMap,
+        layer: &ChunkedLayer,
     ) {
-        self.instance_data.clear();
-        let mut instance_count = 0;
-        let scaled_tile_size = tilemap.tile_size as f32 * tilemap.scale;
-
-        // Render only visible tiles (viewport culling)
-        for y in 0..tilemap.height {
-            for x in 0..tilemap.width {
-                if let Some(tile) = layer.get_tile(x, y) {
-                    if instance_count >= self.max_instances {
-                        self.flush_batch();
-                        instance_count = 0;
-                    }
-
-                    let world_pos = tilemap.tile_to_world(x, y);
-
-                    if let Some(uvs) = tilemap.tileset.get_tile_uvs(tile.id) {
-                        // Add instance data
-                        self.instance_data.extend_from_slice(&[
-                            world_pos.x,
-                            world_pos.y, // position
-                            scaled_tile_size,
-                            scaled_tile_size, // scale
-                            uvs[0].x,
-                            uvs[0].y, // UV 좌표
-                            uvs[2].x,
-                            uvs[2].y,
-                        ]);
-
-                        instance_count += 1;
-                    }
-                }
+        self.clear_batches(tilemap);
+        let mut chunk_tiles = std::mem::take(&mut self.chunk_tiles);
+        chunk_tiles.clear();
+        for (coord, chunk) in layer.resident_chunks() {
+            let (origin_x, origin_y) = layer.chunk_origin(coord);
+            for (x, y, tile) in chunk.iter() {
+                let world_pos = tilemap.tile_to_world_signed(
+                    origin_x + x as i32,
+                    origin_y + y as i32,
+                );
+                chunk_tiles.push((world_pos, tile));
             }
         }
+        // Cells further down overlap the ones above them in every
+        // orientation, so sorting by position draws back to front
+        if tilemap.orientation != Orientation::Orthogonal {
+            chunk_tiles.sort_unstable_by(|(a, _), (b, _)| {
+                a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x))
+            });
+        }
+        for (world_pos, tile) in &chunk_tiles {
+            self.push_tile(tilemap, tile, *world_pos, 1.0);
+        }
+        self.chunk_tiles = chunk_tiles;
+        self.draw_batches(tilemap);
+    }
 
-        if instance_count > 0 {
-            self.flush_batch();
+    // Bucket tiles by tileset so each texture is bound once per layer on
+    // orthogonal maps
+    fn clear_batches(
+        &mut self,
+        tilemap: &TileMap,
+    ) {
+        self.batches.resize_with(tilemap.tilesets.len(), Vec::new);
+        for batch in &mut self.batches {
+            batch.clear();
+        }
+        self.batch_tileset = None;
+    }
+
+    fn push_tile(
+        &mut self,
+        tilemap: &TileMap,
+        tile: &TileInstance,
+        world_pos: Vec2,
+        brightness: f32,
+    ) {
+        let Some(index) = tilemap.tileset_index(tile.id) else {
+            return;
+        };
+        let entry = &tilemap.tilesets[index];
+        let local_id = tile.id - entry.first_gid;
+        let Some(uvs) = entry.tileset.get_tile_uvs(local_id) else {
+            return;
+        };
+
+        // Keep the back to front order across tilesets where tiles overlap
+        if tilemap.orientation != Orientation::Orthogonal
+            && self.batch_tileset.is_some_and(|last| last != index)
+        {
+            self.draw_batches(tilemap);
+        }
+        self.batch_tileset = Some(index);
+
+        // Tile art may be taller than the grid cell; it stands on the
+        // cell's bottom edge like in Tiled
+        let cell_size = tilemap.world_tile_size();
+        let size = Vec2::new(
+            entry.tileset.tile_width() as f32,
+            entry.tileset.tile_height() as f32,
+        ) * tilemap.scale;
+        let position = world_pos + Vec2::new(0.0, cell_size.y - size.y);
+        self.batches[index].extend_from_slice(&[
+            position.x,
+            position.y, // position
+            size.x,
+            size.y, // scale
+            uvs[0].x,
+            uvs[0].y, // UV coordinates
+            uvs[2].x,
+            uvs[2].y,
+            brightness, // color
+            brightness,
+            brightness,
+            1.0,
+            f32::from(tile.flags.bits()), // flip flags
+        ]);
+    }
+
+    fn draw_batches(
+        &mut self,
+        tilemap: &TileMap,
+    ) {
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0);
+        }
+        let mut batches = std::mem::take(&mut self.batches);
+        for (entry, batch) in tilemap.tilesets.iter().zip(&mut batches) {
+            if batch.is_empty() {
+                continue;
+            }
+            entry.tileset.bind_texture();
+            for chunk in batch.chunks(self.max_instances * FLOATS_PER_INSTANCE)
+            {
+                self.flush_batch(chunk);
+            }
+            batch.clear();
         }
+        self.batches = batches;
     }
 
-    fn flush_batch(&self) {
+    fn flush_batch(
+        &self,
+        instance_data: &[f32],
+    ) {
         unsafe {
             gl::BindVertexArray(self.vao);
             gl::BindBuffer(gl::ARRAY_BUFFER, self.instance_vbo);
             gl::BufferSubData(
                 gl::ARRAY_BUFFER,
                 0,
-                (self.instance_data.len() * std::mem::size_of::<f32>())
-                    as GLsizeiptr,
-                self.instance_data.as_ptr() as *const _,
+                std::mem::size_of_val(instance_data) as GLsizeiptr,
+                instance_data.as_ptr() as *const _,
             );
 
             gl::DrawArraysInstanced(
                 gl::TRIANGLES,
                 0,
                 6, // 6 vertices per tile
-                (self.instance_data.len() / 8) as GLsizei, // instance count
+                (instance_data.len() / FLOATS_PER_INSTANCE) as GLsizei,
             );
         }
     }
 }
 
-const TILEMAP_VERTEX_SHADER: &str = r#"
-    #version 330 core
-    layout (location = 0) in vec2 aPos;
-    layout (location = 1) in vec2 aTexCoords;
-    layout (location = 2) in vec4 aInstance;  // pos & scale
-    layout (location = 3) in vec4 aInstanceTexCoords;  // UV coordinates
-
-    out vec2 TexCoords;
-
-    uniform mat4 projection;
-
-    void main()
-    {
-        vec2 pos = aPos * aInstance.zw + aInstance.xy;
-        gl_Position = projection * vec4(pos, 0.0, 1.0);
-        
-        vec2 texPos = aTexCoords;
-        TexCoords = mix(aInstanceTexCoords.xy, aInstanceTexCoords.zw, texPos);
-    }
-"#;
-
-const TILEMAP_FRAGMENT_SHADER: &str = r#"
-    #version 330 core
-    in vec2 TexCoords;
-    out vec4 FragColor;
-
-    uniform sampler2D texture0;
-
-    void main()
-    {
-        FragColor = texture(texture0, TexCoords);
-    }
-"#;
-
-// 쉐이더 컴파일 헬퍼 함수
-fn compile_shader(
-    shader_type: GLenum,
-    source: &str,
-) -> Result<GLuint, String> {
-    unsafe {
-        let shader = gl::CreateShader(shader_type);
-        let c_str = std::ffi::CString::new(source.as_bytes()).unwrap();
-        gl::ShaderSource(shader, 1, &c_str.as_ptr(), std::ptr::null());
-        gl::CompileShader(shader);
-
-        let mut success = gl::FALSE as GLint;
-        gl::GetShaderiv(shader, gl::COMPILE_STATUS, &mut success);
-
-        if success != gl::TRUE as GLint {
-            let mut len = 0;
-            gl::GetShaderiv(shader, gl::INFO_LOG_LENGTH, &mut len);
-            let mut buffer = Vec::with_capacity(len as usize);
-            buffer.set_len((len as usize) - 1);
-            gl::GetShaderInfoLog(
-                shader,
-                len,
-                std::ptr::null_mut(),
-                buffer.as_mut_ptr() as *mut _,
-            );
-            return Err(String::from_utf8(buffer).unwrap());
-        }
-
-        Ok(shader)
-    }
-}
-
-// 쉐이더 프로그램 링크 헬퍼 함수
-fn link_program(
-    vertex_shader: GLuint,
-    fragment_shader: GLuint,
-) -> Result<GLuint, String> {
-    unsafe {
-        let program = gl::CreateProgram();
-        gl::AttachShader(program, vertex_shader);
-        gl::AttachShader(program, fragment_shader);
-        gl::LinkProgram(program);
-
-        let mut success = gl::FALSE as GLint;
-        gl::GetProgramiv(program, gl::LINK_STATUS, &mut success);
-
-        if success != gl::TRUE as GLint {
-            let mut len = 0;
-            gl::GetProgramiv(program, gl::INFO_LOG_LENGTH, &mut len);
-            let mut buffer = Vec::with_capacity(len as usize);
-            buffer.set_len((len as usize) - 1);
-            gl::GetProgramInfoLog(
-                program,
-                len,
-                std::ptr::null_mut(),
-                buffer.as_mut_ptr() as *mut _,
-            );
-            return Err(String::from_utf8(buffer).unwrap());
+impl Drop for TileMapRenderer {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteBuffers(1, &self.instance_vbo);
+            gl::DeleteBuffers(1, &self.vbo);
+            gl::DeleteVertexArrays(1, &self.vao);
         }
-
-        gl::DeleteShader(vertex_shader);
-        gl::DeleteShader(fragment_shader);
-
-        Ok(program)
     }
 }
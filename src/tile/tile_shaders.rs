@@ -0,0 +1,44 @@
+pub const TILEMAP_VERTEX_SHADER: &str = r#"
+    #version 330 core
+    layout (location = 0) in vec2 aPos;
+    layout (location = 1) in vec2 aTexCoords;
+    layout (location = 2) in vec4 aInstance;  // pos & scale
+    layout (location = 3) in vec4 aInstanceTexCoords;  // UV coordinates
+    layout (location = 4) in vec4 aInstanceColor;  // fog of war shade
+    layout (location = 5) in float aInstanceFlags;  // TileFlags bits
+
+    out vec2 TexCoords;
+    out vec4 Color;
+
+    uniform mat4 projection;
+
+    void main()
+    {
+        vec2 pos = aPos * aInstance.zw + aInstance.xy;
+        gl_Position = projection * vec4(pos, 0.0, 1.0);
+        
+        // Sample the image point drawn here, undoing the flips in reverse
+        // order: vertical, horizontal, then diagonal
+        int flags = int(aInstanceFlags + 0.5);
+        vec2 texPos = aTexCoords;
+        if ((flags & 2) != 0) texPos.y = 1.0 - texPos.y;
+        if ((flags & 1) != 0) texPos.x = 1.0 - texPos.x;
+        if ((flags & 4) != 0) texPos = texPos.yx;
+        TexCoords = mix(aInstanceTexCoords.xy, aInstanceTexCoords.zw, texPos);
+        Color = aInstanceColor;
+    }
+"#;
+
+pub const TILEMAP_FRAGMENT_SHADER: &str = r#"
+    #version 330 core
+    in vec2 TexCoords;
+    in vec4 Color;
+    out vec4 FragColor;
+
+    uniform sampler2D image;
+
+    void main()
+    {
+        FragColor = texture(image, TexCoords) * Color;
+    }
+"#;
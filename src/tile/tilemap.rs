@@ -1,28 +1,243 @@
 use glam::Vec2;
 use std::collections::HashMap;
 
-use super::{TileProperties, Tileset};
+use super::chunked::ChunkedLayer;
+use super::damage::TileDamage;
+use super::orientation::Orientation;
+use super::procgen::AdjacencyRules;
+use super::{TerrainRules, TileProperties, TileType, Tileset};
+use crate::shader::Material;
 
+/// Grid of tile layers drawn from one or more tilesets.
+///
+/// Tile IDs stored in layers are global: each tileset covers the range
+/// starting at its `first_gid`, like Tiled's `firstgid`. A map created
+/// with [`TileMap::new`] has its first tileset at 0, so IDs of a single
+/// tileset map stay local IDs.
 #[derive(Debug)]
 pub struct TileMap {
     pub width: u32,
     pub height: u32,
-    pub tile_size: u32,
+    /// Grid cell size in pixels. Tile images taller than the cell, as in
+    /// isometric maps, extend upwards from its bottom.
+    pub tile_width: u32,
+    pub tile_height: u32,
     pub scale: f32,
-    pub tileset: Tileset,
+    /// How the grid is laid out. Path finding and field of view work on
+    /// tile coordinates; raycasts and line of sight need an orthogonal or
+    /// isometric grid.
+    pub orientation: Orientation,
+    /// Tilesets in ascending `first_gid` order.
+    pub tilesets: Vec<MapTileset>,
     pub layers: HashMap<String, TileLayer>,
+    /// Unbounded layers streamed around the view, see
+    /// [`TileMap::update_chunks`]. Only the renderer draws them; path
+    /// finding, visibility and the tile queries cover `layers` only.
+    pub chunked_layers: HashMap<String, ChunkedLayer>,
+    layers_revision: u64,
+    /// Properties of tiles their tileset defines none for.
+    default_properties: TileProperties,
+}
+
+/// Tileset of a map and the first global tile ID it covers.
+#[derive(Debug)]
+pub struct MapTileset {
+    pub first_gid: u32,
+    pub tileset: Tileset,
+}
+
+impl MapTileset {
+    /// Whether `gid` falls inside this tileset's range.
+    pub fn contains(
+        &self,
+        gid: u32,
+    ) -> bool {
+        gid >= self.first_gid
+            && gid - self.first_gid < self.tileset.tile_count().max(1)
+    }
 }
 
 #[derive(Debug)]
 pub struct TileLayer {
     pub visible: bool,
     pub tiles: Vec<Vec<Option<TileInstance>>>,
+    /// Custom shader the layer is drawn with instead of the renderer's.
+    pub material: Option<Material>,
+    /// Autotiling rules applied by [`TileLayer::set_tile`] and
+    /// [`TileLayer::clear_tile`].
+    pub terrains: Vec<TerrainRules>,
+    /// Health of damaged tiles only, see [`TileMap::damage_tile`].
+    pub(crate) damage: HashMap<(u32, u32), TileDamage>,
+    /// Properties replacing the tileset's for single tiles.
+    overrides: HashMap<(u32, u32), TileProperties>,
+    revision: u64,
 }
 
-#[derive(Debug, Clone)]
+/// Tile placed in a layer: its global ID and how it is flipped.
+///
+/// Instances are small and `Copy`; their properties are looked up in the
+/// tileset, see [`TileMap::properties_in`], unless the layer overrides
+/// them for that position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct TileInstance {
+    /// Global tile ID, see [`TileMap`].
     pub id: u32,
-    pub properties: TileProperties,
+    pub flags: TileFlags,
+}
+
+impl TileInstance {
+    pub const fn new(id: u32) -> Self {
+        Self {
+            id,
+            flags: TileFlags::NONE,
+        }
+    }
+
+    pub const fn with_flags(
+        mut self,
+        flags: TileFlags,
+    ) -> Self {
+        self.flags = flags;
+        self
+    }
+}
+
+/// Flip bits of a tile instance, matching Tiled's. A diagonal flip swaps
+/// the x and y axes; combined with the other flips it rotates the tile
+/// in 90 degree steps.
+///
+/// ```
+/// use glam::Vec2;
+/// use teengine::tile::TileFlags;
+///
+/// // A quarter turn clockwise is a diagonal and a horizontal flip
+/// let turned = TileFlags::NONE.rotated_clockwise();
+/// assert_eq!(turned, TileFlags::FLIP_DIAGONAL | TileFlags::FLIP_HORIZONTAL);
+/// assert_eq!(turned.transform_point(Vec2::ZERO), Vec2::new(1.0, 0.0));
+///
+/// let upside_down = turned.rotated_clockwise();
+/// assert_eq!(upside_down.rotated_counterclockwise(), turned);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct TileFlags(u8);
+
+impl TileFlags {
+    pub const NONE: Self = Self(0);
+    pub const FLIP_HORIZONTAL: Self = Self(1);
+    pub const FLIP_VERTICAL: Self = Self(2);
+    pub const FLIP_DIAGONAL: Self = Self(4);
+
+    pub const fn from_bits(bits: u8) -> Self {
+        Self(bits & 0b111)
+    }
+
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+
+    pub const fn contains(
+        self,
+        other: Self,
+    ) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Flags of the same tile turned a quarter clockwise.
+    pub const fn rotated_clockwise(self) -> Self {
+        let (h, v, d) = self.parts();
+        Self::from_parts(!v, h, !d)
+    }
+
+    /// Flags of the same tile turned a quarter counterclockwise.
+    pub const fn rotated_counterclockwise(self) -> Self {
+        let (h, v, d) = self.parts();
+        Self::from_parts(v, !h, !d)
+    }
+
+    /// Flags of the same tile mirrored left to right.
+    pub const fn flipped_horizontally(self) -> Self {
+        Self(self.0 ^ Self::FLIP_HORIZONTAL.0)
+    }
+
+    /// Flags of the same tile mirrored top to bottom.
+    pub const fn flipped_vertically(self) -> Self {
+        Self(self.0 ^ Self::FLIP_VERTICAL.0)
+    }
+
+    /// Where `point` of the tile image ends up when the tile is drawn with
+    /// these flags. Points are in tile units, (0, 0) at the top left and
+    /// (1, 1) at the bottom right. Like Tiled, the diagonal flip comes
+    /// first, then the horizontal and vertical ones.
+    pub fn transform_point(
+        self,
+        point: Vec2,
+    ) -> Vec2 {
+        let mut point = point;
+        if self.contains(Self::FLIP_DIAGONAL) {
+            point = Vec2::new(point.y, point.x);
+        }
+        if self.contains(Self::FLIP_HORIZONTAL) {
+            point.x = 1.0 - point.x;
+        }
+        if self.contains(Self::FLIP_VERTICAL) {
+            point.y = 1.0 - point.y;
+        }
+        point
+    }
+
+    /// Point of the tile image drawn at `point` of the tile, the inverse
+    /// of [`TileFlags::transform_point`].
+    pub fn inverse_transform_point(
+        self,
+        point: Vec2,
+    ) -> Vec2 {
+        let mut point = point;
+        if self.contains(Self::FLIP_VERTICAL) {
+            point.y = 1.0 - point.y;
+        }
+        if self.contains(Self::FLIP_HORIZONTAL) {
+            point.x = 1.0 - point.x;
+        }
+        if self.contains(Self::FLIP_DIAGONAL) {
+            point = Vec2::new(point.y, point.x);
+        }
+        point
+    }
+
+    const fn parts(self) -> (bool, bool, bool) {
+        (
+            self.contains(Self::FLIP_HORIZONTAL),
+            self.contains(Self::FLIP_VERTICAL),
+            self.contains(Self::FLIP_DIAGONAL),
+        )
+    }
+
+    const fn from_parts(
+        horizontal: bool,
+        vertical: bool,
+        diagonal: bool,
+    ) -> Self {
+        Self(
+            (horizontal as u8 * Self::FLIP_HORIZONTAL.0)
+                | (vertical as u8 * Self::FLIP_VERTICAL.0)
+                | (diagonal as u8 * Self::FLIP_DIAGONAL.0),
+        )
+    }
+}
+
+impl std::ops::BitOr for TileFlags {
+    type Output = Self;
+
+    fn bitor(
+        self,
+        other: Self,
+    ) -> Self {
+        Self(self.0 | other.0)
+    }
 }
 
 impl TileMap {
@@ -36,11 +251,237 @@ impl TileMap {
         Self {
             width,
             height,
-            tile_size,
+            tile_width: tile_size,
+            tile_height: tile_size,
             scale,
+            orientation: Orientation::Orthogonal,
             layers: HashMap::new(),
-            tileset,
+            chunked_layers: HashMap::new(),
+            layers_revision: 0,
+            default_properties: TileProperties::new_default(),
+            tilesets: vec![MapTileset {
+                first_gid: 0,
+                tileset,
+            }],
+        }
+    }
+
+    pub fn with_orientation(
+        mut self,
+        orientation: Orientation,
+    ) -> Self {
+        self.orientation = orientation;
+        self
+    }
+
+    /// Use a grid of `tile_width` by `tile_height` pixel cells instead of
+    /// the square cells passed to [`TileMap::new`].
+    pub fn with_tile_dimensions(
+        mut self,
+        tile_width: u32,
+        tile_height: u32,
+    ) -> Self {
+        self.tile_width = tile_width;
+        self.tile_height = tile_height;
+        self
+    }
+
+    /// Add a tileset after the last one and return its first global ID.
+    pub fn add_tileset(
+        &mut self,
+        tileset: Tileset,
+    ) -> u32 {
+        let first_gid = self.tilesets.last().map_or(0, |last| {
+            last.first_gid + last.tileset.tile_count().max(1)
+        });
+        self.tilesets.push(MapTileset { first_gid, tileset });
+        first_gid
+    }
+
+    /// Add a tileset at a fixed first global ID, e.g. from a Tiled map.
+    /// Fails if its range overlaps a tileset already in the map.
+    pub fn add_tileset_at(
+        &mut self,
+        first_gid: u32,
+        tileset: Tileset,
+    ) -> Result<(), String> {
+        let added = MapTileset { first_gid, tileset };
+        let Some(last_gid) =
+            first_gid.checked_add(added.tileset.tile_count().max(1) - 1)
+        else {
+            return Err(format!(
+                "Tileset {} at first gid {} runs past the largest gid",
+                added.tileset.name(),
+                first_gid
+            ));
+        };
+        if let Some(other) = self.tilesets.iter().find(|other| {
+            other.contains(first_gid)
+                || other.contains(last_gid)
+                || added.contains(other.first_gid)
+        }) {
+            return Err(format!(
+                "Tileset {} at first gid {} overlaps tileset {} at {}",
+                added.tileset.name(),
+                first_gid,
+                other.tileset.name(),
+                other.first_gid
+            ));
+        }
+
+        let index = self
+            .tilesets
+            .partition_point(|other| other.first_gid < first_gid);
+        self.tilesets.insert(index, added);
+        Ok(())
+    }
+
+    /// Check that no two tilesets' gid ranges overlap, e.g. after a
+    /// reload changed a tileset's tile count.
+    pub fn check_tileset_ranges(&self) -> Result<(), String> {
+        for pair in self.tilesets.windows(2) {
+            let (entry, next) = (&pair[0], &pair[1]);
+            let end = entry.first_gid as u64
+                + entry.tileset.tile_count().max(1) as u64;
+            if end > next.first_gid as u64 {
+                return Err(format!(
+                    "Tileset {} at first gid {} overlaps tileset {} at {}",
+                    entry.tileset.name(),
+                    entry.first_gid,
+                    next.tileset.name(),
+                    next.first_gid
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// The first tileset, for maps that only use one.
+    pub fn tileset(&self) -> &Tileset {
+        &self.tilesets[0].tileset
+    }
+
+    pub fn tileset_mut(&mut self) -> &mut Tileset {
+        &mut self.tilesets[0].tileset
+    }
+
+    /// Index into `tilesets` of the tileset covering `gid`.
+    pub fn tileset_index(
+        &self,
+        gid: u32,
+    ) -> Option<usize> {
+        let index = self
+            .tilesets
+            .partition_point(|tileset| tileset.first_gid <= gid)
+            .checked_sub(1)?;
+        self.tilesets[index].contains(gid).then_some(index)
+    }
+
+    /// Tileset covering `gid` and the tile's local ID inside it.
+    pub fn resolve_gid(
+        &self,
+        gid: u32,
+    ) -> Option<(&Tileset, u32)> {
+        let entry = &self.tilesets[self.tileset_index(gid)?];
+        Some((&entry.tileset, gid - entry.first_gid))
+    }
+
+    /// Global ID of `local_id` in the tileset at `tileset_index`.
+    pub fn gid(
+        &self,
+        tileset_index: usize,
+        local_id: u32,
+    ) -> Option<u32> {
+        let entry = self.tilesets.get(tileset_index)?;
+        (local_id < entry.tileset.tile_count().max(1))
+            .then_some(entry.first_gid + local_id)
+    }
+
+    /// Properties defined in the tileset for the tile `gid`.
+    pub fn tile_properties(
+        &self,
+        gid: u32,
+    ) -> Option<&TileProperties> {
+        let (tileset, local_id) = self.resolve_gid(gid)?;
+        tileset.get_tile_properties(local_id)
+    }
+
+    /// Properties of the tile `gid`, or default properties if its tileset
+    /// defines none.
+    pub fn properties_of(
+        &self,
+        gid: u32,
+    ) -> &TileProperties {
+        self.tile_properties(gid)
+            .unwrap_or(&self.default_properties)
+    }
+
+    /// Properties of the tile at (`x`, `y`) of `layer`: the layer's
+    /// override for that position if it has one, else those of the tile's
+    /// ID. `None` if the cell is empty.
+    pub fn properties_in<'a>(
+        &'a self,
+        layer: &'a TileLayer,
+        x: u32,
+        y: u32,
+    ) -> Option<&'a TileProperties> {
+        let tile = layer.tiles.get(y as usize)?.get(x as usize)?.as_ref()?;
+        Some(
+            layer
+                .overrides
+                .get(&(x, y))
+                .unwrap_or_else(|| self.properties_of(tile.id)),
+        )
+    }
+
+    /// Like [`TileMap::properties_in`] for the layer named `layer_name`.
+    pub fn properties_at(
+        &self,
+        layer_name: &str,
+        x: u32,
+        y: u32,
+    ) -> Option<&TileProperties> {
+        self.properties_in(self.layers.get(layer_name)?, x, y)
+    }
+
+    /// Unflipped instance of the tile `gid`.
+    pub fn tile_instance(
+        &self,
+        gid: u32,
+    ) -> TileInstance {
+        TileInstance::new(gid)
+    }
+
+    /// Terrain set `name` from whichever tileset defines it, resolved to
+    /// global IDs.
+    pub fn terrain_rules(
+        &self,
+        name: &str,
+    ) -> Option<TerrainRules> {
+        self.tilesets.iter().find_map(|entry| {
+            entry.tileset.terrain_rules(name, entry.first_gid)
+        })
+    }
+
+    /// Adjacency rules of every tileset, for wave function collapse.
+    pub fn adjacency_rules(&self) -> AdjacencyRules {
+        let mut rules = AdjacencyRules::new();
+        for entry in &self.tilesets {
+            rules.extend(entry.tileset.adjacency_rules(entry.first_gid));
         }
+        rules
+    }
+
+    /// Instance of the first tile with `tile_type` in its properties,
+    /// e.g. to map `NoiseTerrain` output onto tiles.
+    pub fn tile_of_type(
+        &self,
+        tile_type: &TileType,
+    ) -> Option<TileInstance> {
+        self.tilesets.iter().find_map(|entry| {
+            let local_id = entry.tileset.find_tile_type(tile_type)?;
+            Some(self.tile_instance(entry.first_gid + local_id))
+        })
     }
 
     pub fn add_layer(
@@ -48,7 +489,89 @@ impl TileMap {
         name: String,
         layer: TileLayer,
     ) {
-        self.layers.insert(name, layer);
+        // Carry over the edits of a replaced layer so the map revision
+        // never goes back to an earlier value
+        if let Some(replaced) = self.layers.insert(name, layer) {
+            self.layers_revision =
+                self.layers_revision.wrapping_add(replaced.revision);
+        }
+        self.layers_revision = self.layers_revision.wrapping_add(1);
+    }
+
+    pub fn remove_layer(
+        &mut self,
+        name: &str,
+    ) -> Option<TileLayer> {
+        let removed = self.layers.remove(name);
+        if let Some(removed) = &removed {
+            self.layers_revision =
+                self.layers_revision.wrapping_add(removed.revision);
+        }
+        self.layers_revision = self.layers_revision.wrapping_add(1);
+        removed
+    }
+
+    pub fn add_chunked_layer(
+        &mut self,
+        name: String,
+        layer: ChunkedLayer,
+    ) {
+        self.chunked_layers.insert(name, layer);
+    }
+
+    pub fn remove_chunked_layer(
+        &mut self,
+        name: &str,
+    ) -> Option<ChunkedLayer> {
+        self.chunked_layers.remove(name)
+    }
+
+    /// Stream the chunks of every chunked layer around the world
+    /// rectangle from `view_min` to `view_max`, e.g. the camera's
+    /// `visible_rect`. Call it whenever the view moves.
+    pub fn update_chunks(
+        &mut self,
+        view_min: Vec2,
+        view_max: Vec2,
+    ) -> Result<(), String> {
+        // Rotated grids such as isometric ones reach their extreme tiles
+        // at different corners of the view, so bound all four
+        let corners = [
+            view_min,
+            Vec2::new(view_max.x, view_min.y),
+            Vec2::new(view_min.x, view_max.y),
+            view_max,
+        ]
+        .map(|corner| self.world_to_tile_signed(corner));
+        let min = corners.iter().fold((i32::MAX, i32::MAX), |min, tile| {
+            (min.0.min(tile.0), min.1.min(tile.1))
+        });
+        let max = corners.iter().fold((i32::MIN, i32::MIN), |max, tile| {
+            (max.0.max(tile.0), max.1.max(tile.1))
+        });
+        for layer in self.chunked_layers.values_mut() {
+            layer.stream(min, max)?;
+        }
+        Ok(())
+    }
+
+    /// Record a change to the map that no layer method saw, e.g. tileset
+    /// properties changed by a reload, so caches keyed on
+    /// [`TileMap::revision`] are rebuilt.
+    pub fn mark_changed(&mut self) {
+        self.layers_revision = self.layers_revision.wrapping_add(1);
+    }
+
+    /// Counter that grows whenever tiles change through the layer
+    /// methods, layers are added and removed or the map is marked
+    /// changed. Caches built from the map, such as path finders, compare
+    /// it to know when to rebuild.
+    pub fn revision(&self) -> u64 {
+        self.layers
+            .values()
+            .fold(self.layers_revision, |sum, layer| {
+                sum.wrapping_add(layer.revision)
+            })
     }
 
     pub fn get_tile_at(
@@ -64,32 +587,89 @@ impl TileMap {
             .and_then(|tile| tile.as_ref())
     }
 
+    /// Size of one grid cell in world units.
+    pub fn world_tile_size(&self) -> Vec2 {
+        Vec2::new(self.tile_width as f32, self.tile_height as f32) * self.scale
+    }
+
+    /// Tile containing `world_pos`, clamped to zero on the negative side,
+    /// so positions left of or above the map land on its first row or
+    /// column.
+    #[deprecated(
+        note = "use `world_to_tile_checked` or `world_to_tile_signed`, which \
+                do not clamp positions outside the map"
+    )]
     pub fn world_to_tile(
         &self,
         world_pos: Vec2,
     ) -> (u32, u32) {
-        (
-            (world_pos.x / (self.tile_size as f32 * self.scale)) as u32,
-            (world_pos.y / (self.tile_size as f32 * self.scale)) as u32,
-        )
+        let (x, y) = self.world_to_tile_signed(world_pos);
+        (x.max(0) as u32, y.max(0) as u32)
     }
 
+    /// Tile containing `world_pos`, negative left of or above the map.
+    pub fn world_to_tile_signed(
+        &self,
+        world_pos: Vec2,
+    ) -> (i32, i32) {
+        self.orientation
+            .world_to_tile(world_pos, self.world_tile_size())
+    }
+
+    /// Tile containing `world_pos`, or `None` outside the map.
+    pub fn world_to_tile_checked(
+        &self,
+        world_pos: Vec2,
+    ) -> Option<(u32, u32)> {
+        let (x, y) = self.world_to_tile_signed(world_pos);
+        self.contains_tile(x, y).then_some((x as u32, y as u32))
+    }
+
+    /// Whether the signed tile coordinate lies inside the map.
+    pub fn contains_tile(
+        &self,
+        x: i32,
+        y: i32,
+    ) -> bool {
+        x >= 0 && y >= 0 && (x as u32) < self.width && (y as u32) < self.height
+    }
+
+    /// Top left corner of the tile's grid cell.
     pub fn tile_to_world(
         &self,
         tile_x: u32,
         tile_y: u32,
     ) -> Vec2 {
-        Vec2::new(
-            tile_x as f32 * self.tile_size as f32 * self.scale,
-            tile_y as f32 * self.tile_size as f32 * self.scale,
-        )
+        self.tile_to_world_signed(tile_x as i32, tile_y as i32)
+    }
+
+    /// Top left corner of a tile that may lie outside the map.
+    pub fn tile_to_world_signed(
+        &self,
+        tile_x: i32,
+        tile_y: i32,
+    ) -> Vec2 {
+        self.orientation
+            .tile_to_world(tile_x, tile_y, self.world_tile_size())
+    }
+
+    /// Center of the tile's grid cell.
+    pub fn tile_center(
+        &self,
+        tile_x: i32,
+        tile_y: i32,
+    ) -> Vec2 {
+        self.orientation
+            .tile_center(tile_x, tile_y, self.world_tile_size())
     }
 
     pub fn update(
         &mut self,
         delta_time: f32,
     ) {
-        self.tileset.update_animations(delta_time);
+        for entry in &mut self.tilesets {
+            entry.tileset.update_animations(delta_time);
+        }
     }
 }
 
@@ -103,27 +683,134 @@ impl TileLayer {
         Self {
             visible: true,
             tiles,
+            material: None,
+            terrains: Vec::new(),
+            damage: HashMap::new(),
+            overrides: HashMap::new(),
+            revision: 0,
         }
     }
 
+    pub fn with_terrain(
+        mut self,
+        rules: TerrainRules,
+    ) -> Self {
+        self.add_terrain(rules);
+        self
+    }
+
+    /// Autotile the layer with `rules` from now on. Tiles of the set
+    /// already in the layer are recomputed.
+    pub fn add_terrain(
+        &mut self,
+        rules: TerrainRules,
+    ) {
+        rules.refresh(&mut self.tiles);
+        self.terrains.push(rules);
+        self.revision += 1;
+    }
+
+    /// Recompute every autotiled bitmask tile, e.g. after editing `tiles`
+    /// directly.
+    pub fn refresh_terrain(&mut self) {
+        for rules in &self.terrains {
+            rules.refresh(&mut self.tiles);
+        }
+        self.revision += 1;
+    }
+
+    /// Record a change made by editing `tiles` directly, so caches keyed
+    /// on [`TileMap::revision`] are rebuilt.
+    pub fn mark_changed(&mut self) {
+        self.revision += 1;
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
     pub fn get_tile(
         &self,
         x: u32,
         y: u32,
     ) -> Option<TileInstance> {
-        self.tiles.get(y as usize)?.get(x as usize)?.clone()
+        *self.tiles.get(y as usize)?.get(x as usize)?
+    }
+
+    /// Give the tile at (`x`, `y`) its own properties instead of its
+    /// tileset's, e.g. a chest that was opened. The override is dropped
+    /// when the tile is replaced or cleared.
+    pub fn set_properties_override(
+        &mut self,
+        x: u32,
+        y: u32,
+        properties: TileProperties,
+    ) {
+        self.overrides.insert((x, y), properties);
+        self.revision += 1;
+    }
+
+    pub fn remove_properties_override(
+        &mut self,
+        x: u32,
+        y: u32,
+    ) -> Option<TileProperties> {
+        let removed = self.overrides.remove(&(x, y));
+        if removed.is_some() {
+            self.revision += 1;
+        }
+        removed
     }
 
+    pub fn properties_override(
+        &self,
+        x: u32,
+        y: u32,
+    ) -> Option<&TileProperties> {
+        self.overrides.get(&(x, y))
+    }
+
+    /// Place `tile` at (`x`, `y`). If it belongs to a terrain set of the
+    /// layer, it and its neighbours are swapped for the tiles matching
+    /// their new surroundings.
     pub fn set_tile(
         &mut self,
         x: u32,
         y: u32,
         tile: TileInstance,
     ) {
-        if let Some(row) = self.tiles.get_mut(y as usize) {
-            if let Some(cell) = row.get_mut(x as usize) {
-                *cell = Some(tile);
-            }
+        self.replace_tile(x, y, Some(tile));
+    }
+
+    /// Remove the tile at (`x`, `y`), updating autotiled neighbours.
+    pub fn clear_tile(
+        &mut self,
+        x: u32,
+        y: u32,
+    ) {
+        self.replace_tile(x, y, None);
+    }
+
+    pub(crate) fn replace_tile(
+        &mut self,
+        x: u32,
+        y: u32,
+        tile: Option<TileInstance>,
+    ) {
+        let Some(cell) = self
+            .tiles
+            .get_mut(y as usize)
+            .and_then(|row| row.get_mut(x as usize))
+        else {
+            return;
+        };
+        let previous = std::mem::replace(cell, tile).map(|tile| tile.id);
+        self.damage.remove(&(x, y));
+        self.overrides.remove(&(x, y));
+
+        for rules in &self.terrains {
+            rules.update_around(&mut self.tiles, x, y, previous);
         }
+        self.revision += 1;
     }
 }
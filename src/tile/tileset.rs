@@ -1,10 +1,14 @@
+use crate::texture::atlas::{AtlasRegion, TextureAtlas};
+use crate::texture::options::{BakedColorKey, TextureOptions};
 use crate::Texture;
 
+use super::autotile::{TerrainRules, TerrainSetData};
+use super::procgen::AdjacencyRules;
 use super::properties::*;
 use glam::Vec2;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TilesetData {
@@ -14,7 +18,14 @@ pub struct TilesetData {
     pub tile_height: u32,
     pub spacing: u32,
     pub margin: u32,
+    /// Hex color made transparent when the image is loaded, e.g.
+    /// `"#FF00FF"`.
+    #[serde(default)]
+    pub color_key: Option<String>,
     pub tiles: HashMap<u32, TileData>,
+    /// Autotiling rule sets, see [`TerrainSetData`].
+    #[serde(default)]
+    pub terrain_sets: Vec<TerrainSetData>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,6 +33,45 @@ pub struct TileData {
     pub id: u32,
     pub properties: TileProperties,
     pub animation: Option<AnimationData>,
+    /// Edge sockets for wave function collapse.
+    #[serde(default)]
+    pub adjacency: Option<AdjacencyData>,
+    /// Tiles swapped in as the tile takes damage, if it has `health`.
+    #[serde(default)]
+    pub destruction: Option<DestructionData>,
+}
+
+/// Sockets on a tile's north, east, south and west edges. Tiles fit next
+/// to each other when the sockets on the shared edge match.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdjacencyData {
+    pub sockets: [String; 4],
+    #[serde(default = "default_weight")]
+    pub weight: f32,
+}
+
+fn default_weight() -> f32 {
+    1.0
+}
+
+/// What a destructible tile turns into while it is damaged and once it is
+/// destroyed. Tile IDs are local to the tileset.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DestructionData {
+    #[serde(default)]
+    pub stages: Vec<DamageStage>,
+    /// Tile left behind when health reaches zero. The cell is cleared if
+    /// unset.
+    #[serde(default)]
+    pub destroyed_tile: Option<u32>,
+}
+
+/// Swap to `tile_id` once health drops to `below` times the maximum,
+/// e.g. 0.5 for a cracked wall at half health.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DamageStage {
+    pub below: f32,
+    pub tile_id: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,10 +88,13 @@ pub struct AnimationFrame {
 
 #[derive(Debug)]
 pub struct Tileset {
+    source_path: PathBuf,
     texture: Texture,
+    atlas_region: Option<AtlasRegion>,
     tile_data: TilesetData,
     tile_uvs: HashMap<u32, [Vec2; 4]>,
     animated_tiles: HashMap<u32, AnimatedTile>,
+    tile_count: u32,
 }
 
 #[derive(Debug)]
@@ -53,51 +106,171 @@ struct AnimatedTile {
 
 impl Tileset {
     pub fn new(tileset_path: &Path) -> Result<Self, String> {
-        let tileset_file = std::fs::File::open(tileset_path)
-            .map_err(|e| format!("Failed to open tileset file: {}", e))?;
+        let tile_data = Self::load_data(tileset_path)?;
 
-        let tile_data: TilesetData = serde_json::from_reader(tileset_file)
-            .map_err(|e| format!("Failed to parse tileset data: {}", e))?;
+        let texture = Self::load_texture(&tile_data)?;
+
+        let mut tile_uvs = HashMap::new();
+        let mut animated_tiles = HashMap::new();
+
+        let tile_count = Self::calculate_tile_uvs(
+            &tile_data,
+            &mut tile_uvs,
+            &mut animated_tiles,
+            Self::full_region(&texture),
+            texture.width(),
+            texture.height(),
+        );
+
+        Ok(Self {
+            source_path: tileset_path.to_path_buf(),
+            texture,
+            atlas_region: None,
+            tile_data,
+            tile_uvs,
+            animated_tiles,
+            tile_count,
+        })
+    }
 
-        let texture_path = Path::new(&tile_data.image_path);
-        let texture = Texture::new(texture_path)?;
+    /// Load a tileset whose image was packed into `atlas` under
+    /// `region_name`. The `image_path` in the JSON is ignored.
+    pub fn from_atlas(
+        tileset_path: &Path,
+        atlas: &TextureAtlas,
+        region_name: &str,
+    ) -> Result<Self, String> {
+        let tile_data = Self::load_data(tileset_path)?;
+        let region = *atlas.region(region_name).ok_or_else(|| {
+            format!("Atlas has no image named {}", region_name)
+        })?;
+        let texture = atlas.texture().clone();
 
         let mut tile_uvs = HashMap::new();
         let mut animated_tiles = HashMap::new();
 
-        Self::calculate_tile_uvs(
+        let tile_count = Self::calculate_tile_uvs(
             &tile_data,
             &mut tile_uvs,
             &mut animated_tiles,
+            region,
             texture.width(),
             texture.height(),
         );
 
         Ok(Self {
+            source_path: tileset_path.to_path_buf(),
             texture,
+            atlas_region: Some(region),
             tile_data,
             tile_uvs,
             animated_tiles,
+            tile_count,
         })
     }
 
+    /// Re-read the tileset JSON and rebuild UVs and animations in place.
+    /// If anything fails the tileset is left untouched.
+    pub fn reload(&mut self) -> Result<(), String> {
+        *self = self.reloaded()?;
+        Ok(())
+    }
+
+    /// Re-read the tileset JSON into a new tileset, leaving this one as it
+    /// is, e.g. to validate the result before swapping it in.
+    ///
+    /// The texture handle is shared and reloaded in place unless the JSON
+    /// now points at a different image. Tilesets packed into an atlas keep
+    /// using the atlas texture.
+    pub fn reloaded(&self) -> Result<Self, String> {
+        let tile_data = Self::load_data(&self.source_path)?;
+
+        let texture = if self.atlas_region.is_some() {
+            self.texture.clone()
+        } else if tile_data.image_path == self.tile_data.image_path
+            && tile_data.color_key == self.tile_data.color_key
+        {
+            self.texture.reload()?;
+            self.texture.clone()
+        } else {
+            Self::load_texture(&tile_data)?
+        };
+        let region = self
+            .atlas_region
+            .unwrap_or_else(|| Self::full_region(&texture));
+
+        let mut tile_uvs = HashMap::new();
+        let mut animated_tiles = HashMap::new();
+
+        let tile_count = Self::calculate_tile_uvs(
+            &tile_data,
+            &mut tile_uvs,
+            &mut animated_tiles,
+            region,
+            texture.width(),
+            texture.height(),
+        );
+
+        Ok(Self {
+            source_path: self.source_path.clone(),
+            texture,
+            atlas_region: self.atlas_region,
+            tile_data,
+            tile_uvs,
+            animated_tiles,
+            tile_count,
+        })
+    }
+
+    fn load_data(tileset_path: &Path) -> Result<TilesetData, String> {
+        let tileset_file = std::fs::File::open(tileset_path)
+            .map_err(|e| format!("Failed to open tileset file: {}", e))?;
+
+        let tile_data: TilesetData = serde_json::from_reader(tileset_file)
+            .map_err(|e| format!("Failed to parse tileset data: {}", e))?;
+
+        if tile_data.tile_width == 0 || tile_data.tile_height == 0 {
+            return Err("Tileset tile size must be non-zero".to_string());
+        }
+
+        Ok(tile_data)
+    }
+
+    fn full_region(texture: &Texture) -> AtlasRegion {
+        AtlasRegion {
+            x: 0,
+            y: 0,
+            width: texture.width(),
+            height: texture.height(),
+        }
+    }
+
     fn calculate_tile_uvs(
         tile_data: &TilesetData,
         tile_uvs: &mut HashMap<u32, [Vec2; 4]>,
         animated_tiles: &mut HashMap<u32, AnimatedTile>,
+        image_region: AtlasRegion,
         texture_width: u32,
         texture_height: u32,
-    ) {
-        let cols = (texture_width - tile_data.margin * 2 + tile_data.spacing)
-            / (tile_data.tile_width + tile_data.spacing);
+    ) -> u32 {
+        let cols = ((image_region.width + tile_data.spacing)
+            .saturating_sub(tile_data.margin * 2)
+            / (tile_data.tile_width + tile_data.spacing))
+            .max(1);
+        let rows = (image_region.height + tile_data.spacing)
+            .saturating_sub(tile_data.margin * 2)
+            / (tile_data.tile_height + tile_data.spacing);
+        let max_listed = tile_data.tiles.keys().max().map_or(0, |id| id + 1);
 
         for (id, data) in &tile_data.tiles {
             let tile_col = id % cols;
             let tile_row = id / cols;
 
-            let x = tile_data.margin
+            let x = image_region.x
+                + tile_data.margin
                 + tile_col * (tile_data.tile_width + tile_data.spacing);
-            let y = tile_data.margin
+            let y = image_region.y
+                + tile_data.margin
                 + tile_row * (tile_data.tile_height + tile_data.spacing);
 
             let u1 = x as f32 / texture_width as f32;
@@ -126,6 +299,19 @@ impl Tileset {
                 );
             }
         }
+
+        (cols * rows).max(max_listed)
+    }
+
+    fn load_texture(tile_data: &TilesetData) -> Result<Texture, String> {
+        let mut options = TextureOptions::default();
+        if let Some(hex) = &tile_data.color_key {
+            options = options.with_color_key(BakedColorKey::from_hex(
+                hex,
+                BakedColorKey::DEFAULT_TOLERANCE,
+            )?);
+        }
+        Texture::with_options(Path::new(&tile_data.image_path), options)
     }
 
     pub fn update_animations(
@@ -166,4 +352,102 @@ impl Tileset {
     pub fn bind_texture(&self) {
         self.texture.bind();
     }
+
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    pub fn name(&self) -> &str {
+        &self.tile_data.name
+    }
+
+    /// Width of one tile image in pixels.
+    pub fn tile_width(&self) -> u32 {
+        self.tile_data.tile_width
+    }
+
+    /// Height of one tile image in pixels, which may exceed the map's grid
+    /// height for tall isometric art.
+    pub fn tile_height(&self) -> u32 {
+        self.tile_data.tile_height
+    }
+
+    /// Number of tile IDs the tileset covers: every cell of the image grid,
+    /// or more if the JSON lists tiles beyond it.
+    pub fn tile_count(&self) -> u32 {
+        self.tile_count
+    }
+
+    pub fn terrain_sets(&self) -> &[TerrainSetData] {
+        &self.tile_data.terrain_sets
+    }
+
+    /// Terrain set `name` resolved for a map placing this tileset at
+    /// `first_gid`.
+    pub fn terrain_rules(
+        &self,
+        name: &str,
+        first_gid: u32,
+    ) -> Option<TerrainRules> {
+        let data = self
+            .tile_data
+            .terrain_sets
+            .iter()
+            .find(|set| set.name == name)?;
+        Some(TerrainRules::new(data, first_gid))
+    }
+
+    /// Adjacency of every tile with sockets, for a map placing this
+    /// tileset at `first_gid`.
+    pub fn adjacency_rules(
+        &self,
+        first_gid: u32,
+    ) -> AdjacencyRules {
+        let mut ids: Vec<&u32> = self.tile_data.tiles.keys().collect();
+        ids.sort();
+
+        let mut rules = AdjacencyRules::new();
+        for id in ids {
+            if let Some(adjacency) = &self.tile_data.tiles[id].adjacency {
+                rules.add_tile(
+                    first_gid + id,
+                    adjacency.weight,
+                    [
+                        &adjacency.sockets[0],
+                        &adjacency.sockets[1],
+                        &adjacency.sockets[2],
+                        &adjacency.sockets[3],
+                    ],
+                );
+            }
+        }
+        rules
+    }
+
+    pub fn destruction(
+        &self,
+        tile_id: u32,
+    ) -> Option<&DestructionData> {
+        self.tile_data.tiles.get(&tile_id)?.destruction.as_ref()
+    }
+
+    /// Lowest local ID whose properties have `tile_type`.
+    pub fn find_tile_type(
+        &self,
+        tile_type: &TileType,
+    ) -> Option<u32> {
+        self.tile_data
+            .tiles
+            .iter()
+            .filter(|(_, data)| {
+                data.properties.gameplay.tile_type == *tile_type
+            })
+            .map(|(&id, _)| id)
+            .min()
+    }
+
+    /// Path of the JSON file this tileset was loaded from.
+    pub fn source_path(&self) -> &Path {
+        &self.source_path
+    }
 }
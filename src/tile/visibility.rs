@@ -0,0 +1,370 @@
+use glam::Vec2;
+
+use super::procgen::Grid;
+use super::query::GridRay;
+use super::TileMap;
+
+// Octant transforms (xx, xy, yx, yy) for recursive shadowcasting
+const OCTANTS: [[i64; 4]; 8] = [
+    [1, 0, 0, 1],
+    [0, 1, 1, 0],
+    [0, -1, 1, 0],
+    [-1, 0, 0, 1],
+    [-1, 0, 0, -1],
+    [0, -1, -1, 0],
+    [0, 1, -1, 0],
+    [1, 0, 0, -1],
+];
+
+/// Tiles visible from `origin` within `radius` tiles, using recursive
+/// shadowcasting over `opacity`, where `true` blocks sight. Opaque tiles
+/// that are seen are visible themselves, so walls bordering a room show.
+///
+/// ```
+/// use teengine::tile::procgen::Grid;
+/// use teengine::tile::visibility::field_of_view;
+///
+/// // A pillar east of the viewer hides the tile behind it
+/// let mut opacity = Grid::new(7, 3, false);
+/// opacity.set(4, 1, true);
+/// let fov = field_of_view(&opacity, (2, 1), 8);
+/// assert_eq!(fov.get(4, 1), Some(&true));
+/// assert_eq!(fov.get(5, 1), Some(&false));
+/// assert_eq!(fov.get(0, 0), Some(&true));
+/// ```
+pub fn field_of_view(
+    opacity: &Grid<bool>,
+    origin: (u32, u32),
+    radius: u32,
+) -> Grid<bool> {
+    let mut caster = Shadowcaster {
+        opacity,
+        visible: Grid::new(opacity.width(), opacity.height(), false),
+        origin: (origin.0 as i64, origin.1 as i64),
+        radius: radius as i64,
+    };
+    caster.visible.set(origin.0, origin.1, true);
+    for transform in OCTANTS {
+        caster.cast(1, 1.0, 0.0, transform);
+    }
+    caster.visible
+}
+
+struct Shadowcaster<'a> {
+    opacity: &'a Grid<bool>,
+    visible: Grid<bool>,
+    origin: (i64, i64),
+    radius: i64,
+}
+
+impl Shadowcaster<'_> {
+    fn is_opaque(
+        &self,
+        x: i64,
+        y: i64,
+    ) -> bool {
+        !self.opacity.in_bounds(x, y)
+            || self.opacity.get(x as u32, y as u32) == Some(&true)
+    }
+
+    /// Scan one octant row by row from `row`, between the `start` and
+    /// `end` slopes, recursing around opaque tiles.
+    fn cast(
+        &mut self,
+        row: i64,
+        mut start: f32,
+        end: f32,
+        [xx, xy, yx, yy]: [i64; 4],
+    ) {
+        if start < end {
+            return;
+        }
+        let mut next_start = start;
+        for distance in row..=self.radius {
+            let dy = -distance;
+            let mut blocked = false;
+            for dx in -distance..=0 {
+                let x = self.origin.0 + dx * xx + dy * xy;
+                let y = self.origin.1 + dx * yx + dy * yy;
+                let left_slope = (dx as f32 - 0.5) / (dy as f32 + 0.5);
+                let right_slope = (dx as f32 + 0.5) / (dy as f32 - 0.5);
+                if start < right_slope {
+                    continue;
+                }
+                if end > left_slope {
+                    break;
+                }
+
+                if dx * dx + dy * dy <= self.radius * self.radius
+                    && self.opacity.in_bounds(x, y)
+                {
+                    self.visible.set(x as u32, y as u32, true);
+                }
+
+                let opaque = self.is_opaque(x, y);
+                if blocked {
+                    if opaque {
+                        next_start = right_slope;
+                    } else {
+                        blocked = false;
+                        start = next_start;
+                    }
+                } else if opaque && distance < self.radius {
+                    blocked = true;
+                    self.cast(
+                        distance + 1,
+                        start,
+                        left_slope,
+                        [xx, xy, yx, yy],
+                    );
+                    next_start = right_slope;
+                }
+            }
+            if blocked {
+                break;
+            }
+        }
+    }
+}
+
+/// Tiles on the Bresenham line from `from` to `to`, both included.
+pub fn bresenham_line(
+    from: (i64, i64),
+    to: (i64, i64),
+) -> Vec<(i64, i64)> {
+    let dx = (to.0 - from.0).abs();
+    let dy = -(to.1 - from.1).abs();
+    let step_x = (to.0 - from.0).signum();
+    let step_y = (to.1 - from.1).signum();
+    let mut error = dx + dy;
+    let (mut x, mut y) = from;
+
+    let mut tiles = Vec::with_capacity((dx - dy) as usize + 1);
+    loop {
+        tiles.push((x, y));
+        if (x, y) == to {
+            return tiles;
+        }
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if doubled <= dx {
+            error += dx;
+            y += step_y;
+        }
+    }
+}
+
+/// Every tile the segment from `from` to `to` passes through, in order,
+/// using a DDA grid traversal. Positions are in tile units, so (1.5, 0.5)
+/// is the center of tile (1, 0).
+pub fn dda_line(
+    from: Vec2,
+    to: Vec2,
+) -> Vec<(i64, i64)> {
+    let start = (from.x.floor() as i64, from.y.floor() as i64);
+    let end = (to.x.floor() as i64, to.y.floor() as i64);
+    let steps = (end.0 - start.0).abs() + (end.1 - start.1).abs();
+    GridRay::new(from, (to - from).normalize_or_zero(), f32::INFINITY)
+        .take(steps as usize + 1)
+        .map(|step| step.tile)
+        .collect()
+}
+
+/// Whether no opaque tile lies strictly between the tiles `from` and
+/// `to` on their Bresenham line. The end tiles themselves may be opaque,
+/// so a wall can be seen.
+pub fn line_of_sight(
+    opacity: &Grid<bool>,
+    from: (u32, u32),
+    to: (u32, u32),
+) -> bool {
+    let line = bresenham_line(
+        (from.0 as i64, from.1 as i64),
+        (to.0 as i64, to.1 as i64),
+    );
+    is_clear(opacity, &line)
+}
+
+/// Like [`line_of_sight`] between two points in tile units, checking
+/// every tile the segment touches.
+pub fn line_of_sight_points(
+    opacity: &Grid<bool>,
+    from: Vec2,
+    to: Vec2,
+) -> bool {
+    is_clear(opacity, &dda_line(from, to))
+}
+
+fn is_clear(
+    opacity: &Grid<bool>,
+    line: &[(i64, i64)],
+) -> bool {
+    if line.len() <= 2 {
+        return true;
+    }
+    line[1..line.len() - 1].iter().all(|&(x, y)| {
+        opacity.in_bounds(x, y)
+            && opacity.get(x as u32, y as u32) == Some(&false)
+    })
+}
+
+/// How much of a tile the player knows about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visibility {
+    /// Never seen.
+    Hidden,
+    /// Seen before but not currently in view.
+    Explored,
+    Visible,
+}
+
+/// Fog of war state per tile, for `TileMapRenderer::render_with_visibility`.
+///
+/// Feed it a fresh field of view every time the viewer moves. Explored
+/// tiles stay known and are drawn at `explored_brightness`; hidden tiles
+/// are not drawn at all.
+#[derive(Debug, Clone)]
+pub struct VisibilityMap {
+    visible: Grid<bool>,
+    explored: Grid<bool>,
+    explored_brightness: f32,
+}
+
+impl VisibilityMap {
+    pub fn new(
+        width: u32,
+        height: u32,
+    ) -> Self {
+        Self {
+            visible: Grid::new(width, height, false),
+            explored: Grid::new(width, height, false),
+            explored_brightness: 0.4,
+        }
+    }
+
+    pub fn with_explored_brightness(
+        mut self,
+        brightness: f32,
+    ) -> Self {
+        self.explored_brightness = brightness.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn explored_brightness(&self) -> f32 {
+        self.explored_brightness
+    }
+
+    /// Make `fov` the visible set and add it to the explored tiles.
+    pub fn update(
+        &mut self,
+        fov: &Grid<bool>,
+    ) {
+        for y in 0..self.visible.height() {
+            for x in 0..self.visible.width() {
+                let seen = fov.get(x, y) == Some(&true);
+                self.visible.set(x, y, seen);
+                if seen {
+                    self.explored.set(x, y, true);
+                }
+            }
+        }
+    }
+
+    /// Mark every tile explored, e.g. after picking up a map.
+    pub fn reveal_all(&mut self) {
+        self.explored =
+            Grid::new(self.explored.width(), self.explored.height(), true);
+    }
+
+    /// Forget everything seen so far.
+    pub fn reset(&mut self) {
+        let (width, height) = (self.visible.width(), self.visible.height());
+        self.visible = Grid::new(width, height, false);
+        self.explored = Grid::new(width, height, false);
+    }
+
+    pub fn visibility(
+        &self,
+        x: u32,
+        y: u32,
+    ) -> Visibility {
+        if self.visible.get(x, y) == Some(&true) {
+            Visibility::Visible
+        } else if self.explored.get(x, y) == Some(&true) {
+            Visibility::Explored
+        } else {
+            Visibility::Hidden
+        }
+    }
+
+    pub fn is_visible(
+        &self,
+        x: u32,
+        y: u32,
+    ) -> bool {
+        self.visibility(x, y) == Visibility::Visible
+    }
+
+    /// Brightness to draw the tile with, `None` if it stays hidden.
+    pub fn brightness(
+        &self,
+        x: u32,
+        y: u32,
+    ) -> Option<f32> {
+        match self.visibility(x, y) {
+            Visibility::Visible => Some(1.0),
+            Visibility::Explored => Some(self.explored_brightness),
+            Visibility::Hidden => None,
+        }
+    }
+}
+
+impl TileMap {
+    /// Opacity of every tile: `true` where any layer has an opaque tile,
+    /// see `TileProperties::is_opaque`.
+    pub fn opacity(&self) -> Grid<bool> {
+        let mut opacity = Grid::new(self.width, self.height, false);
+        for tile in self.tiles() {
+            if tile.properties.is_opaque() {
+                opacity.set(tile.x, tile.y, true);
+            }
+        }
+        opacity
+    }
+
+    /// Field of view from a tile. Builds the opacity grid on every call;
+    /// for many queries on an unchanged map, keep [`TileMap::opacity`] and
+    /// call [`field_of_view`] directly.
+    pub fn field_of_view(
+        &self,
+        origin: (u32, u32),
+        radius: u32,
+    ) -> Grid<bool> {
+        field_of_view(&self.opacity(), origin, radius)
+    }
+
+    /// Line of sight between two world positions.
+    ///
+    /// Always `false` on staggered and hexagonal maps: their offset rows
+    /// do not form a straight grid to trace the segment through. Use
+    /// [`line_of_sight`] between tiles there.
+    pub fn line_of_sight(
+        &self,
+        from: Vec2,
+        to: Vec2,
+    ) -> bool {
+        let Some((to_grid, offset)) =
+            self.orientation.grid_transform(self.world_tile_size())
+        else {
+            return false;
+        };
+        line_of_sight_points(
+            &self.opacity(),
+            to_grid * from + offset,
+            to_grid * to + offset,
+        )
+    }
+}